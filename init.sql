@@ -49,4 +49,114 @@ ALTER TABLE tasks ADD COLUMN env_vars TEXT NOT NULL DEFAULT '{}';
 -- 添加 sort_order 列并按 id 初始化顺序
 ALTER TABLE tasks ADD COLUMN sort_order INTEGER NOT NULL DEFAULT 0;
 UPDATE tasks SET sort_order = id WHERE sort_order = 0;
-CREATE INDEX IF NOT EXISTS idx_tasks_sort_order ON tasks(sort_order);
\ No newline at end of file
+CREATE INDEX IF NOT EXISTS idx_tasks_sort_order ON tasks(sort_order);
+-- 添加标准输出/标准错误的重定向选项, 存储 JSON 字符串, 例如: {"append": true, "timestamp": false, "rotation": null}
+ALTER TABLE tasks ADD COLUMN stdout_options TEXT NOT NULL DEFAULT '{}';
+ALTER TABLE tasks ADD COLUMN stderr_options TEXT NOT NULL DEFAULT '{}';
+
+-- 添加 merge_stderr 列, 将标准错误合并到标准输出
+ALTER TABLE tasks ADD COLUMN merge_stderr BOOLEAN NOT NULL DEFAULT 0;
+
+-- 创建任务运行记录表, 每次运行对应一条记录
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    task_id INTEGER NOT NULL,
+    -- RFC 3339 时间, 例如: "2026-02-15T23:00:00+08:00"
+    started_at TEXT NOT NULL,
+    ended_at TEXT,
+    exit_code INTEGER,
+    -- 自动捕获的输出文件路径, 任务设置了 stdout/stderr 时为 NULL
+    output TEXT
+);
+CREATE INDEX IF NOT EXISTS idx_runs_task_id ON runs(task_id);
+
+-- 添加 pty 列, 存储伪终端选项的 JSON 字符串, 例如: {"rows": 24, "cols": 80}, 为空表示不使用伪终端
+ALTER TABLE tasks ADD COLUMN pty TEXT;
+
+-- 添加 recording 列, 伪终端会话的 asciicast 录制文件路径, 非伪终端模式为 NULL
+ALTER TABLE runs ADD COLUMN recording TEXT;
+
+-- 添加 stdin_source 列, 标准输入来源的 JSON 字符串, 例如: {"tag": "Inline", "content": "yes"}
+ALTER TABLE tasks ADD COLUMN stdin_source TEXT NOT NULL DEFAULT '{"tag":"File"}';
+
+-- 添加 limits 列, 资源限制的 JSON 字符串, 例如: {"open_files": 1024, "cpu_seconds": 3600}
+ALTER TABLE tasks ADD COLUMN limits TEXT NOT NULL DEFAULT '{}';
+-- 添加 limit_violation 列, 进程因超出资源限制而结束时记录超出的限制, 例如: "CpuTime"
+ALTER TABLE runs ADD COLUMN limit_violation TEXT;
+
+-- 添加 cgroup 列, cgroup 资源限制的 JSON 字符串, 例如: {"memory_max": 536870912, "pids_max": 64}
+ALTER TABLE tasks ADD COLUMN cgroup TEXT NOT NULL DEFAULT '{}';
+-- 添加从运行的 cgroup 统计的 CPU 时间 (微秒) 和内存使用峰值 (字节), 不使用 cgroup 时为 NULL
+ALTER TABLE runs ADD COLUMN cpu_usage_usec INTEGER;
+ALTER TABLE runs ADD COLUMN memory_peak INTEGER;
+
+-- 添加 priority 列, 调度优先级和 CPU 亲和性的 JSON 字符串, 例如: {"nice": 19, "io_class": {"tag": "Idle"}, "cpu_affinity": [4, 5, 6, 7]}
+ALTER TABLE tasks ADD COLUMN priority TEXT NOT NULL DEFAULT '{}';
+
+-- 添加 identity 列, 运行任务的用户, 组和 umask 的 JSON 字符串, 例如: {"user": "builder", "groups": ["docker"], "umask": 18}
+ALTER TABLE tasks ADD COLUMN identity TEXT NOT NULL DEFAULT '{}';
+
+-- 添加 sandbox 列, 沙箱选项的 JSON 字符串, 为 NULL 时不使用沙箱, 例如: {"writable_paths": ["/var/lib/scraper"]}
+ALTER TABLE tasks ADD COLUMN sandbox TEXT;
+
+-- 添加 concurrency 列, 任务已经在运行时再次被触发的处理方式, 例如: {"tag": "Parallel", "content": 2}
+ALTER TABLE tasks ADD COLUMN concurrency TEXT NOT NULL DEFAULT '{"tag":"Skip"}';
+-- 添加运行期间被跳过的触发次数
+ALTER TABLE runs ADD COLUMN skipped_triggers INTEGER NOT NULL DEFAULT 0;
+
+-- 添加 restart 列, KeepAlive 和 UntilSucceed 任务的重启策略的 JSON 字符串, 为 '{}' 时使用默认值, 例如: {"initial_delay_ms": 1000, "multiplier": 2.0, "max_delay_ms": 60000, "max_attempts": 10}
+ALTER TABLE tasks ADD COLUMN restart TEXT NOT NULL DEFAULT '{}';
+
+-- 添加 suspension 列, suspension 条件的 JSON 字符串, 为 NULL 时使用应用配置中的默认条件, 例如: {"timespan_ms": 60000, "failures": 5, "cooldown_ms": 600000}
+ALTER TABLE tasks ADD COLUMN suspension TEXT;
+-- 添加 suspended 列, 任务被暂停的时间和原因的 JSON 字符串, 为 NULL 时没有被暂停
+ALTER TABLE tasks ADD COLUMN suspended TEXT;
+
+-- 添加 success 列, 运行成功条件的 JSON 字符串, 为 '{}' 时只有退出码 0 视为成功, 例如: {"exit_codes": [0, 24], "signals": [15], "failure_pattern": "ERROR"}
+ALTER TABLE tasks ADD COLUMN success TEXT NOT NULL DEFAULT '{}';
+-- 添加上一次运行是否成功
+ALTER TABLE tasks ADD COLUMN last_succeeded BOOLEAN;
+-- 添加运行是否成功, 为 NULL 时仍在运行
+ALTER TABLE runs ADD COLUMN succeeded BOOLEAN;
+
+-- 添加 health_check 列, KeepAlive 任务的健康检查选项的 JSON 字符串, 为 NULL 时不检查, 例如: {"probe": {"tag": "Http", "content": {"port": 8080, "path": "/health", "status": 200}}, "interval_ms": 10000}
+ALTER TABLE tasks ADD COLUMN health_check TEXT;
+
+-- 添加 notify 列, sd_notify 选项的 JSON 字符串, 为 NULL 时不提供 NOTIFY_SOCKET, 例如: {"watchdog_ms": 30000}
+ALTER TABLE tasks ADD COLUMN notify TEXT;
+
+-- 添加 hooks 列, 启动之前和退出之后运行的 hook 的 JSON 字符串, 例如: {"pre_start": {"program": "/usr/bin/mount", "args": ["/mnt/share"]}, "post_stop": {"program": "/usr/bin/umount", "args": ["/mnt/share"]}}
+ALTER TABLE tasks ADD COLUMN hooks TEXT NOT NULL DEFAULT '{}';
+
+-- 添加 on_exit 列, 管理器退出时如何处理正在运行的进程, "Kill" 结束进程, "Detach" 保持运行并在下一次启动时接管
+ALTER TABLE tasks ADD COLUMN on_exit TEXT NOT NULL DEFAULT '"Kill"';
+-- 添加运行的进程 ID, 进程启动时间和系统 boot id, 用于在管理器重启之后接管进程
+ALTER TABLE runs ADD COLUMN pid INTEGER;
+ALTER TABLE runs ADD COLUMN pid_start_time INTEGER;
+ALTER TABLE runs ADD COLUMN boot_id TEXT;
+
+-- 添加上一次运行的结束方式和运行的结束方式, JSON 字符串, 例如: {"tag": "Exited", "content": {"code": 1}}, {"tag": "Signaled", "content": {"signal": 9, "core_dumped": false}}, {"tag": "Stopped"}
+ALTER TABLE tasks ADD COLUMN last_outcome TEXT;
+ALTER TABLE runs ADD COLUMN outcome TEXT;
+
+-- 创建命名环境表, 任务通过名称引用, 修改环境之后所有使用它的任务在下一次启动时生效
+CREATE TABLE IF NOT EXISTS environments (
+    id INTEGER PRIMARY KEY AUTOINCREMENT NOT NULL,
+    name TEXT NOT NULL UNIQUE,
+    -- 环境变量的 JSON 字符串, 例如: {"HTTPS_PROXY": "http://proxy:3128"}
+    env_vars TEXT NOT NULL DEFAULT '{}',
+    -- 添加到 PATH 前面的目录的 JSON 字符串, 例如: ["/opt/venvs/scraper/bin"]
+    path_prefixes TEXT NOT NULL DEFAULT '[]',
+    -- 激活脚本, 每次启动任务之前由 /bin/sh 执行, 例如: ". /opt/venvs/scraper/bin/activate"
+    activation_script TEXT
+);
+-- 添加 environment 列, 任务引用的环境名称, 为 NULL 时不使用
+ALTER TABLE tasks ADD COLUMN environment TEXT;
+
+-- 添加 replicas 列, 同时运行的副本数量, 每个副本启动时参数, 环境变量和路径中的 ${INSTANCE} 展开为副本编号 (从 0 开始)
+ALTER TABLE tasks ADD COLUMN replicas INTEGER NOT NULL DEFAULT 1;
+-- 添加运行所属副本的编号, 任务不使用副本时为 NULL
+ALTER TABLE runs ADD COLUMN instance INTEGER;
+
+-- 添加 progress_fd 列, 专用的进度报告文件描述符, 任务向其中写入 "::bgm progress 42/100" 等报告行, 为 NULL 时只从输出中读取报告
+ALTER TABLE tasks ADD COLUMN progress_fd INTEGER;
@@ -2,4 +2,7 @@
 
 pub mod prelude;
 
+pub mod environments;
+pub mod replica_states;
+pub mod runs;
 pub mod tasks;
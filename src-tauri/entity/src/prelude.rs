@@ -1,3 +1,6 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 2.0
 
+pub use super::environments::Entity as Environments;
+pub use super::replica_states::Entity as ReplicaStates;
+pub use super::runs::Entity as Runs;
 pub use super::tasks::Entity as Tasks;
@@ -34,6 +34,50 @@ pub struct Model {
     pub working_dir: Option<String>,
     #[sea_orm(column_type = "Text")]
     pub env_vars: String,
+    #[sea_orm(column_type = "Text")]
+    pub stdout_options: String,
+    #[sea_orm(column_type = "Text")]
+    pub stderr_options: String,
+    pub merge_stderr: bool,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub pty: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub stdin_source: String,
+    #[sea_orm(column_type = "Text")]
+    pub limits: String,
+    #[sea_orm(column_type = "Text")]
+    pub cgroup: String,
+    #[sea_orm(column_type = "Text")]
+    pub priority: String,
+    #[sea_orm(column_type = "Text")]
+    pub identity: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub sandbox: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub concurrency: String,
+    #[sea_orm(column_type = "Text")]
+    pub restart: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub suspension: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub suspended: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub success: String,
+    pub last_succeeded: Option<bool>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub health_check: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub notify: Option<String>,
+    #[sea_orm(column_type = "Text")]
+    pub hooks: String,
+    #[sea_orm(column_type = "Text")]
+    pub on_exit: String,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub last_outcome: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub environment: Option<String>,
+    pub replicas: i64,
+    pub progress_fd: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
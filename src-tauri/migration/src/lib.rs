@@ -5,6 +5,29 @@ mod m20260218_042706_col_no_console;
 mod m20260218_141207_col_working_dir;
 mod m20260222_084716_col_env_vars;
 mod m20260315_000001_col_sort_order;
+mod m20261018_000001_col_output_options;
+mod m20261018_000002_create_runs;
+mod m20261018_000003_col_pty;
+mod m20261018_000004_col_run_recording;
+mod m20261018_000005_col_stdin_source;
+mod m20261018_000006_col_limits;
+mod m20261018_000007_col_cgroup;
+mod m20261018_000008_col_priority;
+mod m20261018_000009_col_identity;
+mod m20261018_000010_col_sandbox;
+mod m20261018_000011_col_concurrency;
+mod m20261018_000012_col_restart;
+mod m20261018_000013_col_suspension;
+mod m20261018_000014_col_success;
+mod m20261018_000015_col_health_check;
+mod m20261018_000016_col_notify;
+mod m20261018_000017_col_hooks;
+mod m20261018_000018_col_on_exit;
+mod m20261018_000019_col_outcome;
+mod m20261018_000020_create_environments;
+mod m20261018_000021_col_replicas;
+mod m20261018_000022_col_progress_fd;
+mod m20261018_000023_create_replica_states;
 
 pub struct Migrator;
 
@@ -17,6 +40,29 @@ impl MigratorTrait for Migrator {
             Box::new(m20260218_141207_col_working_dir::Migration),
             Box::new(m20260222_084716_col_env_vars::Migration),
             Box::new(m20260315_000001_col_sort_order::Migration),
+            Box::new(m20261018_000001_col_output_options::Migration),
+            Box::new(m20261018_000002_create_runs::Migration),
+            Box::new(m20261018_000003_col_pty::Migration),
+            Box::new(m20261018_000004_col_run_recording::Migration),
+            Box::new(m20261018_000005_col_stdin_source::Migration),
+            Box::new(m20261018_000006_col_limits::Migration),
+            Box::new(m20261018_000007_col_cgroup::Migration),
+            Box::new(m20261018_000008_col_priority::Migration),
+            Box::new(m20261018_000009_col_identity::Migration),
+            Box::new(m20261018_000010_col_sandbox::Migration),
+            Box::new(m20261018_000011_col_concurrency::Migration),
+            Box::new(m20261018_000012_col_restart::Migration),
+            Box::new(m20261018_000013_col_suspension::Migration),
+            Box::new(m20261018_000014_col_success::Migration),
+            Box::new(m20261018_000015_col_health_check::Migration),
+            Box::new(m20261018_000016_col_notify::Migration),
+            Box::new(m20261018_000017_col_hooks::Migration),
+            Box::new(m20261018_000018_col_on_exit::Migration),
+            Box::new(m20261018_000019_col_outcome::Migration),
+            Box::new(m20261018_000020_create_environments::Migration),
+            Box::new(m20261018_000021_col_replicas::Migration),
+            Box::new(m20261018_000022_col_progress_fd::Migration),
+            Box::new(m20261018_000023_create_replica_states::Migration),
         ]
     }
 }
@@ -0,0 +1,64 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite 的 ALTER TABLE 一次只能添加一列
+        for col in [Tasks::StdoutOptions, Tasks::StderrOptions] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Tasks::Table)
+                        .add_column(
+                            // 存储输出选项的 JSON 字符串，非空，默认值为 '{}'
+                            ColumnDef::new(col).text().not_null().default("{}"),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tasks::Table)
+                    .add_column(
+                        ColumnDef::new(Tasks::MergeStderr)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for col in [
+            Tasks::StdoutOptions,
+            Tasks::StderrOptions,
+            Tasks::MergeStderr,
+        ] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Tasks::Table)
+                        .drop_column(col)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tasks {
+    Table,
+    StdoutOptions,
+    StderrOptions,
+    MergeStderr,
+}
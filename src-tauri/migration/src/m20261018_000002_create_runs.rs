@@ -0,0 +1,60 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 1. 创建任务运行记录表
+        manager
+            .create_table(
+                Table::create()
+                    .table(Runs::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Runs::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Runs::TaskId).integer().not_null())
+                    .col(ColumnDef::new(Runs::StartedAt).string().not_null())
+                    .col(ColumnDef::new(Runs::EndedAt).string())
+                    .col(ColumnDef::new(Runs::ExitCode).integer())
+                    .col(ColumnDef::new(Runs::Output).string())
+                    .to_owned(),
+            )
+            .await?;
+
+        // 2. 创建索引: idx_runs_task_id
+        manager
+            .create_index(
+                Index::create()
+                    .if_not_exists()
+                    .name("idx_runs_task_id")
+                    .table(Runs::Table)
+                    .col(Runs::TaskId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Runs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Runs {
+    Table,
+    Id,
+    TaskId,
+    StartedAt,
+    EndedAt,
+    ExitCode,
+    Output,
+}
@@ -0,0 +1,75 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tasks::Table)
+                    .add_column(
+                        // 存储 cgroup 资源限制的 JSON 字符串，非空，默认值为 '{}'
+                        ColumnDef::new(Tasks::Cgroup)
+                            .text()
+                            .not_null()
+                            .default("{}"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // SQLite 的 ALTER TABLE 一次只能添加一列
+        for col in [Runs::CpuUsageUsec, Runs::MemoryPeak] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Runs::Table)
+                        .add_column(
+                            // 从运行的 cgroup 统计的资源使用，不使用 cgroup 时为空
+                            ColumnDef::new(col).big_integer(),
+                        )
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for col in [Runs::CpuUsageUsec, Runs::MemoryPeak] {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(Runs::Table)
+                        .drop_column(col)
+                        .to_owned(),
+                )
+                .await?;
+        }
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tasks::Table)
+                    .drop_column(Tasks::Cgroup)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tasks {
+    Table,
+    Cgroup,
+}
+
+#[derive(DeriveIden)]
+enum Runs {
+    Table,
+    CpuUsageUsec,
+    MemoryPeak,
+}
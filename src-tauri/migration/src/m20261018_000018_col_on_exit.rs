@@ -0,0 +1,74 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tasks::Table)
+                    .add_column(
+                        // 存储管理器退出时如何处理进程的 JSON 字符串，非空，默认结束进程
+                        ColumnDef::new(Tasks::OnExit)
+                            .text()
+                            .not_null()
+                            .default(r#""Kill""#),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Runs::Table)
+                    // 进程 ID，启动失败时为空
+                    .add_column(ColumnDef::new(Runs::Pid).big_integer())
+                    // 进程的启动时间，用于确认进程 ID 没有被复用
+                    .add_column(ColumnDef::new(Runs::PidStartTime).big_integer())
+                    // 进程启动时系统的 boot id
+                    .add_column(ColumnDef::new(Runs::BootId).text())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Runs::Table)
+                    .drop_column(Runs::Pid)
+                    .drop_column(Runs::PidStartTime)
+                    .drop_column(Runs::BootId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tasks::Table)
+                    .drop_column(Tasks::OnExit)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tasks {
+    Table,
+    OnExit,
+}
+
+#[derive(DeriveIden)]
+enum Runs {
+    Table,
+    Pid,
+    PidStartTime,
+    BootId,
+}
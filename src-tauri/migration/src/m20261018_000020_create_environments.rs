@@ -0,0 +1,89 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 1. 创建命名环境表，任务通过名称引用
+        manager
+            .create_table(
+                Table::create()
+                    .table(Environments::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Environments::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Environments::Name)
+                            .text()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    // 环境变量的 JSON 字符串
+                    .col(
+                        ColumnDef::new(Environments::EnvVars)
+                            .text()
+                            .not_null()
+                            .default("{}"),
+                    )
+                    // 添加到 PATH 前面的目录的 JSON 字符串
+                    .col(
+                        ColumnDef::new(Environments::PathPrefixes)
+                            .text()
+                            .not_null()
+                            .default("[]"),
+                    )
+                    // 激活脚本，为空时不运行
+                    .col(ColumnDef::new(Environments::ActivationScript).text())
+                    .to_owned(),
+            )
+            .await?;
+
+        // 2. 任务引用的环境名称，为空时不使用
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tasks::Table)
+                    .add_column(ColumnDef::new(Tasks::Environment).text())
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tasks::Table)
+                    .drop_column(Tasks::Environment)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Environments::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Environments {
+    Table,
+    Id,
+    Name,
+    EnvVars,
+    PathPrefixes,
+    ActivationScript,
+}
+
+#[derive(DeriveIden)]
+enum Tasks {
+    Table,
+    Environment,
+}
@@ -0,0 +1,45 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // 每个副本的状态各占一行，副本同时更新时互不覆盖
+        manager
+            .create_table(
+                Table::create()
+                    .table(ReplicaStates::Table)
+                    .if_not_exists()
+                    .col(ColumnDef::new(ReplicaStates::TaskId).integer().not_null())
+                    .col(ColumnDef::new(ReplicaStates::Instance).integer().not_null())
+                    // 副本上一次运行的结束方式的 JSON 字符串，为空表示还没有结束过
+                    .col(ColumnDef::new(ReplicaStates::LastOutcome).text())
+                    // 副本的 suspension 状态的 JSON 字符串，为空表示没有被暂停
+                    .col(ColumnDef::new(ReplicaStates::Suspended).text())
+                    .primary_key(
+                        Index::create()
+                            .col(ReplicaStates::TaskId)
+                            .col(ReplicaStates::Instance),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(ReplicaStates::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum ReplicaStates {
+    Table,
+    TaskId,
+    Instance,
+    LastOutcome,
+    Suspended,
+}
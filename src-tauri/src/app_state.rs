@@ -1,17 +1,29 @@
+use std::collections::HashMap;
+
 use migration::MigratorTrait;
 use sea_orm::{Database, DatabaseConnection};
-use tokio::sync::{RwLock, RwLockReadGuard};
-use tracing::warn;
+use tokio::{
+    sync::{Mutex, RwLock, RwLockReadGuard, broadcast::error::RecvError, watch},
+    task::JoinHandle,
+};
+use tracing::{info, warn};
 
 use crate::{
     config::{AppConfig, config_dir, db_path},
+    output::OutputLine,
+    progress::ProgressEvent,
     schedule::Scheduler,
+    shell_env::{self, BaseEnv},
 };
 
 pub(crate) struct AppState {
     config: RwLock<AppConfig>,
     db: RwLock<DatabaseConnection>,
     scheduler: Scheduler,
+    /// 登录 shell 的环境变量, 第一次捕获完成之前为空.
+    base_env: watch::Sender<Option<BaseEnv>>,
+    /// 任务 id -> 转发该任务实时输出的协程.
+    output_subscriptions: Mutex<HashMap<i64, JoinHandle<()>>>,
 }
 
 impl AppState {
@@ -41,11 +53,34 @@ impl AppState {
     pub(crate) async fn build() -> crate::Result<Self> {
         let db = Self::open_database().await?;
         let config = AppConfig::load_from_file(config_dir()?.join("config.toml")).await?;
-        let scheduler = Scheduler::bind(db.clone()).await;
+        let (base_env, base_env_rx) = watch::channel(None);
+        let scheduler = Scheduler::bind(db.clone(), config.clone(), base_env_rx).await;
+        // 登录 shell 的配置文件可能很慢, 在后台捕获, 任务的第一次运行等待捕获完成.
+        // 失败时使用管理器自己的环境变量
+        let capture_tx = base_env.clone();
+        let capture_config = config.clone();
+        tokio::spawn(async move {
+            let env = Self::capture_base_env(&capture_config)
+                .await
+                .unwrap_or_else(|e| {
+                    warn!("failed to capture login shell environment: {e:?}");
+                    BaseEnv::default()
+                });
+            // 捕获期间已经手动刷新时保留刷新的结果
+            capture_tx.send_if_modified(|current| {
+                if current.is_some() {
+                    return false;
+                }
+                *current = Some(env);
+                true
+            });
+        });
         Ok(AppState {
             config: RwLock::new(config),
             db: RwLock::new(db),
             scheduler,
+            base_env,
+            output_subscriptions: Mutex::new(HashMap::new()),
         })
     }
 
@@ -75,8 +110,78 @@ impl AppState {
 
     pub(crate) async fn update_config(&self, config: AppConfig) -> crate::Result<()> {
         let mut cfg = self.config.write().await;
+        let login_shell_env = cfg.login_shell_env();
         cfg.update(config);
         cfg.save().await?;
+        self.scheduler.refresh_config(cfg.clone()).await?;
+        if cfg.login_shell_env() != login_shell_env {
+            drop(cfg);
+            self.refresh_base_env().await?;
+        }
         Ok(())
     }
+
+    /// 重新捕获登录 shell 的环境变量, 返回变量的数量, 没有开启时清空.
+    pub(crate) async fn refresh_base_env(&self) -> crate::Result<usize> {
+        let config = self.get_config().await;
+        let base_env = Self::capture_base_env(&config).await?;
+        let len = base_env.len();
+        // 之后启动的进程生效
+        self.base_env.send_replace(Some(base_env));
+        Ok(len)
+    }
+
+    async fn capture_base_env(config: &AppConfig) -> crate::Result<BaseEnv> {
+        if !config.login_shell_env() {
+            return Ok(BaseEnv::default());
+        }
+        let env = shell_env::capture().await?;
+        info!("captured {} variables from login shell", env.len());
+        Ok(BaseEnv::new(env))
+    }
+
+    /// 订阅任务的实时输出, 每一行输出都会调用 `on_line`, 重复订阅同一个任务会替换之前的订阅.
+    pub(crate) async fn subscribe_output(
+        &self,
+        id: i64,
+        on_line: impl Fn(OutputLine) + Send + 'static,
+    ) {
+        let mut rx = self.scheduler.subscribe_output();
+        let handle = tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(line) if line.task_id == id => on_line(line),
+                    Ok(_) => (),
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("output subscription of task {id} lagged {n} lines")
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+        if let Some(old) = self.output_subscriptions.lock().await.insert(id, handle) {
+            old.abort();
+        }
+    }
+
+    /// 订阅所有任务通过 `::bgm` 报告的进度, 每一次更新都会调用 `on_progress`, 在应用启动时调用一次.
+    pub(crate) fn subscribe_progress(&self, on_progress: impl Fn(ProgressEvent) + Send + 'static) {
+        let mut rx = self.scheduler.subscribe_progress();
+        tokio::spawn(async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => on_progress(event),
+                    Err(RecvError::Lagged(n)) => warn!("progress subscription lagged {n} reports"),
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+
+    /// 取消订阅任务的实时输出.
+    pub(crate) async fn unsubscribe_output(&self, id: i64) {
+        if let Some(handle) = self.output_subscriptions.lock().await.remove(&id) {
+            handle.abort();
+        }
+    }
 }
@@ -0,0 +1,176 @@
+//! 将伪终端会话录制为 asciicast v2 (`.cast`) 文件.
+//!
+//! 格式说明: <https://docs.asciinema.org/manual/asciicast/v2/>
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use tokio::{
+    io::{AsyncWriteExt, BufWriter},
+    time::Instant,
+};
+
+use crate::pty::PtyOptions;
+
+#[derive(Serialize)]
+struct Header<'a> {
+    version: u8,
+    width: u16,
+    height: u16,
+    timestamp: i64,
+    title: &'a str,
+    env: Env<'a>,
+}
+
+#[derive(Serialize)]
+struct Env<'a> {
+    #[serde(rename = "TERM")]
+    term: &'a str,
+}
+
+/// asciicast 录制器, 记录伪终端的每一段输出及其相对于开始录制的时间.
+pub(crate) struct CastRecorder {
+    path: PathBuf,
+    writer: BufWriter<tokio::fs::File>,
+    start: Instant,
+    /// 还没有组成完整 UTF-8 字符的字节.
+    pending: Vec<u8>,
+}
+
+impl CastRecorder {
+    /// 创建录制文件并写入文件头.
+    pub(crate) async fn create(
+        path: impl Into<PathBuf>,
+        pty: &PtyOptions,
+        title: &str,
+        term: &str,
+    ) -> crate::Result<Self> {
+        let path = path.into();
+        let file = tokio::fs::File::create(&path).await.map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Io,
+                format!("failed to create recording file: {}", path.display()),
+                Box::new(e),
+            )
+        })?;
+        let header = serde_json::to_string(&Header {
+            version: 2,
+            width: pty.cols,
+            height: pty.rows,
+            timestamp: chrono::Local::now().timestamp(),
+            title,
+            env: Env { term },
+        })
+        .map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::JsonSer,
+                "failed to serialize recording header",
+                Box::new(e),
+            )
+        })?;
+        let mut recorder = Self {
+            path,
+            writer: BufWriter::new(file),
+            start: Instant::now(),
+            pending: Vec::new(),
+        };
+        recorder.write_line(&header).await?;
+        Ok(recorder)
+    }
+
+    async fn write_line(&mut self, line: &str) -> crate::Result<()> {
+        let failed_to_write = |e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Io,
+                format!("failed to write recording file: {}", self.path.display()),
+                Box::new(e),
+            )
+        };
+        self.writer
+            .write_all(line.as_bytes())
+            .await
+            .map_err(failed_to_write)?;
+        self.writer.write_all(b"\n").await.map_err(failed_to_write)
+    }
+
+    /// 记录一段终端输出.
+    pub(crate) async fn output(&mut self, data: &[u8]) -> crate::Result<()> {
+        self.pending.extend_from_slice(data);
+        let text = take_utf8(&mut self.pending, false);
+        self.event(text).await
+    }
+
+    async fn event(&mut self, text: String) -> crate::Result<()> {
+        if text.is_empty() {
+            return Ok(());
+        }
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let line = serde_json::to_string(&(elapsed, "o", text)).map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::JsonSer,
+                "failed to serialize recording event",
+                Box::new(e),
+            )
+        })?;
+        self.write_line(&line).await
+    }
+
+    /// 写入剩余的输出并刷新文件.
+    pub(crate) async fn finish(mut self) -> crate::Result<()> {
+        let text = take_utf8(&mut self.pending, true);
+        self.event(text).await?;
+        self.writer.flush().await.map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Io,
+                format!("failed to write recording file: {}", self.path.display()),
+                Box::new(e),
+            )
+        })
+    }
+}
+
+/// 取出 `pending` 开头的 UTF-8 文本, 无效的字节会被替换.
+///
+/// 末尾不完整的字符保留在 `pending` 中等待后续输出, 除非 `flush` 为 true.
+fn take_utf8(pending: &mut Vec<u8>, flush: bool) -> String {
+    let mut text = String::new();
+    loop {
+        match std::str::from_utf8(pending) {
+            Ok(s) => {
+                text.push_str(s);
+                pending.clear();
+                return text;
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                match e.error_len() {
+                    Some(invalid) => {
+                        let chunk: Vec<u8> = pending.drain(..valid + invalid).collect();
+                        text.push_str(&String::from_utf8_lossy(&chunk));
+                    }
+                    None if flush => {
+                        text.push_str(&String::from_utf8_lossy(pending));
+                        pending.clear();
+                        return text;
+                    }
+                    None => {
+                        let chunk: Vec<u8> = pending.drain(..valid).collect();
+                        text.push_str(&String::from_utf8_lossy(&chunk));
+                        return text;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// 读取录制文件的内容.
+pub(crate) async fn read_recording(path: &Path) -> crate::Result<String> {
+    tokio::fs::read_to_string(path).await.map_err(|e| {
+        crate::Error::with_source(
+            crate::ErrorKind::Io,
+            format!("failed to read recording file: {}", path.display()),
+            Box::new(e),
+        )
+    })
+}
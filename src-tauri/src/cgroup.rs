@@ -0,0 +1,339 @@
+//! 将任务进程放入独立的 cgroup v2 (仅 Linux 下有效).
+//!
+//! 每个任务对应委派子树 (`AppConfig::cgroup_root`) 下的 `task-<id>` 目录, 资源限制设置在这一层,
+//! 每次运行在其中创建 `run-<run_id>` 子目录, 用于单独结束和统计这次运行的进程.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::warn;
+
+#[cfg(target_os = "linux")]
+use crate::setup::Step;
+use crate::setup::StepReporter;
+
+/// 任务 cgroup 的资源限制, 为空表示不限制.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct CgroupLimits {
+    /// 内存上限 (字节), `memory.max`.
+    pub memory_max: Option<u64>,
+    /// CPU 带宽, `cpu.max`.
+    pub cpu_max: Option<CpuMax>,
+    /// 进程数量上限, `pids.max`.
+    pub pids_max: Option<u64>,
+    /// 块设备 IO 上限, `io.max`.
+    pub io_max: Vec<IoMax>,
+}
+
+/// 每 `period_us` 微秒最多使用 `quota_us` 微秒的 CPU 时间.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct CpuMax {
+    pub quota_us: u64,
+    pub period_us: u64,
+}
+
+/// 单个块设备的 IO 上限, 为空的项表示不限制.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct IoMax {
+    /// 设备号, 例如 `8:0`.
+    pub device: String,
+    pub rbps: Option<u64>,
+    pub wbps: Option<u64>,
+    pub riops: Option<u64>,
+    pub wiops: Option<u64>,
+}
+
+impl IoMax {
+    fn line(&self) -> String {
+        let value = |v: Option<u64>| v.map_or_else(|| "max".to_string(), |v| v.to_string());
+        format!(
+            "{} rbps={} wbps={} riops={} wiops={}",
+            self.device,
+            value(self.rbps),
+            value(self.wbps),
+            value(self.riops),
+            value(self.wiops)
+        )
+    }
+}
+
+/// 从 cgroup 的统计文件读取的资源使用情况.
+#[derive(Serialize, Clone, Debug, Default)]
+pub(crate) struct CgroupStats {
+    /// 累计 CPU 时间 (微秒), 来自 `cpu.stat` 的 `usage_usec`.
+    pub(crate) cpu_usage_usec: u64,
+    /// 当前内存使用 (字节), `memory.current`.
+    pub(crate) memory_current: u64,
+    /// 内存使用峰值 (字节), `memory.peak`, 内核不支持时为空.
+    pub(crate) memory_peak: Option<u64>,
+    /// 当前进程数量, `pids.current`.
+    pub(crate) pids_current: u64,
+}
+
+fn io_error(msg: String, e: std::io::Error) -> crate::Error {
+    crate::Error::with_source(crate::ErrorKind::Io, msg, Box::new(e))
+}
+
+const CONTROLLERS: [&str; 4] = ["+cpu", "+memory", "+pids", "+io"];
+
+/// 已经提示过无法开启的控制器, 每次启动都会重新开启, 每个控制器只提示一次.
+static FAILED_CONTROLLERS: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+/// 为 `dir` 的子 cgroup 开启需要的控制器, 没有委派的控制器会失败, 忽略即可.
+async fn enable_controllers(dir: &Path) {
+    let subtree_control = dir.join("cgroup.subtree_control");
+    for controller in CONTROLLERS {
+        if let Err(e) = tokio::fs::write(&subtree_control, controller).await {
+            let mut failed = FAILED_CONTROLLERS.lock().unwrap();
+            if !failed.contains(&controller) {
+                failed.push(controller);
+                warn!(
+                    "failed to enable cgroup controller {controller} in {}: {e:?}",
+                    dir.display()
+                );
+            }
+        }
+    }
+}
+
+/// `io.max` 中设置了上限的设备号.
+fn io_max_devices(content: &str) -> impl Iterator<Item = &str> {
+    content.lines().filter_map(|l| l.split_whitespace().next())
+}
+
+async fn create_dir(path: &Path) -> crate::Result<()> {
+    tokio::fs::create_dir_all(path)
+        .await
+        .map_err(|e| io_error(format!("failed to create cgroup: {}", path.display()), e))
+}
+
+/// 任务或者单次运行的 cgroup.
+#[derive(Debug)]
+pub(crate) struct Cgroup {
+    path: PathBuf,
+}
+
+impl Cgroup {
+    /// 任务 `task_id` 在 `root` 下的 cgroup, 不检查是否存在.
+    pub(crate) fn of_task(root: &Path, task_id: i64) -> Self {
+        Self {
+            path: root.join(format!("task-{task_id}")),
+        }
+    }
+
+    /// 任务 `task_id` 的第 `run_id` 次运行的 cgroup, 不检查是否存在.
+    pub(crate) fn of_run(root: &Path, task_id: i64, run_id: i64) -> Self {
+        Self {
+            path: Self::of_task(root, task_id)
+                .path
+                .join(format!("run-{run_id}")),
+        }
+    }
+
+    async fn write(&self, file: &str, content: &str) -> crate::Result<()> {
+        let path = self.path.join(file);
+        tokio::fs::write(&path, content)
+            .await
+            .map_err(|e| io_error(format!("failed to write {}", path.display()), e))
+    }
+
+    async fn read(&self, file: &str) -> crate::Result<String> {
+        let path = self.path.join(file);
+        tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| io_error(format!("failed to read {}", path.display()), e))
+    }
+
+    /// 创建任务的 cgroup 并写入资源限制, cgroup 已存在时只更新资源限制.
+    pub(crate) async fn prepare(
+        root: &Path,
+        task_id: i64,
+        limits: &CgroupLimits,
+    ) -> crate::Result<Self> {
+        if !cfg!(target_os = "linux") {
+            return Err(crate::Error::with_message(
+                crate::ErrorKind::Io,
+                "cgroups are only supported on Linux",
+            ));
+        }
+        enable_controllers(root).await;
+        let cgroup = Self::of_task(root, task_id);
+        create_dir(&cgroup.path).await?;
+
+        let max = |v: Option<u64>| v.map_or_else(|| "max".to_string(), |v| v.to_string());
+        cgroup.write("memory.max", &max(limits.memory_max)).await?;
+        cgroup.write("pids.max", &max(limits.pids_max)).await?;
+        let cpu_max = match &limits.cpu_max {
+            Some(c) => format!("{} {}", c.quota_us, c.period_us),
+            None => "max".to_string(),
+        };
+        cgroup.write("cpu.max", &cpu_max).await?;
+        // 已经从任务中移除的设备恢复为不限制
+        let current = cgroup.read("io.max").await.unwrap_or_default();
+        for device in io_max_devices(&current) {
+            if !limits.io_max.iter().any(|io| io.device == device) {
+                let unlimited = IoMax {
+                    device: device.to_string(),
+                    rbps: None,
+                    wbps: None,
+                    riops: None,
+                    wiops: None,
+                };
+                cgroup.write("io.max", &unlimited.line()).await?;
+            }
+        }
+        for io in &limits.io_max {
+            cgroup.write("io.max", &io.line()).await?;
+        }
+        // 进程都放在运行的子 cgroup 中, 任务的 cgroup 自身不包含进程.
+        enable_controllers(&cgroup.path).await;
+        Ok(cgroup)
+    }
+
+    /// 为第 `run_id` 次运行创建子 cgroup.
+    pub(crate) async fn create_run(&self, run_id: i64) -> crate::Result<Self> {
+        let cgroup = Self {
+            path: self.path.join(format!("run-{run_id}")),
+        };
+        create_dir(&cgroup.path).await?;
+        Ok(cgroup)
+    }
+
+    /// 子进程 exec 之前将自身加入 cgroup.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn attach(&self, cmd: &mut Command, steps: &StepReporter) -> crate::Result<()> {
+        use std::os::fd::AsRawFd;
+
+        let path = self.path.join("cgroup.procs");
+        let procs = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .map_err(|e| io_error(format!("failed to open {}", path.display()), e))?;
+        let steps = steps.clone();
+        // SAFETY: 只调用了 async-signal-safe 的 write.
+        unsafe {
+            cmd.pre_exec(move || {
+                // 写入 0 表示移动写入者自身.
+                if libc::write(procs.as_raw_fd(), b"0".as_ptr().cast(), 1) == -1 {
+                    return Err(steps.fail(Step::Cgroup, std::io::Error::last_os_error()));
+                }
+                Ok(())
+            });
+        }
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn attach(&self, _cmd: &mut Command, _steps: &StepReporter) -> crate::Result<()> {
+        Err(crate::Error::with_message(
+            crate::ErrorKind::Io,
+            "cgroups are only supported on Linux",
+        ))
+    }
+
+    /// 结束 cgroup 中的所有进程, 包括脱离了父进程的后台进程.
+    pub(crate) async fn kill(&self) {
+        if self.write("cgroup.kill", "1").await.is_ok() {
+            return;
+        }
+        // 内核不支持 cgroup.kill (5.14 之前), 逐个结束进程.
+        #[cfg(target_os = "linux")]
+        if let Ok(procs) = self.read("cgroup.procs").await {
+            for pid in procs.lines().filter_map(|l| l.trim().parse::<i32>().ok()) {
+                // SAFETY: kill 没有内存安全方面的要求.
+                unsafe {
+                    libc::kill(pid, libc::SIGKILL);
+                }
+            }
+        }
+    }
+
+    /// cgroup 中是否还有进程.
+    pub(crate) async fn is_populated(&self) -> bool {
+        self.read("cgroup.events")
+            .await
+            .is_ok_and(|events| events.lines().any(|l| l.trim() == "populated 1"))
+    }
+
+    /// 结束所有进程并删除 cgroup 及其子 cgroup.
+    pub(crate) async fn remove(self) {
+        self.kill().await;
+        if let Ok(mut entries) = tokio::fs::read_dir(&self.path).await {
+            while let Ok(Some(entry)) = entries.next_entry().await {
+                if entry.file_type().await.is_ok_and(|t| t.is_dir()) {
+                    remove_dir(&entry.path()).await;
+                }
+            }
+        }
+        remove_dir(&self.path).await;
+    }
+
+    /// 读取 cgroup 的资源使用情况.
+    pub(crate) async fn stats(&self) -> crate::Result<CgroupStats> {
+        let number = |s: String| s.trim().parse::<u64>().unwrap_or_default();
+        let cpu_usage_usec = self
+            .read("cpu.stat")
+            .await?
+            .lines()
+            .find_map(|l| l.strip_prefix("usage_usec "))
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or_default();
+        Ok(CgroupStats {
+            cpu_usage_usec,
+            memory_current: self.read("memory.current").await.map(number)?,
+            memory_peak: self.read("memory.peak").await.ok().map(number),
+            pids_current: self.read("pids.current").await.map(number)?,
+        })
+    }
+}
+
+/// 删除空的 cgroup 目录, 进程退出之后 cgroup 才能被删除, 所以会重试几次.
+async fn remove_dir(path: &Path) {
+    for _ in 0..10 {
+        match tokio::fs::remove_dir(path).await {
+            Ok(()) => return,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(_) => tokio::time::sleep(std::time::Duration::from_millis(100)).await,
+        }
+    }
+    warn!("failed to remove cgroup: {}", path.display());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_max_line() {
+        let io = IoMax {
+            device: "8:0".to_string(),
+            rbps: Some(1_048_576),
+            wbps: None,
+            riops: None,
+            wiops: Some(100),
+        };
+        assert_eq!(io.line(), "8:0 rbps=1048576 wbps=max riops=max wiops=100");
+    }
+
+    #[test]
+    fn io_max_devices_of_file() {
+        let content = "8:0 rbps=1048576 wbps=max riops=max wiops=max\n259:0 rbps=max wbps=max riops=10 wiops=max\n";
+        assert_eq!(
+            io_max_devices(content).collect::<Vec<_>>(),
+            ["8:0", "259:0"]
+        );
+        assert_eq!(io_max_devices("").count(), 0);
+    }
+
+    #[test]
+    fn run_cgroup_under_task() {
+        let root = Path::new("/sys/fs/cgroup/bgm");
+        assert_eq!(Cgroup::of_task(root, 3).path, root.join("task-3"));
+        assert_eq!(Cgroup::of_run(root, 3, 42).path, root.join("task-3/run-42"));
+    }
+}
@@ -1,12 +1,16 @@
 use std::path::Path;
 
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Emitter, State};
 use tauri_plugin_dialog::DialogExt;
-use tokio::fs;
 
 use crate::{
     app_state::AppState,
+    cast::read_recording,
+    cgroup::{Cgroup, CgroupStats},
     config::AppConfig,
+    environment::{Environment, EnvironmentDAO},
+    resolve,
+    run::{OutputChunk, Run, RunDAO, read_output, tail_output},
     schedule::TaskStatus,
     task::{Task, TaskDAO},
 };
@@ -56,10 +60,11 @@ pub(crate) async fn remove_task(app_state: State<'_, AppState>, id: i64) -> Resu
 pub(crate) async fn manually_run_task(
     app_state: State<'_, AppState>,
     id: i64,
+    payload: Option<String>,
 ) -> Result<(), String> {
     app_state
         .scheduler()
-        .manually_run_task(id)
+        .manually_run_task(id, payload)
         .await
         .map_err(|e| format!("{e}"))
 }
@@ -115,22 +120,13 @@ pub(crate) async fn pick_dir(window: tauri::Window) -> Result<Option<String>, St
 }
 
 #[tauri::command]
-pub(crate) async fn is_program_runnable(path: &Path) -> Result<bool, String> {
-    match fs::metadata(path).await {
-        Ok(md) => {
-            #[cfg(unix)]
-            {
-                use std::os::unix::prelude::MetadataExt;
-
-                // .app 目录或者可执行文件.
-                if md.mode() & 0o111 == 0 {
-                    return Ok(false);
-                }
-            }
-            Ok(true)
-        }
-        Err(_) => Ok(false),
-    }
+pub(crate) async fn is_program_runnable(
+    app_state: State<'_, AppState>,
+    path: &Path,
+) -> Result<bool, String> {
+    let config = app_state.get_config().await;
+    // .app 目录, 可执行文件或者配置了解释器的脚本.
+    Ok(resolve::is_runnable(path, &config))
 }
 
 #[tauri::command]
@@ -156,6 +152,67 @@ pub(crate) async fn update_config(
         .map_err(|e| format!("{e}"))
 }
 
+/// 重新捕获登录 shell 的环境变量, 返回变量的数量.
+#[tauri::command]
+pub(crate) async fn refresh_shell_env(app_state: State<'_, AppState>) -> Result<usize, String> {
+    app_state
+        .refresh_base_env()
+        .await
+        .map_err(|e| format!("{e}"))
+}
+
+#[tauri::command]
+pub(crate) async fn list_environments(
+    app_state: State<'_, AppState>,
+) -> Result<Vec<Environment>, String> {
+    app_state
+        .db()
+        .await
+        .list_environments()
+        .await
+        .map_err(|e| format!("{e}"))
+}
+
+/// 添加或者修改环境, 改名时同时更新引用它的任务.
+#[tauri::command]
+pub(crate) async fn save_environment(
+    app_state: State<'_, AppState>,
+    environment: Environment,
+) -> Result<i64, String> {
+    if environment.name.trim().is_empty() {
+        return Err("environment name cannot be empty".to_string());
+    }
+    let name = environment.name.clone();
+    let (id, old_name) = app_state
+        .db()
+        .await
+        .save_environment(environment)
+        .await
+        .map_err(|e| format!("{e}"))?;
+    if let Some(old_name) = old_name {
+        app_state
+            .scheduler()
+            .rename_environment(old_name, name)
+            .await
+            .map_err(|e| format!("{e}"))?;
+    }
+    Ok(id)
+}
+
+#[tauri::command]
+pub(crate) async fn remove_environment(
+    app_state: State<'_, AppState>,
+    id: i64,
+) -> Result<(), String> {
+    app_state
+        .db()
+        .await
+        .remove_environment(id)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("{e}"))
+}
+
 #[tauri::command]
 pub(crate) async fn stop_task(app_state: State<'_, AppState>, id: i64) -> Result<(), String> {
     app_state
@@ -177,3 +234,158 @@ pub(crate) async fn reorder_tasks(
         .await
         .map_err(|e| format!("{e}"))
 }
+
+#[tauri::command]
+pub(crate) async fn list_task_runs(
+    app_state: State<'_, AppState>,
+    id: i64,
+    limit: u64,
+) -> Result<Vec<Run>, String> {
+    app_state
+        .db()
+        .await
+        .list_runs(id, None, limit)
+        .await
+        .map_err(|e| format!("{e}"))
+}
+
+#[tauri::command]
+pub(crate) async fn get_run_output(
+    app_state: State<'_, AppState>,
+    run_id: i64,
+    offset: u64,
+    limit: u64,
+) -> Result<OutputChunk, String> {
+    let run = app_state
+        .db()
+        .await
+        .get_run(run_id)
+        .await
+        .map_err(|e| format!("{e}"))?
+        .ok_or_else(|| format!("run not found: {run_id}"))?;
+    let output = run
+        .output
+        .ok_or_else(|| format!("output of run {run_id} is not captured"))?;
+    read_output(&output, offset, limit)
+        .await
+        .map_err(|e| format!("{e}"))
+}
+
+/// 读取任务 cgroup 的资源使用统计, 没有使用 cgroup 或者任务还没有运行过时返回空.
+///
+/// 任务的 cgroup 包含所有副本, `instance` 不为空时读取该副本正在进行的运行的 cgroup,
+/// 副本没有在运行时返回空.
+#[tauri::command]
+pub(crate) async fn get_task_stats(
+    app_state: State<'_, AppState>,
+    id: i64,
+    instance: Option<u32>,
+) -> Result<Option<CgroupStats>, String> {
+    let config = app_state.get_config().await;
+    let Some(root) = config.cgroup_root() else {
+        return Ok(None);
+    };
+    let cgroup = match instance {
+        Some(instance) => {
+            let latest = app_state
+                .db()
+                .await
+                .list_runs(id, Some(instance), 1)
+                .await
+                .map_err(|e| format!("{e}"))?
+                .into_iter()
+                .next()
+                .filter(|r| r.ended_at.is_none());
+            match latest {
+                Some(run) => Cgroup::of_run(root, id, run.id),
+                None => return Ok(None),
+            }
+        }
+        None => Cgroup::of_task(root, id),
+    };
+    Ok(cgroup.stats().await.ok())
+}
+
+/// 读取伪终端运行的 asciicast 录制文件内容.
+#[tauri::command]
+pub(crate) async fn get_run_recording(
+    app_state: State<'_, AppState>,
+    run_id: i64,
+) -> Result<String, String> {
+    let run = app_state
+        .db()
+        .await
+        .get_run(run_id)
+        .await
+        .map_err(|e| format!("{e}"))?
+        .ok_or_else(|| format!("run not found: {run_id}"))?;
+    let recording = run
+        .recording
+        .ok_or_else(|| format!("run {run_id} is not recorded"))?;
+    read_recording(&recording).await.map_err(|e| format!("{e}"))
+}
+
+/// 读取任务 (`instance` 不为空时为该副本) 最近一次运行输出的最后 `lines` 行,
+/// 如果最近一次运行没有捕获输出, 则读取任务的标准输出文件.
+///
+/// 使用副本的任务没有指定 `instance` 时读取最近运行的副本的标准输出文件.
+#[tauri::command]
+pub(crate) async fn tail_task_output(
+    app_state: State<'_, AppState>,
+    id: i64,
+    instance: Option<u32>,
+    lines: usize,
+) -> Result<String, String> {
+    let db = app_state.db().await;
+    let latest = db
+        .list_runs(id, instance, 1)
+        .await
+        .map_err(|e| format!("{e}"))?
+        .into_iter()
+        .next();
+    let instance = instance.or_else(|| latest.as_ref().and_then(|r| r.instance));
+    let output = match latest.and_then(|r| r.output) {
+        Some(output) => output,
+        None => db
+            .get_task(id)
+            .await
+            .map_err(|e| format!("{e}"))?
+            // 展开标准输出文件中的 `${INSTANCE}`
+            .map(|t| {
+                if t.replicas > 1 {
+                    t.for_instance(instance.unwrap_or(0))
+                } else {
+                    t
+                }
+            })
+            .and_then(|t| t.stdout)
+            .ok_or_else(|| format!("no output of task {id}"))?,
+    };
+    tail_output(&output, lines)
+        .await
+        .map_err(|e| format!("{e}"))
+}
+
+/// 订阅任务的实时输出, 输出通过 `task-output` 事件推送.
+#[tauri::command]
+pub(crate) async fn subscribe_task_output(
+    app: AppHandle,
+    app_state: State<'_, AppState>,
+    id: i64,
+) -> Result<(), String> {
+    app_state
+        .subscribe_output(id, move |line| {
+            app.emit("task-output", line).ok();
+        })
+        .await;
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn unsubscribe_task_output(
+    app_state: State<'_, AppState>,
+    id: i64,
+) -> Result<(), String> {
+    app_state.unsubscribe_output(id).await;
+    Ok(())
+}
@@ -1,11 +1,14 @@
 use serde::{Deserialize, Serialize};
-use std::path::{Path, PathBuf};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
 use tokio::{
     fs::{self, File},
     io::AsyncReadExt,
 };
 
-use crate::utils::EnsureDirExists;
+use crate::{suspension::SuspensionPolicy, utils::EnsureDirExists};
 
 pub(crate) fn data_dir() -> crate::Result<PathBuf> {
     dirs_next::data_dir()
@@ -27,6 +30,10 @@ pub(crate) fn log_dir() -> crate::Result<PathBuf> {
     data_dir()?.join("logs").ensure_dir_exists()
 }
 
+pub(crate) fn runs_dir() -> crate::Result<PathBuf> {
+    data_dir()?.join("runs").ensure_dir_exists()
+}
+
 pub(crate) fn config_dir() -> crate::Result<PathBuf> {
     dirs_next::config_dir()
         .ok_or_else(|| {
@@ -41,7 +48,7 @@ pub(crate) fn config_dir() -> crate::Result<PathBuf> {
 
 pub(crate) const PKG_NAME: &str = env!("CARGO_PKG_NAME");
 
-#[derive(Serialize, Deserialize, bon::Builder, Clone)]
+#[derive(Serialize, Deserialize, bon::Builder, Clone, Debug)]
 pub(crate) struct AppConfig {
     #[serde(skip)]
     #[builder(skip)]
@@ -51,6 +58,37 @@ pub(crate) struct AppConfig {
     #[serde(default)]
     #[builder(default = false)]
     quiet_launch: bool,
+
+    /// 委派给应用的 cgroup v2 子树, 设置之后每个任务都运行在其中独立的 cgroup 中 (仅 Linux 下有效).
+    #[serde(default)]
+    cgroup_root: Option<PathBuf>,
+
+    /// 没有单独设置 suspension 条件的任务使用的默认条件.
+    #[serde(default)]
+    #[builder(default)]
+    suspension: SuspensionPolicy,
+
+    /// 启动时捕获用户登录 shell 的环境变量, 作为任务环境变量的基础 (仅 Unix 下有效).
+    #[serde(default)]
+    #[builder(default = false)]
+    login_shell_env: bool,
+
+    /// 查找只有文件名的任务程序时, 在 `PATH` 之前搜索的目录.
+    #[serde(default)]
+    #[builder(default)]
+    search_path: Vec<PathBuf>,
+
+    /// 扩展名 (不含 `.`) 对应的解释器命令, 用于运行没有 shebang 或者没有可执行权限的脚本.
+    #[serde(default = "default_interpreters")]
+    #[builder(default = default_interpreters())]
+    interpreters: HashMap<String, Vec<String>>,
+}
+
+fn default_interpreters() -> HashMap<String, Vec<String>> {
+    HashMap::from([
+        ("py".to_string(), vec!["python3".to_string()]),
+        ("js".to_string(), vec!["node".to_string()]),
+    ])
 }
 
 impl AppConfig {
@@ -126,4 +164,37 @@ impl AppConfig {
     pub(crate) fn quiet_launch(&self) -> bool {
         self.quiet_launch
     }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn cgroup_root(&self) -> Option<&Path> {
+        self.cgroup_root.as_deref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn suspension(&self) -> &SuspensionPolicy {
+        &self.suspension
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn login_shell_env(&self) -> bool {
+        self.login_shell_env
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn search_path(&self) -> &[PathBuf] {
+        &self.search_path
+    }
+
+    /// 扩展名对应的解释器命令, 扩展名不区分大小写.
+    #[must_use]
+    pub(crate) fn interpreter(&self, ext: &str) -> Option<&[String]> {
+        self.interpreters
+            .iter()
+            .find(|(k, _)| k.trim_start_matches('.').eq_ignore_ascii_case(ext))
+            .map(|(_, v)| v.as_slice())
+    }
 }
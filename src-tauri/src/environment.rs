@@ -0,0 +1,299 @@
+//! 命名环境: 多个任务共用的环境变量, PATH 前缀和激活脚本, 任务通过名称引用.
+
+use std::{collections::HashMap, path::PathBuf};
+
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, NotSet, PaginatorTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{shell_env, task::Task};
+
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct Environment {
+    pub id: Option<i64>,
+    /// 唯一的名称, 任务通过名称引用.
+    pub name: String,
+    #[serde(default)]
+    pub env_vars: HashMap<String, String>,
+    /// 按顺序添加到 `PATH` 前面的目录.
+    #[serde(default)]
+    pub path_prefixes: Vec<PathBuf>,
+    /// 激活脚本, 每次启动任务之前以任务的身份由 `/bin/sh` 执行,
+    /// 例如 `. ~/venvs/scraper/bin/activate` (仅 Unix 下有效).
+    pub activation_script: Option<String>,
+}
+
+impl From<entity::environments::Model> for Environment {
+    fn from(m: entity::environments::Model) -> Self {
+        Environment {
+            id: Some(m.id),
+            name: m.name,
+            env_vars: serde_json::from_str(&m.env_vars).unwrap_or_default(),
+            path_prefixes: serde_json::from_str(&m.path_prefixes).unwrap_or_default(),
+            activation_script: m.activation_script,
+        }
+    }
+}
+
+impl From<Environment> for entity::environments::ActiveModel {
+    fn from(e: Environment) -> Self {
+        Self {
+            id: match e.id {
+                Some(id) => Set(id),
+                None => NotSet,
+            },
+            name: Set(e.name),
+            env_vars: Set(serde_json::to_string(&e.env_vars).unwrap_or_else(|_| "{}".to_string())),
+            path_prefixes: Set(
+                serde_json::to_string(&e.path_prefixes).unwrap_or_else(|_| "[]".to_string())
+            ),
+            activation_script: Set(e.activation_script),
+        }
+    }
+}
+
+impl Environment {
+    /// 为 `task` 在 `base` 之上应用环境, 依次是激活脚本设置的变量, 环境变量和 `PATH` 前缀.
+    ///
+    /// 激活脚本以任务的身份 ([`Task::identity`]) 运行. 任务是副本时, 环境变量和 `PATH` 前缀中的
+    /// `${INSTANCE}` 和任务自己的环境变量一样展开.
+    pub(crate) async fn apply(
+        &self,
+        base: &HashMap<String, String>,
+        task: &Task,
+    ) -> crate::Result<HashMap<String, String>> {
+        let mut env = base.clone();
+        if let Some(script) = self
+            .activation_script
+            .as_deref()
+            .filter(|s| !s.trim().is_empty())
+        {
+            let activated = shell_env::activate(script, base, &task.identity)
+                .await
+                .map_err(|e| {
+                    crate::Error::with_source(
+                        crate::ErrorKind::Io,
+                        format!(
+                            "failed to run activation script of environment {}",
+                            self.name
+                        ),
+                        Box::new(e),
+                    )
+                })?;
+            env.extend(activated);
+        }
+        env.extend(
+            self.env_vars
+                .iter()
+                .map(|(k, v)| (k.clone(), task.expand_instance(v))),
+        );
+        if !self.path_prefixes.is_empty() {
+            let path = env
+                .get("PATH")
+                .cloned()
+                .or_else(|| std::env::var("PATH").ok())
+                .unwrap_or_default();
+            let dirs = self
+                .path_prefixes
+                .iter()
+                .map(|p| task.expand_instance_path(p))
+                .chain(std::env::split_paths(&path));
+            let path = std::env::join_paths(dirs).map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Io,
+                    format!("invalid PATH prefix of environment {}", self.name),
+                    Box::new(e),
+                )
+            })?;
+            env.insert("PATH".to_string(), path.to_string_lossy().into_owned());
+        }
+        Ok(env)
+    }
+}
+
+/// 任务的完整环境变量, 依次是 `base` (登录 shell 的环境变量), 任务引用的环境和任务自己的环境变量.
+///
+/// 每次启动时重新读取环境, 修改环境之后所有使用它的任务在下一次启动时生效.
+pub(crate) async fn compose(
+    db: &DatabaseConnection,
+    task: &Task,
+    base: &HashMap<String, String>,
+) -> crate::Result<HashMap<String, String>> {
+    let env = match &task.environment {
+        Some(name) => match db.get_environment_by_name(name).await? {
+            Some(environment) => environment.apply(base, task).await?,
+            None => {
+                return Err(crate::Error::with_message(
+                    crate::ErrorKind::Io,
+                    format!("environment {name} does not exist"),
+                ));
+            }
+        },
+        None => base.clone(),
+    };
+    Ok(shell_env::merge(&env, &task.env_vars))
+}
+
+pub trait EnvironmentDAO {
+    /// 按名称列出所有环境.
+    async fn list_environments(&self) -> crate::Result<Vec<Environment>>;
+    async fn get_environment(&self, id: i64) -> crate::Result<Option<Environment>>;
+    async fn get_environment_by_name(&self, name: &str) -> crate::Result<Option<Environment>>;
+    /// 添加或者修改环境, 返回环境的 id 和修改之前的名称 (没有修改名称时为空).
+    /// 修改名称时同时修改引用它的任务.
+    async fn save_environment(
+        &self,
+        environment: Environment,
+    ) -> crate::Result<(i64, Option<String>)>;
+    /// 删除环境, 仍然有任务引用时返回错误. 环境不存在时返回 `Ok(false)`.
+    async fn remove_environment(&self, id: i64) -> crate::Result<bool>;
+}
+
+impl EnvironmentDAO for DatabaseConnection {
+    async fn list_environments(&self) -> crate::Result<Vec<Environment>> {
+        let environments = entity::environments::Entity::find()
+            .order_by_asc(entity::environments::Column::Name)
+            .all(self)
+            .await
+            .map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Db,
+                    "failed to list environments",
+                    Box::new(e),
+                )
+            })?;
+        Ok(environments.into_iter().map(|e| e.into()).collect())
+    }
+
+    async fn get_environment(&self, id: i64) -> crate::Result<Option<Environment>> {
+        let environment = entity::environments::Entity::find_by_id(id)
+            .one(self)
+            .await
+            .map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Db,
+                    format!("failed to get environment id: {id}"),
+                    Box::new(e),
+                )
+            })?;
+        Ok(environment.map(|e| e.into()))
+    }
+
+    async fn get_environment_by_name(&self, name: &str) -> crate::Result<Option<Environment>> {
+        let environment = entity::environments::Entity::find()
+            .filter(entity::environments::Column::Name.eq(name))
+            .one(self)
+            .await
+            .map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Db,
+                    format!("failed to get environment: {name}"),
+                    Box::new(e),
+                )
+            })?;
+        Ok(environment.map(|e| e.into()))
+    }
+
+    async fn save_environment(
+        &self,
+        environment: Environment,
+    ) -> crate::Result<(i64, Option<String>)> {
+        self.transaction::<_, (i64, Option<String>), crate::Error>(|txn| {
+            Box::pin(async move {
+                let name = environment.name.clone();
+                // 在同一个事务中读取原来的名称, 避免和同时进行的修改冲突
+                let old_name = match environment.id {
+                    Some(id) => entity::environments::Entity::find_by_id(id)
+                        .one(txn)
+                        .await
+                        .map_err(|e| {
+                            crate::Error::with_source(
+                                crate::ErrorKind::Db,
+                                format!("failed to get environment id: {id}"),
+                                Box::new(e),
+                            )
+                        })?
+                        .map(|e| e.name)
+                        .filter(|old| *old != name),
+                    None => None,
+                };
+                let am: entity::environments::ActiveModel = environment.into();
+                let a = am.save(txn).await.map_err(|e| {
+                    crate::Error::with_source(
+                        crate::ErrorKind::Db,
+                        format!("failed to save environment: {name}"),
+                        Box::new(e),
+                    )
+                })?;
+                if let Some(old_name) = &old_name {
+                    entity::tasks::Entity::update_many()
+                        .col_expr(
+                            entity::tasks::Column::Environment,
+                            Expr::value(name.clone()),
+                        )
+                        .filter(entity::tasks::Column::Environment.eq(old_name))
+                        .exec(txn)
+                        .await
+                        .map_err(|e| {
+                            crate::Error::with_source(
+                                crate::ErrorKind::Db,
+                                format!("failed to rename environment of tasks: {old_name}"),
+                                Box::new(e),
+                            )
+                        })?;
+                }
+                Ok((a.id.unwrap(), old_name))
+            })
+        })
+        .await
+        .map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                "failed to save environment in transaction",
+                Box::new(e),
+            )
+        })
+    }
+
+    async fn remove_environment(&self, id: i64) -> crate::Result<bool> {
+        let Some(environment) = self.get_environment(id).await? else {
+            return Ok(false);
+        };
+        let used_by = entity::tasks::Entity::find()
+            .filter(entity::tasks::Column::Environment.eq(&environment.name))
+            .count(self)
+            .await
+            .map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Db,
+                    format!(
+                        "failed to count tasks using environment: {}",
+                        environment.name
+                    ),
+                    Box::new(e),
+                )
+            })?;
+        if used_by > 0 {
+            return Err(crate::Error::with_message(
+                crate::ErrorKind::Db,
+                format!(
+                    "environment {} is used by {used_by} task(s)",
+                    environment.name
+                ),
+            ));
+        }
+        let rst = entity::environments::Entity::delete_by_id(id)
+            .exec(self)
+            .await
+            .map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Db,
+                    format!("failed to remove environment id: {id}"),
+                    Box::new(e),
+                )
+            })?;
+        Ok(rst.rows_affected > 0)
+    }
+}
@@ -14,6 +14,8 @@ pub enum ErrorKind {
     JsonSer,
     #[error("tray error")]
     Tray,
+    #[error("regex error")]
+    Regex,
 }
 
 #[derive(thiserror::Error, Debug)]
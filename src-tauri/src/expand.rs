@@ -0,0 +1,113 @@
+//! `${NAME}` 形式的变量展开.
+
+use std::collections::HashMap;
+
+use crate::task::Task;
+
+/// 展开 `template` 中的 `${NAME}`, `$$` 展开为 `$`.
+///
+/// 找不到的变量展开为空字符串, 没有闭合的 `${` 原样保留.
+#[must_use]
+pub(crate) fn expand(template: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        if let Some(after) = rest.strip_prefix("$$") {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${")
+            && let Some(end) = after.find('}')
+        {
+            out.push_str(&lookup(&after[..end]).unwrap_or_default());
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 只展开 `template` 中的 `${name}`, 其他内容原样保留.
+#[must_use]
+pub(crate) fn expand_var(template: &str, name: &str, value: &str) -> String {
+    template.replace(&format!("${{{name}}}"), value)
+}
+
+/// 任务运行时可以使用的变量, 依次查找内置变量, 任务的环境变量和管理器的环境变量.
+pub(crate) struct Vars<'a> {
+    builtin: HashMap<&'static str, String>,
+    env_vars: &'a HashMap<String, String>,
+}
+
+impl<'a> Vars<'a> {
+    /// 内置变量 `TASK_ID`, `TASK_NAME`, 以及触发本次运行的事件提供的 `PAYLOAD`.
+    pub(crate) fn new(task: &'a Task, payload: Option<&str>) -> Self {
+        let mut builtin = HashMap::new();
+        if let Some(id) = task.id {
+            builtin.insert("TASK_ID", id.to_string());
+        }
+        builtin.insert("TASK_NAME", task.name.clone());
+        builtin.insert("PAYLOAD", payload.unwrap_or_default().to_owned());
+        Self {
+            builtin,
+            env_vars: &task.env_vars,
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn get(&self, name: &str) -> Option<String> {
+        self.builtin
+            .get(name)
+            .or_else(|| self.env_vars.get(name))
+            .cloned()
+            .or_else(|| std::env::var(name).ok())
+    }
+
+    #[must_use]
+    pub(crate) fn expand(&self, template: &str) -> String {
+        expand(template, |name| self.get(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "A" => Some("1".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn expand_variables() {
+        assert_eq!(expand("x${A}y", lookup), "x1y");
+        assert_eq!(expand("${A}${A}", lookup), "11");
+        assert_eq!(expand("[${EMPTY}]", lookup), "[]");
+        assert_eq!(expand("[${MISSING}]", lookup), "[]");
+    }
+
+    #[test]
+    fn expand_escapes_and_unclosed() {
+        assert_eq!(expand("$$A", lookup), "$A");
+        assert_eq!(expand("$${A}", lookup), "${A}");
+        assert_eq!(expand("cost $5", lookup), "cost $5");
+        assert_eq!(expand("${A", lookup), "${A");
+        assert_eq!(expand("end$", lookup), "end$");
+    }
+
+    #[test]
+    fn expand_var_only_replaces_name() {
+        assert_eq!(
+            expand_var("/log/${INSTANCE}/${OTHER}.log", "INSTANCE", "2"),
+            "/log/2/${OTHER}.log"
+        );
+        assert_eq!(expand_var("$INSTANCE", "INSTANCE", "2"), "$INSTANCE");
+    }
+}
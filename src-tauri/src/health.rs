@@ -0,0 +1,254 @@
+//! `KeepAlive` 任务的健康检查.
+
+use std::{collections::HashMap, path::PathBuf, process::Stdio, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    process::Command,
+    sync::mpsc,
+    task::JoinHandle,
+};
+
+use crate::{identity::Identity, setup::Steps};
+
+/// 健康检查的方式.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "tag", content = "content")]
+pub enum Probe {
+    /// 能够连接到本机的端口时健康.
+    Tcp { port: u16 },
+    /// 对本机端口发送 HTTP GET 请求, 响应状态码为 `status` 时健康.
+    Http {
+        port: u16,
+        path: String,
+        status: u16,
+    },
+    /// 执行命令, 退出码为 0 时健康. 命令以任务的身份和环境变量运行.
+    Command { program: PathBuf, args: Vec<String> },
+}
+
+/// 健康检查的选项, 连续失败 `failure_threshold` 次时重启任务.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct HealthCheck {
+    pub probe: Probe,
+    /// 进程启动之后第一次检查之前的等待时间 (毫秒).
+    #[serde(default = "default_initial_delay_ms")]
+    pub initial_delay_ms: u64,
+    /// 两次检查之间的间隔 (毫秒).
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u64,
+    /// 单次检查的超时时间 (毫秒), 超时视为失败.
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// 判定为不健康的连续失败次数.
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+}
+
+fn default_initial_delay_ms() -> u64 {
+    5000
+}
+
+fn default_interval_ms() -> u64 {
+    10_000
+}
+
+fn default_timeout_ms() -> u64 {
+    3000
+}
+
+fn default_failure_threshold() -> u32 {
+    3
+}
+
+impl Probe {
+    /// 执行一次检查, 失败时返回原因. 命令以 `identity` 运行, 环境变量为 `env_vars`.
+    async fn check(
+        &self,
+        identity: &Identity,
+        env_vars: &HashMap<String, String>,
+    ) -> Result<(), String> {
+        match self {
+            Probe::Tcp { port } => {
+                TcpStream::connect(("localhost", *port))
+                    .await
+                    .map_err(|e| format!("failed to connect to port {port}: {e}"))?;
+                Ok(())
+            }
+            Probe::Http { port, path, status } => {
+                let actual = http_status(*port, path)
+                    .await
+                    .map_err(|e| format!("failed to request port {port}: {e}"))?;
+                if actual == *status {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "unexpected http status {actual}, expected {status}"
+                    ))
+                }
+            }
+            Probe::Command { program, args } => {
+                let mut cmd = Command::new(program);
+                cmd.args(args)
+                    .envs(env_vars)
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .kill_on_drop(true);
+                let steps = Steps::new().map_err(|e| format!("{e}"))?;
+                crate::identity::apply(&mut cmd, identity, env_vars, steps.reporter())
+                    .map_err(|e| format!("{e}"))?;
+                let status = cmd.status().await.map_err(|e| match steps.failed() {
+                    Some(failed) => format!("failed to run {}: {failed}", program.display()),
+                    None => format!("failed to run {}: {e}", program.display()),
+                })?;
+                if status.success() {
+                    Ok(())
+                } else {
+                    Err(format!("probe command exited with {status}"))
+                }
+            }
+        }
+    }
+}
+
+/// 发送 HTTP/1.0 GET 请求, 返回响应的状态码.
+async fn http_status(port: u16, path: &str) -> std::io::Result<u16> {
+    let mut stream = TcpStream::connect(("localhost", port)).await?;
+    let path = if path.starts_with('/') {
+        path.to_string()
+    } else {
+        format!("/{path}")
+    };
+    let request =
+        format!("GET {path} HTTP/1.0\r\nHost: localhost:{port}\r\nConnection: close\r\n\r\n");
+    stream.write_all(request.as_bytes()).await?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).await?;
+    // 状态行: HTTP/1.1 200 OK
+    line.split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::InvalidData, "malformed response"))
+}
+
+/// 正在运行的任务的健康状态.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(tag = "tag", content = "content")]
+pub(crate) enum Health {
+    /// 还没有完成第一次检查.
+    #[default]
+    Starting,
+    Healthy,
+    /// 最近的检查失败, `failures` 为连续失败的次数.
+    Unhealthy {
+        failures: u32,
+        reason: String,
+    },
+}
+
+/// 按照 [`HealthCheck`] 定期检查正在运行的任务.
+///
+/// 检查在单独的协程中进行, 等待结果被取消时不会中断正在进行的检查.
+#[derive(Debug, Default)]
+pub(crate) struct HealthMonitor {
+    health: Health,
+    failures: u32,
+    failure_threshold: u32,
+    /// 没有在检查时为空.
+    probing: Option<Probing>,
+}
+
+/// 后台进行的检查.
+#[derive(Debug)]
+struct Probing {
+    handle: JoinHandle<()>,
+    /// 每次检查的结果.
+    results: mpsc::Receiver<Result<(), String>>,
+}
+
+impl Drop for HealthMonitor {
+    fn drop(&mut self) {
+        self.reset();
+    }
+}
+
+impl HealthMonitor {
+    #[inline]
+    #[must_use]
+    pub(crate) fn health(&self) -> &Health {
+        &self.health
+    }
+
+    /// 进程启动之后开始在后台定期检查, 已经在检查时重新开始.
+    ///
+    /// 命令检查以任务的身份 `identity` 和进程的环境变量 `env_vars` 运行.
+    pub(crate) fn start(
+        &mut self,
+        check: &HealthCheck,
+        identity: &Identity,
+        env_vars: &HashMap<String, String>,
+    ) {
+        self.reset();
+        let (tx, rx) = mpsc::channel(1);
+        let check = check.clone();
+        let identity = identity.clone();
+        let env_vars = env_vars.clone();
+        let handle = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(check.initial_delay_ms)).await;
+            loop {
+                let timeout = Duration::from_millis(check.timeout_ms);
+                let probe = check.probe.check(&identity, &env_vars);
+                let result = match tokio::time::timeout(timeout, probe).await {
+                    Ok(result) => result,
+                    Err(_) => Err(format!("probe timed out after {}ms", check.timeout_ms)),
+                };
+                if tx.send(result).await.is_err() {
+                    break;
+                }
+                tokio::time::sleep(Duration::from_millis(check.interval_ms)).await;
+            }
+        });
+        self.failure_threshold = check.failure_threshold.max(1);
+        self.probing = Some(Probing {
+            handle,
+            results: rx,
+        });
+    }
+
+    /// 等待下一次检查的结果, 连续失败次数达到阈值时返回 false, 没有在检查时永远不会完成.
+    pub(crate) async fn next(&mut self) -> bool {
+        let Some(probing) = &mut self.probing else {
+            return std::future::pending().await;
+        };
+        let Some(result) = probing.results.recv().await else {
+            return std::future::pending().await;
+        };
+        match result {
+            Ok(()) => {
+                self.failures = 0;
+                self.health = Health::Healthy;
+                true
+            }
+            Err(reason) => {
+                self.failures += 1;
+                self.health = Health::Unhealthy {
+                    failures: self.failures,
+                    reason,
+                };
+                self.failures < self.failure_threshold
+            }
+        }
+    }
+
+    /// 进程退出或者被重启之后停止检查, 下一次启动时重新开始.
+    pub(crate) fn reset(&mut self) {
+        if let Some(probing) = self.probing.take() {
+            probing.handle.abort();
+        }
+        self.health = Health::Starting;
+        self.failures = 0;
+    }
+}
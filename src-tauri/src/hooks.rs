@@ -0,0 +1,175 @@
+//! 任务启动之前和退出之后运行的 hook 命令.
+//!
+//! hook 以管理器的身份运行, 继承任务的环境变量和工作目录, 并通过 `BGM_` 开头的环境变量获得本次运行的信息.
+
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    process::{ExitStatus, Stdio},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::warn;
+
+use crate::task::Task;
+
+/// hook 没有设置超时时间时的默认值.
+const DEFAULT_HOOK_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Hook {
+    pub program: PathBuf,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// 超时时间 (毫秒), 超时的 hook 会被结束并视为失败, 为空时使用 60 秒.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// 任务的 hook.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct Hooks {
+    /// 启动之前运行, 失败时放弃本次运行.
+    pub pre_start: Option<Hook>,
+    /// 每次退出之后运行.
+    pub post_stop: Option<Hook>,
+    /// 运行失败之后运行, 在 `post_stop` 之前.
+    pub on_failure: Option<Hook>,
+}
+
+impl Hook {
+    /// 运行 hook 并等待结束, 退出码非 0 或者超时时返回错误.
+    pub(crate) async fn run(&self, task: &Task, env: &[(&str, String)]) -> crate::Result<()> {
+        let mut cmd = Command::new(&self.program);
+        cmd.args(&self.args)
+            .envs(&task.env_vars)
+            .envs(env.iter().map(|(k, v)| (k, v)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(working_dir) = &task.working_dir {
+            cmd.current_dir(working_dir);
+        }
+        let timeout = self
+            .timeout_ms
+            .map_or(DEFAULT_HOOK_TIMEOUT, Duration::from_millis);
+        let output = match tokio::time::timeout(timeout, cmd.output()).await {
+            Ok(output) => output.map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Io,
+                    format!("failed to run hook: {}", self.program.display()),
+                    Box::new(e),
+                )
+            })?,
+            Err(_) => {
+                return Err(crate::Error::with_message(
+                    crate::ErrorKind::Io,
+                    format!("hook timed out: {}", self.program.display()),
+                ));
+            }
+        };
+        if output.status.success() {
+            return Ok(());
+        }
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(crate::Error::with_message(
+            crate::ErrorKind::Io,
+            format!(
+                "hook {} exited with {}: {}",
+                self.program.display(),
+                output.status,
+                stderr.trim()
+            ),
+        ))
+    }
+}
+
+/// 所有 hook 都可以使用的环境变量.
+fn base_env(task: &Task, run_id: i64, hook: &str) -> Vec<(&'static str, String)> {
+    vec![
+        ("BGM_TASK_ID", task.id.unwrap_or_default().to_string()),
+        ("BGM_TASK_NAME", task.name.clone()),
+        ("BGM_RUN_ID", run_id.to_string()),
+        ("BGM_HOOK", hook.to_string()),
+    ]
+}
+
+/// 运行 `pre_start` hook, 没有设置时直接返回 Ok.
+pub(crate) async fn pre_start(task: &Task, run_id: i64) -> crate::Result<()> {
+    match &task.hooks.pre_start {
+        Some(hook) => hook.run(task, &base_env(task, run_id, "pre_start")).await,
+        None => Ok(()),
+    }
+}
+
+/// 进程退出之后在后台依次运行 `on_failure` 和 `post_stop` hook.
+///
+/// 无法判断是否成功 (`succeeded` 为空, 例如接管的进程) 时只运行 `post_stop`.
+/// 除了基本的变量之外, 还提供 `BGM_PID`, `BGM_EXIT_CODE`, `BGM_SIGNAL`, `BGM_CORE_DUMPED` 和 `BGM_SUCCEEDED`,
+/// 没有对应的值时不设置. hook 使用本次运行的完整环境变量 `env_vars`.
+pub(crate) fn after_exit(
+    task: &Task,
+    env_vars: HashMap<String, String>,
+    run_id: i64,
+    pid: Option<u32>,
+    status: Option<ExitStatus>,
+    succeeded: Option<bool>,
+) {
+    let mut hooks = Vec::new();
+    if succeeded == Some(false)
+        && let Some(hook) = &task.hooks.on_failure
+    {
+        hooks.push(("on_failure", hook.clone()));
+    }
+    if let Some(hook) = &task.hooks.post_stop {
+        hooks.push(("post_stop", hook.clone()));
+    }
+    if hooks.is_empty() {
+        return;
+    }
+
+    let mut exit_env = Vec::new();
+    if let Some(succeeded) = succeeded {
+        exit_env.push((
+            "BGM_SUCCEEDED",
+            if succeeded { "1" } else { "0" }.to_string(),
+        ));
+    }
+    if let Some(pid) = pid {
+        exit_env.push(("BGM_PID", pid.to_string()));
+    }
+    if let Some(code) = status.and_then(|s| s.code()) {
+        exit_env.push(("BGM_EXIT_CODE", code.to_string()));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = status.and_then(|s| s.signal()) {
+            exit_env.push(("BGM_SIGNAL", signal.to_string()));
+            let core_dumped = status.is_some_and(|s| s.core_dumped());
+            exit_env.push((
+                "BGM_CORE_DUMPED",
+                if core_dumped { "1" } else { "0" }.to_string(),
+            ));
+        }
+    }
+
+    let task = Task {
+        env_vars,
+        ..task.clone()
+    };
+    tokio::spawn(async move {
+        for (name, hook) in hooks {
+            let mut env = base_env(&task, run_id, name);
+            env.extend(exit_env.iter().cloned());
+            if let Err(e) = hook.run(&task, &env).await {
+                warn!("{name} hook of task {:?} failed: {e:?}", task.id);
+            }
+        }
+    });
+}
@@ -0,0 +1,282 @@
+//! 以其他用户和组的身份运行任务进程 (仅 Unix 下有效).
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+#[cfg(unix)]
+use crate::setup::Step;
+use crate::setup::StepReporter;
+
+/// 任务进程的身份, 为空表示使用管理器的身份.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct Identity {
+    /// 用户名或者 uid, 切换到其他用户需要 root 权限.
+    pub user: Option<String>,
+    /// 组名或者 gid, 为空时使用用户的主组.
+    pub group: Option<String>,
+    /// 附加组的组名或者 gid, 为空时使用用户在 `/etc/group` 中所属的组.
+    pub groups: Vec<String>,
+    /// 文件创建掩码, 例如 `0o022`.
+    pub umask: Option<u32>,
+}
+
+impl Identity {
+    #[inline]
+    #[must_use]
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn invalid(msg: String) -> crate::Error {
+    crate::Error::with_message(crate::ErrorKind::Io, msg)
+}
+
+/// 从 passwd 数据库查到的用户.
+#[cfg(unix)]
+struct User {
+    name: String,
+    uid: libc::uid_t,
+    gid: libc::gid_t,
+    home: std::path::PathBuf,
+}
+
+/// 调用 getpwnam_r/getpwuid_r/getgrnam_r 之类的函数, 缓冲区不够时自动扩大.
+///
+/// 返回值中的字符串指向 `buf`, 使用完之前 `buf` 必须有效.
+#[cfg(unix)]
+fn lookup<T>(
+    buf: &mut Vec<libc::c_char>,
+    mut f: impl FnMut(*mut T, *mut libc::c_char, libc::size_t, *mut *mut T) -> libc::c_int,
+) -> std::io::Result<Option<T>> {
+    buf.resize(1024, 0);
+    loop {
+        let mut entry = std::mem::MaybeUninit::<T>::uninit();
+        let mut result = std::ptr::null_mut();
+        match f(entry.as_mut_ptr(), buf.as_mut_ptr(), buf.len(), &mut result) {
+            0 if result.is_null() => return Ok(None),
+            // SAFETY: 返回 0 并且 result 非空时 entry 已被初始化, 其中的指针指向 buf.
+            0 => return Ok(Some(unsafe { entry.assume_init() })),
+            libc::ERANGE if buf.len() < 1 << 20 => buf.resize(buf.len() * 2, 0),
+            e => return Err(std::io::Error::from_raw_os_error(e)),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn find_user(name: &str) -> crate::Result<User> {
+    use std::{
+        ffi::{CStr, CString, OsStr},
+        os::unix::ffi::OsStrExt,
+    };
+
+    let error = |e: std::io::Error| {
+        crate::Error::with_source(
+            crate::ErrorKind::Io,
+            format!("failed to look up user: {name}"),
+            Box::new(e),
+        )
+    };
+    let mut buf = Vec::new();
+    let passwd = match name.parse::<libc::uid_t>() {
+        // SAFETY: 参数都是有效的指针, 缓冲区长度与实际一致.
+        Ok(uid) => lookup(&mut buf, |pwd, b, len, result| unsafe {
+            libc::getpwuid_r(uid, pwd, b, len, result)
+        }),
+        Err(_) => {
+            let c_name = CString::new(name).map_err(|e| error(e.into()))?;
+            // SAFETY: 同上.
+            lookup(&mut buf, |pwd, b, len, result| unsafe {
+                libc::getpwnam_r(c_name.as_ptr(), pwd, b, len, result)
+            })
+        }
+    };
+    let Some(passwd) = passwd.map_err(error)? else {
+        return Err(invalid(format!("user not found: {name}")));
+    };
+    // SAFETY: passwd 中的字符串以 NUL 结尾, 指向仍然有效的 buf.
+    let (user_name, home) = unsafe {
+        (
+            CStr::from_ptr(passwd.pw_name)
+                .to_string_lossy()
+                .into_owned(),
+            std::path::PathBuf::from(OsStr::from_bytes(CStr::from_ptr(passwd.pw_dir).to_bytes())),
+        )
+    };
+    Ok(User {
+        name: user_name,
+        uid: passwd.pw_uid,
+        gid: passwd.pw_gid,
+        home,
+    })
+}
+
+#[cfg(unix)]
+fn find_group(name: &str) -> crate::Result<libc::gid_t> {
+    if let Ok(gid) = name.parse() {
+        return Ok(gid);
+    }
+    let c_name =
+        std::ffi::CString::new(name).map_err(|_| invalid(format!("invalid group name: {name}")))?;
+    let mut buf = Vec::new();
+    // SAFETY: 参数都是有效的指针, 缓冲区长度与实际一致.
+    let group = lookup(&mut buf, |grp, b, len, result| unsafe {
+        libc::getgrnam_r(c_name.as_ptr(), grp, b, len, result)
+    })
+    .map_err(|e| {
+        crate::Error::with_source(
+            crate::ErrorKind::Io,
+            format!("failed to look up group: {name}"),
+            Box::new(e),
+        )
+    })?;
+    group
+        .map(|g| g.gr_gid)
+        .ok_or_else(|| invalid(format!("group not found: {name}")))
+}
+
+/// 用户在组数据库中所属的所有组, 包括主组.
+#[cfg(unix)]
+fn user_groups(user: &User) -> crate::Result<Vec<libc::gid_t>> {
+    let name = std::ffi::CString::new(user.name.as_str())
+        .map_err(|_| invalid(format!("invalid user name: {}", user.name)))?;
+    let mut groups: Vec<libc::gid_t> = vec![0; 64];
+    loop {
+        let mut count = groups.len() as libc::c_int;
+        // SAFETY: groups 的长度与 count 一致.
+        let ret = unsafe {
+            #[cfg(target_os = "macos")]
+            let ret = libc::getgrouplist(
+                name.as_ptr(),
+                user.gid as libc::c_int,
+                groups.as_mut_ptr().cast(),
+                &mut count,
+            );
+            #[cfg(not(target_os = "macos"))]
+            let ret = libc::getgrouplist(name.as_ptr(), user.gid, groups.as_mut_ptr(), &mut count);
+            ret
+        };
+        if ret >= 0 {
+            groups.truncate(count as usize);
+            return Ok(groups);
+        }
+        if groups.len() >= 65536 {
+            return Err(invalid(format!("too many groups for user: {}", user.name)));
+        }
+        let len = (count as usize).max(groups.len() * 2);
+        groups.resize(len, 0);
+    }
+}
+
+/// 在子进程 exec 之前切换用户, 组和附加组并设置 umask, 切换用户时设置 `HOME`, `USER` 和 `LOGNAME`.
+///
+/// 必须在其他需要特权的 pre_exec 操作 (例如加入 cgroup, 设置负的 nice 值) 之后调用.
+/// 任务自己设置的同名环境变量优先.
+#[cfg(unix)]
+pub(crate) fn apply(
+    cmd: &mut Command,
+    identity: &Identity,
+    env_vars: &std::collections::HashMap<String, String>,
+    steps: &StepReporter,
+) -> crate::Result<()> {
+    if identity.is_empty() {
+        return Ok(());
+    }
+    let user = identity.user.as_deref().map(find_user).transpose()?;
+    // SAFETY: geteuid 总是成功.
+    let euid = unsafe { libc::geteuid() };
+    let switch = euid == 0 || identity.group.is_some() || !identity.groups.is_empty();
+    if euid != 0 && (switch || user.as_ref().is_some_and(|u| u.uid != euid)) {
+        return Err(invalid(
+            "running a task as another user or group requires the manager to run as root"
+                .to_string(),
+        ));
+    }
+    // 非 root 时只能是当前用户, 不需要切换, 只设置环境变量和 umask.
+    let gid = match (&identity.group, &user) {
+        (Some(group), _) => Some(find_group(group)?),
+        (None, Some(user)) => Some(user.gid),
+        (None, None) => None,
+    }
+    .filter(|_| switch);
+    let groups = if !identity.groups.is_empty() {
+        let mut groups = identity
+            .groups
+            .iter()
+            .map(|g| find_group(g))
+            .collect::<crate::Result<Vec<_>>>()?;
+        groups.extend(gid);
+        Some(groups)
+    } else if let Some(user) = user.as_ref().filter(|_| switch) {
+        let mut groups = user_groups(user)?;
+        if let Some(gid) = gid
+            && !groups.contains(&gid)
+        {
+            groups.push(gid);
+        }
+        Some(groups)
+    } else {
+        gid.map(|gid| vec![gid])
+    };
+
+    if let Some(user) = &user {
+        for (key, value) in [
+            ("HOME", user.home.as_os_str()),
+            ("USER", user.name.as_ref()),
+            ("LOGNAME", user.name.as_ref()),
+        ] {
+            if !env_vars.contains_key(key) {
+                cmd.env(key, value);
+            }
+        }
+    }
+
+    let uid = user.map(|u| u.uid).filter(|_| switch);
+    let umask = identity.umask;
+    let steps = steps.clone();
+    // SAFETY: 只调用了 async-signal-safe 的系统调用, 闭包中没有分配内存.
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(groups) = &groups {
+                #[cfg(target_os = "macos")]
+                let len = groups.len() as libc::c_int;
+                #[cfg(not(target_os = "macos"))]
+                let len = groups.len();
+                if libc::setgroups(len, groups.as_ptr()) == -1 {
+                    return Err(steps.fail(Step::Identity, std::io::Error::last_os_error()));
+                }
+            }
+            if let Some(gid) = gid
+                && libc::setgid(gid) == -1
+            {
+                return Err(steps.fail(Step::Identity, std::io::Error::last_os_error()));
+            }
+            if let Some(uid) = uid
+                && libc::setuid(uid) == -1
+            {
+                return Err(steps.fail(Step::Identity, std::io::Error::last_os_error()));
+            }
+            if let Some(umask) = umask {
+                libc::umask(umask as libc::mode_t);
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn apply(
+    _cmd: &mut Command,
+    identity: &Identity,
+    _env_vars: &std::collections::HashMap<String, String>,
+    _steps: &StepReporter,
+) -> crate::Result<()> {
+    if identity.is_empty() {
+        return Ok(());
+    }
+    Err(invalid(
+        "running a task as another user is only supported on Unix".to_string(),
+    ))
+}
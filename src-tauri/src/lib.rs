@@ -4,17 +4,41 @@ use crate::{
 };
 
 mod app_state;
+mod cast;
+mod cgroup;
 mod commands;
 mod config;
+mod environment;
 mod error;
+mod expand;
+mod health;
+mod hooks;
+mod identity;
+mod limits;
 mod log;
+mod notify;
+mod outcome;
+mod output;
+mod priority;
+mod process;
+mod progress;
+mod pty;
+mod resolve;
+mod restart;
+mod run;
+mod sandbox;
 mod schedule;
+mod setup;
+mod shell_env;
+mod stdin;
+mod success;
+mod suspension;
 mod task;
 mod tray;
 mod utils;
 
 use error::{Error, ErrorKind, Result};
-use tauri::{Manager, WindowEvent};
+use tauri::{Emitter, Manager, WindowEvent};
 use tracing::info;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -45,13 +69,29 @@ pub async fn run() {
             is_program_runnable,
             get_config,
             update_config,
+            refresh_shell_env,
+            list_environments,
+            save_environment,
+            remove_environment,
             exit,
             stop_task,
             pick_dir,
-            reorder_tasks
+            reorder_tasks,
+            list_task_runs,
+            get_run_output,
+            get_run_recording,
+            get_task_stats,
+            tail_task_output,
+            subscribe_task_output,
+            unsubscribe_task_output
         ])
         .setup(|app| {
             tray::init_tray(app)?;
+            // 所有任务的进度通过 `task-progress` 事件推送
+            let emitter = app.handle().clone();
+            app.state::<AppState>().subscribe_progress(move |event| {
+                emitter.emit("task-progress", event).ok();
+            });
             let handle = app.handle().clone();
             toggle_dock_icon(false);
             tokio::spawn(async move {
@@ -0,0 +1,121 @@
+//! 通过 setrlimit 限制任务进程的资源使用 (仅 Unix 下有效).
+
+use std::process::ExitStatus;
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+#[cfg(unix)]
+use crate::setup::Step;
+use crate::setup::StepReporter;
+
+/// 任务进程的资源限制, 为空表示继承管理器的限制.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct ResourceLimits {
+    /// 虚拟地址空间大小 (字节), `RLIMIT_AS`.
+    pub address_space: Option<u64>,
+    /// CPU 时间 (秒), `RLIMIT_CPU`, 超出时进程收到 SIGXCPU, 1 秒之后收到 SIGKILL.
+    pub cpu_seconds: Option<u64>,
+    /// 打开文件数量, `RLIMIT_NOFILE`.
+    pub open_files: Option<u64>,
+    /// 当前用户的进程数量, `RLIMIT_NPROC`.
+    pub processes: Option<u64>,
+    /// core dump 文件大小 (字节), `RLIMIT_CORE`, 为 0 时不生成 core dump.
+    pub core_size: Option<u64>,
+}
+
+impl ResourceLimits {
+    #[inline]
+    #[must_use]
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 可以从进程退出状态确定的超出限制的情况.
+///
+/// 超出其他限制时进程只会收到错误 (例如 EMFILE, EAGAIN, ENOMEM), 由程序自己决定如何退出,
+/// 无法可靠地归因到资源限制.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LimitViolation {
+    /// 超出 CPU 时间限制.
+    CpuTime,
+}
+
+impl LimitViolation {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            LimitViolation::CpuTime => "CpuTime",
+        }
+    }
+
+    pub(crate) fn parse(s: &str) -> Option<Self> {
+        match s {
+            "CpuTime" => Some(LimitViolation::CpuTime),
+            _ => None,
+        }
+    }
+}
+
+/// 在子进程 exec 之前设置资源限制.
+#[cfg(unix)]
+pub(crate) fn apply(cmd: &mut Command, limits: &ResourceLimits, steps: &StepReporter) {
+    if limits.is_empty() {
+        return;
+    }
+    let resources = [
+        (libc::RLIMIT_AS, limits.address_space, 0),
+        // 硬限制比软限制多 1 秒, 先发送可以被捕获的 SIGXCPU, 便于区分超出限制和被强制结束.
+        (libc::RLIMIT_CPU, limits.cpu_seconds, 1),
+        (libc::RLIMIT_NOFILE, limits.open_files, 0),
+        (libc::RLIMIT_NPROC, limits.processes, 0),
+        (libc::RLIMIT_CORE, limits.core_size, 0),
+    ];
+    let steps = steps.clone();
+    // SAFETY: 只调用了 async-signal-safe 的 setrlimit 和 write.
+    unsafe {
+        cmd.pre_exec(move || {
+            for (resource, value, extra) in resources {
+                let Some(value) = value else {
+                    continue;
+                };
+                let limit = libc::rlimit {
+                    rlim_cur: value as libc::rlim_t,
+                    rlim_max: value.saturating_add(extra) as libc::rlim_t,
+                };
+                if libc::setrlimit(resource, &limit) == -1 {
+                    return Err(steps.fail(Step::Limits, std::io::Error::last_os_error()));
+                }
+            }
+            Ok(())
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn apply(_cmd: &mut Command, _limits: &ResourceLimits, _steps: &StepReporter) {}
+
+/// 根据进程的退出状态判断是否超出了资源限制, 只用于不是由管理器结束的进程.
+///
+/// 进程忽略 SIGXCPU 时在硬限制处收到 SIGKILL. SIGKILL 也可能来自 OOM killer 或者其他进程,
+/// 无法区分, 设置了 CPU 时间限制时都按照超出 CPU 时间处理.
+#[must_use]
+pub(crate) fn violation(status: &ExitStatus, limits: &ResourceLimits) -> Option<LimitViolation> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        match status.signal() {
+            Some(libc::SIGXCPU | libc::SIGKILL) if limits.cpu_seconds.is_some() => {
+                Some(LimitViolation::CpuTime)
+            }
+            _ => None,
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (status, limits);
+        None
+    }
+}
@@ -0,0 +1,172 @@
+//! 兼容 systemd `sd_notify` 的就绪和看门狗协议.
+//!
+//! 子进程通过 `NOTIFY_SOCKET` 指定的 Unix datagram socket 发送 `READY=1`, `STATUS=...`,
+//! `WATCHDOG=1` 和 `STOPPING=1` 等消息.
+
+use std::{
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::{process::Command, sync::Notify, task::JoinHandle};
+
+/// 任务的 `sd_notify` 选项.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct NotifyOptions {
+    /// 看门狗超时时间 (毫秒), 超过这个时间没有收到 `WATCHDOG=1` 时结束进程, 为空表示不使用看门狗.
+    pub watchdog_ms: Option<u64>,
+}
+
+/// 子进程通过 `sd_notify` 报告的状态.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct NotifyState {
+    /// 收到了 `READY=1`.
+    pub(crate) ready: bool,
+    /// 最近一次 `STATUS=` 的内容.
+    pub(crate) status: Option<String>,
+    /// 收到了 `STOPPING=1`, 之后不再检查看门狗.
+    pub(crate) stopping: bool,
+}
+
+/// 一次运行的 notify socket, 被 drop 之后停止接收消息并删除 socket 文件.
+#[derive(Debug)]
+pub(crate) struct NotifyListener {
+    path: PathBuf,
+    state: Arc<Mutex<NotifyState>>,
+    /// 看门狗超时之后被通知.
+    expired: Arc<Notify>,
+    handle: JoinHandle<()>,
+}
+
+impl NotifyListener {
+    /// 在 `path` 创建 socket, 并设置子进程的 `NOTIFY_SOCKET` 和 `WATCHDOG_USEC` 环境变量.
+    pub(crate) fn bind(
+        cmd: &mut Command,
+        path: PathBuf,
+        options: &NotifyOptions,
+    ) -> crate::Result<Self> {
+        let listener = Self::rebind(path, options)?;
+        cmd.env("NOTIFY_SOCKET", &listener.path);
+        if let Some(watchdog_ms) = options.watchdog_ms {
+            cmd.env("WATCHDOG_USEC", (watchdog_ms * 1000).to_string());
+        }
+        Ok(listener)
+    }
+
+    /// 在 `path` 重新创建 socket, 用于管理器重启之后接管的进程, 进程仍然使用原来的 `NOTIFY_SOCKET`.
+    ///
+    /// 之前收到的状态不会恢复.
+    #[cfg(unix)]
+    pub(crate) fn rebind(path: PathBuf, options: &NotifyOptions) -> crate::Result<Self> {
+        let failed_to_bind = |e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Io,
+                format!("failed to bind notify socket: {}", path.display()),
+                Box::new(e),
+            )
+        };
+        // 上一次运行意外退出时可能留下了 socket 文件
+        std::fs::remove_file(&path).ok();
+        let socket = tokio::net::UnixDatagram::bind(&path).map_err(failed_to_bind)?;
+
+        let watchdog = options.watchdog_ms.map(std::time::Duration::from_millis);
+        let state = Arc::new(Mutex::new(NotifyState::default()));
+        let expired = Arc::new(Notify::new());
+        let handle = tokio::spawn(listen(socket, watchdog, state.clone(), expired.clone()));
+        Ok(Self {
+            path,
+            state,
+            expired,
+            handle,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn rebind(_path: PathBuf, _options: &NotifyOptions) -> crate::Result<Self> {
+        Err(crate::Error::with_message(
+            crate::ErrorKind::Io,
+            "sd_notify is not supported on this platform",
+        ))
+    }
+
+    #[must_use]
+    pub(crate) fn state(&self) -> NotifyState {
+        self.state.lock().unwrap().clone()
+    }
+
+    /// 等待看门狗超时, 返回的 future 不借用 `self`.
+    pub(crate) fn expired(&self) -> impl Future<Output = ()> + use<> {
+        let expired = self.expired.clone();
+        async move { expired.notified().await }
+    }
+}
+
+impl Drop for NotifyListener {
+    fn drop(&mut self) {
+        self.handle.abort();
+        std::fs::remove_file(&self.path).ok();
+    }
+}
+
+/// 接收消息并更新 `state`, 看门狗超时之后通知 `expired` 并停止检查看门狗.
+#[cfg(unix)]
+async fn listen(
+    socket: tokio::net::UnixDatagram,
+    mut watchdog: Option<std::time::Duration>,
+    state: Arc<Mutex<NotifyState>>,
+    expired: Arc<Notify>,
+) {
+    use tokio::time::Instant;
+
+    let mut deadline = watchdog.map(|d| Instant::now() + d);
+    let mut buf = vec![0u8; 4096];
+    loop {
+        let n = tokio::select! {
+            received = socket.recv(&mut buf) => match received {
+                Ok(n) => n,
+                Err(e) => {
+                    tracing::warn!("failed to receive notify message: {e:?}");
+                    return;
+                }
+            },
+            _ = async {
+                match deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline).await,
+                    None => std::future::pending().await,
+                }
+            } => {
+                deadline = None;
+                expired.notify_one();
+                continue;
+            }
+        };
+        let message = String::from_utf8_lossy(&buf[..n]);
+        let mut state = state.lock().unwrap();
+        for (key, value) in message.lines().filter_map(|l| l.split_once('=')) {
+            match (key, value) {
+                ("READY", "1") => state.ready = true,
+                ("STATUS", status) => state.status = Some(status.to_string()),
+                ("STOPPING", "1") => {
+                    state.stopping = true;
+                    deadline = None;
+                }
+                ("WATCHDOG", "1") if !state.stopping => {
+                    deadline = watchdog.map(|d| Instant::now() + d);
+                }
+                ("WATCHDOG", "trigger") if !state.stopping => {
+                    deadline = None;
+                    expired.notify_one();
+                }
+                ("WATCHDOG_USEC", usec) if !state.stopping => {
+                    if let Ok(usec) = usec.parse() {
+                        watchdog = Some(std::time::Duration::from_micros(usec));
+                        deadline = watchdog.map(|d| Instant::now() + d);
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+//! 一次运行的结束方式.
+
+use std::{fmt, process::ExitStatus};
+
+use serde::{Deserialize, Serialize};
+
+use crate::resolve::LaunchError;
+
+/// 运行的结束方式.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "tag", content = "content")]
+pub enum Outcome {
+    /// 进程自己退出.
+    Exited { code: i64 },
+    /// 进程被信号结束, 不是由管理器发送的信号, 例如 OOM killer (仅 Unix).
+    Signaled { signal: i32, core_dumped: bool },
+    /// 被用户结束, 包括停止, 禁用和删除任务, 管理器退出, 以及 `Replace` 并发策略.
+    Stopped,
+    /// `sd_notify` 看门狗超时而被结束.
+    TimedOut,
+    /// 健康检查连续失败而被结束.
+    Unhealthy,
+    /// 进程没有启动, `reason` 为失败的原因.
+    FailedToLaunch {
+        #[serde(default)]
+        kind: LaunchFailure,
+        reason: String,
+    },
+    /// 无法获取退出状态, 例如管理器重启之后接管的进程, 或者管理器在进程结束之前意外退出.
+    Unknown,
+}
+
+/// 启动失败的类型.
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LaunchFailure {
+    /// 找不到程序 (ENOENT).
+    NotFound,
+    /// 没有执行权限 (EACCES).
+    PermissionDenied,
+    /// 脚本的解释器不存在或者无法识别的可执行文件格式.
+    BadInterpreter,
+    /// exec 之前的设置步骤失败, 例如资源限制, 沙箱, cgroup 或者切换用户.
+    Setup,
+    /// 其他原因, 例如工作目录不存在.
+    #[default]
+    Other,
+}
+
+impl Outcome {
+    /// 根据进程的退出状态得到结束方式, `killed` 为管理器结束进程的原因, 优先于退出状态.
+    #[must_use]
+    pub(crate) fn new(status: Option<&ExitStatus>, killed: Option<Outcome>) -> Self {
+        if let Some(killed) = killed {
+            return killed;
+        }
+        let Some(status) = status else {
+            return Outcome::Unknown;
+        };
+        if let Some(code) = status.code() {
+            return Outcome::Exited {
+                code: i64::from(code),
+            };
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+
+            if let Some(signal) = status.signal() {
+                return Outcome::Signaled {
+                    signal,
+                    core_dumped: status.core_dumped(),
+                };
+            }
+        }
+        Outcome::Unknown
+    }
+
+    /// 启动失败的结束方式, 原因包含错误的整个来源链, 类型来自链中的 [`LaunchError`].
+    #[must_use]
+    pub(crate) fn launch_failure(e: &crate::Error) -> Self {
+        use std::error::Error;
+
+        let mut kind = LaunchFailure::Other;
+        let mut reason = e.to_string();
+        let mut source = e.source();
+        while let Some(s) = source {
+            if let Some(e) = s.downcast_ref::<LaunchError>() {
+                kind = e.failure;
+            }
+            reason.push_str(": ");
+            reason.push_str(&s.to_string());
+            source = s.source();
+        }
+        Outcome::FailedToLaunch { kind, reason }
+    }
+
+    /// 进程自己退出时的退出码.
+    #[inline]
+    #[must_use]
+    pub(crate) fn code(&self) -> Option<i64> {
+        match self {
+            Outcome::Exited { code } => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Exited { code } => write!(f, "exited with code {code}"),
+            Outcome::Signaled {
+                signal,
+                core_dumped,
+            } => {
+                write!(f, "killed by signal {signal}")?;
+                if *core_dumped {
+                    write!(f, " (core dumped)")?;
+                }
+                Ok(())
+            }
+            Outcome::Stopped => write!(f, "stopped by user"),
+            Outcome::TimedOut => write!(f, "timed out"),
+            Outcome::Unhealthy => write!(f, "killed after failing health checks"),
+            Outcome::FailedToLaunch { reason, .. } => write!(f, "failed to launch: {reason}"),
+            Outcome::Unknown => write!(f, "exit status unknown"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn new_from_exit_status() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let exited = ExitStatus::from_raw(3 << 8);
+        assert_eq!(
+            Outcome::new(Some(&exited), None),
+            Outcome::Exited { code: 3 }
+        );
+        assert_eq!(
+            Outcome::new(Some(&ExitStatus::from_raw(libc::SIGKILL)), None),
+            Outcome::Signaled {
+                signal: libc::SIGKILL,
+                core_dumped: false
+            }
+        );
+        // 管理器结束进程的原因优先于退出状态
+        assert_eq!(
+            Outcome::new(Some(&exited), Some(Outcome::Unhealthy)),
+            Outcome::Unhealthy
+        );
+        assert_eq!(Outcome::new(None, None), Outcome::Unknown);
+    }
+
+    #[test]
+    fn code_only_for_exited() {
+        assert_eq!(Outcome::Exited { code: 2 }.code(), Some(2));
+        assert_eq!(Outcome::Stopped.code(), None);
+        assert_eq!(Outcome::Unknown.code(), None);
+    }
+
+    #[test]
+    fn launch_failure_takes_kind_from_source_chain() {
+        let e = crate::Error::with_source(
+            crate::ErrorKind::Io,
+            "failed to run task program",
+            Box::new(LaunchError::new(
+                LaunchFailure::NotFound,
+                "program not found",
+                None,
+            )),
+        );
+        let Outcome::FailedToLaunch { kind, reason } = Outcome::launch_failure(&e) else {
+            panic!("not a launch failure");
+        };
+        assert_eq!(kind, LaunchFailure::NotFound);
+        assert!(reason.contains("failed to run task program"));
+        assert!(reason.contains("program not found"));
+
+        let other = crate::Error::with_message(crate::ErrorKind::Io, "no cgroup");
+        assert!(matches!(
+            Outcome::launch_failure(&other),
+            Outcome::FailedToLaunch {
+                kind: LaunchFailure::Other,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn serde_format() {
+        assert_eq!(
+            serde_json::to_string(&Outcome::Exited { code: 1 }).unwrap(),
+            r#"{"tag":"Exited","content":{"code":1}}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&Outcome::Stopped).unwrap(),
+            r#"{"tag":"Stopped"}"#
+        );
+        // 旧的记录没有启动失败的类型
+        let old: Outcome =
+            serde_json::from_str(r#"{"tag":"FailedToLaunch","content":{"reason":"x"}}"#).unwrap();
+        assert_eq!(
+            old,
+            Outcome::FailedToLaunch {
+                kind: LaunchFailure::Other,
+                reason: "x".to_string()
+            }
+        );
+    }
+}
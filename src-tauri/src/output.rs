@@ -0,0 +1,778 @@
+//! 任务标准输出/标准错误的重定向, 日志轮转与实时推送.
+
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, SeekFrom},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWriteExt, BufReader},
+    process::{Child, Command},
+    sync::{Mutex, broadcast, oneshot},
+    task::JoinHandle,
+    time::Instant,
+};
+use tracing::warn;
+
+use crate::{
+    cast::CastRecorder,
+    process::OnExit,
+    progress::{self, ProgressEvent, ProgressTracker},
+    pty,
+    setup::StepReporter,
+    success::OutputMatcher,
+    task::Task,
+};
+
+/// 单个输出流的重定向选项.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct OutputOptions {
+    /// 追加写入, 否则每次运行时清空文件.
+    pub append: bool,
+    /// 在每一行的开头添加时间戳.
+    pub timestamp: bool,
+    /// 日志轮转策略, 为空则不轮转.
+    pub rotation: Option<Rotation>,
+}
+
+impl OutputOptions {
+    /// 是否需要由管理器转发子进程的输出, 否则直接将文件交给子进程.
+    #[inline]
+    #[must_use]
+    pub(crate) fn needs_pump(&self) -> bool {
+        self.timestamp || self.rotation.is_some()
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Rotation {
+    pub policy: RotationPolicy,
+    /// 保留的历史文件数量, 历史文件以 `.1`, `.2`, ... 为后缀, 数字越大越旧.
+    pub keep: usize,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "tag", content = "content")]
+pub enum RotationPolicy {
+    /// 文件大小达到指定字节数时轮转.
+    Size(u64),
+    /// 每隔指定时间轮转一次.
+    Interval(Duration),
+}
+
+/// 支持轮转的输出文件.
+pub(crate) struct RotatingFile {
+    path: PathBuf,
+    file: tokio::fs::File,
+    rotation: Option<Rotation>,
+    written: u64,
+    opened_at: Instant,
+}
+
+fn failed_to_open(path: &Path, e: io::Error) -> crate::Error {
+    crate::Error::with_source(
+        crate::ErrorKind::Io,
+        format!("failed to open output file: {}", path.display()),
+        Box::new(e),
+    )
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut s = OsString::from(path.as_os_str());
+    s.push(format!(".{n}"));
+    s.into()
+}
+
+impl RotatingFile {
+    /// 打开输出文件, 当不追加写入且设置了轮转时, 上一次运行的输出会被轮转为历史文件而不是被清空.
+    pub(crate) async fn open(
+        path: impl Into<PathBuf>,
+        append: bool,
+        rotation: Option<Rotation>,
+    ) -> crate::Result<Self> {
+        let path = path.into();
+        if !append
+            && let Some(rotation) = &rotation
+            && tokio::fs::metadata(&path).await.is_ok_and(|m| m.len() > 0)
+        {
+            Self::shift(&path, rotation.keep).await?;
+        }
+        let file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .append(append)
+            .truncate(!append)
+            .open(&path)
+            .await
+            .map_err(|e| failed_to_open(&path, e))?;
+        let written = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+        Ok(Self {
+            path,
+            file,
+            rotation,
+            written,
+            opened_at: Instant::now(),
+        })
+    }
+
+    /// 将 `path` 重命名为 `path.1`, 已有的历史文件依次后移, 超出 `keep` 的历史文件会被删除.
+    async fn shift(path: &Path, keep: usize) -> crate::Result<()> {
+        let failed_to_rotate = |e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Io,
+                format!("failed to rotate output file: {}", path.display()),
+                Box::new(e),
+            )
+        };
+        if keep == 0 {
+            return match tokio::fs::remove_file(path).await {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(failed_to_rotate(e)),
+                _ => Ok(()),
+            };
+        }
+        match tokio::fs::remove_file(rotated_path(path, keep)).await {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(failed_to_rotate(e)),
+            _ => (),
+        }
+        for n in (1..keep).rev() {
+            let from = rotated_path(path, n);
+            if tokio::fs::try_exists(&from).await.unwrap_or(false) {
+                tokio::fs::rename(&from, rotated_path(path, n + 1))
+                    .await
+                    .map_err(failed_to_rotate)?;
+            }
+        }
+        tokio::fs::rename(path, rotated_path(path, 1))
+            .await
+            .map_err(failed_to_rotate)
+    }
+
+    fn should_rotate(&self, incoming: u64) -> bool {
+        match self.rotation.as_ref().map(|r| &r.policy) {
+            Some(RotationPolicy::Size(max)) => self.written > 0 && self.written + incoming > *max,
+            Some(RotationPolicy::Interval(d)) => self.opened_at.elapsed() >= *d,
+            None => false,
+        }
+    }
+
+    async fn rotate(&mut self) -> crate::Result<()> {
+        let keep = self.rotation.as_ref().map(|r| r.keep).unwrap_or_default();
+        self.file.flush().await.ok();
+        Self::shift(&self.path, keep).await?;
+        self.file = tokio::fs::File::create(&self.path)
+            .await
+            .map_err(|e| failed_to_open(&self.path, e))?;
+        self.written = 0;
+        self.opened_at = Instant::now();
+        Ok(())
+    }
+
+    /// 写入一行输出, 写入之前按需轮转.
+    pub(crate) async fn write_line(&mut self, line: &[u8]) -> crate::Result<()> {
+        if self.should_rotate(line.len() as u64) {
+            self.rotate().await?;
+        }
+        let failed_to_write = |e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Io,
+                format!("failed to write output file: {}", self.path.display()),
+                Box::new(e),
+            )
+        };
+        self.file.write_all(line).await.map_err(failed_to_write)?;
+        // 及时刷新, 便于外部 tail 文件.
+        self.file.flush().await.map_err(failed_to_write)?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+}
+
+type SharedFile = Arc<Mutex<RotatingFile>>;
+
+/// 输出流.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// 任务输出中的一行, 用于实时推送.
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct OutputLine {
+    pub(crate) task_id: i64,
+    pub(crate) run_id: i64,
+    pub(crate) stream: Stream,
+    /// 不包含行尾换行符, 非 UTF-8 字符会被替换.
+    pub(crate) line: String,
+}
+
+/// 广播所有任务的实时输出和输出中的进度报告.
+#[derive(Clone, Debug)]
+pub(crate) struct OutputSender {
+    lines: broadcast::Sender<OutputLine>,
+    progress: broadcast::Sender<ProgressEvent>,
+}
+
+impl OutputSender {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            lines: broadcast::channel(capacity).0,
+            progress: broadcast::channel(capacity).0,
+        }
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<OutputLine> {
+        self.lines.subscribe()
+    }
+
+    pub(crate) fn subscribe_progress(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.progress.subscribe()
+    }
+}
+
+/// 将一次运行的输出逐行广播给订阅者, 交给 `matcher` 检查, 并解析其中的进度报告.
+#[derive(Clone)]
+pub(crate) struct OutputTap {
+    tx: OutputSender,
+    task_id: i64,
+    run_id: i64,
+    matcher: Option<Arc<OutputMatcher>>,
+    progress: ProgressTracker,
+}
+
+impl OutputTap {
+    pub(crate) fn new(
+        tx: OutputSender,
+        task_id: i64,
+        run_id: i64,
+        matcher: Option<Arc<OutputMatcher>>,
+        progress: ProgressTracker,
+    ) -> Self {
+        Self {
+            tx,
+            task_id,
+            run_id,
+            matcher,
+            progress,
+        }
+    }
+
+    /// 处理一行进度报告, 是有效的报告时更新本次运行的进度并广播.
+    pub(crate) fn report(&self, line: &str) {
+        if let Some(progress) = self.progress.feed(line) {
+            self.tx
+                .progress
+                .send(ProgressEvent {
+                    task_id: self.task_id,
+                    run_id: self.run_id,
+                    progress,
+                })
+                .ok();
+        }
+    }
+
+    fn send(&self, stream: Stream, line: &[u8]) {
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        let is_report = line.starts_with(progress::PREFIX.as_bytes());
+        if self.tx.lines.receiver_count() == 0 && self.matcher.is_none() && !is_report {
+            return;
+        }
+        let line = String::from_utf8_lossy(line).into_owned();
+        if is_report {
+            self.report(&line);
+        }
+        if let Some(matcher) = &self.matcher {
+            matcher.feed(&line);
+        }
+        // 没有订阅者时发送失败, 忽略即可.
+        self.tx
+            .lines
+            .send(OutputLine {
+                task_id: self.task_id,
+                run_id: self.run_id,
+                stream,
+                line,
+            })
+            .ok();
+    }
+}
+
+/// 需要由管理器转发的输出流.
+#[derive(Clone)]
+struct Pump {
+    file: SharedFile,
+    timestamp: bool,
+}
+
+/// 直接交给子进程的输出文件, 通过轮询文件内容实时推送.
+struct Tail {
+    path: PathBuf,
+    start: u64,
+    stream: Stream,
+}
+
+/// 在子进程启动之后, 将管道中的输出转发到文件, 并实时推送所有输出.
+pub(crate) struct OutputPumps {
+    stdout: Option<Pump>,
+    stderr: Option<Pump>,
+    tails: Vec<Tail>,
+    tap: OutputTap,
+    /// 伪终端模式下的主设备, 子进程的所有输出都从这里读取并转发到 `stdout`.
+    pty: Option<tokio::fs::File>,
+    /// 伪终端会话的录制器.
+    recorder: Option<CastRecorder>,
+}
+
+/// 子进程运行期间持有, 被 drop 之后停止轮询输出文件.
+#[derive(Debug)]
+pub(crate) struct TailGuard {
+    stop: Option<oneshot::Sender<()>>,
+    /// 转发和轮询输出的后台任务.
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl TailGuard {
+    /// 停止轮询输出文件, 等待已经输出的内容处理完毕.
+    ///
+    /// 后台进程可能继承了输出管道, 最多等待 `timeout`.
+    pub(crate) async fn finish(&mut self, timeout: Duration) {
+        self.stop = None;
+        let handles = std::mem::take(&mut self.handles);
+        let join = async {
+            for handle in handles {
+                handle.await.ok();
+            }
+        };
+        tokio::time::timeout(timeout, join).await.ok();
+    }
+}
+
+/// 打开直接交给子进程的输出文件, 返回文件和打开后的文件长度.
+fn open_direct(path: &Path, append: bool) -> crate::Result<(File, u64)> {
+    let file = File::options()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(path)
+        .map_err(|e| failed_to_open(path, e))?;
+    let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+    Ok((file, len))
+}
+
+fn try_clone(file: &File, path: &Path) -> crate::Result<File> {
+    file.try_clone().map_err(|e| failed_to_open(path, e))
+}
+
+/// 任务是否有没有设置重定向文件的输出流, 这些输出流需要被自动捕获.
+#[must_use]
+pub(crate) fn needs_capture(task: &Task) -> bool {
+    if task.pty.is_some() {
+        // 伪终端只有一个输出流
+        return task.stdout.is_none();
+    }
+    task.stdout.is_none() || (task.stderr.is_none() && !task.merge_stderr)
+}
+
+/// 根据任务的输出选项设置子进程的标准输出和标准错误.
+///
+/// 不需要额外处理的流直接重定向到文件, 需要添加时间戳或者轮转的流设置为管道,
+/// 在进程启动之后调用 [`OutputPumps::spawn`] 转发.
+/// 没有设置重定向文件的流会被捕获到 `capture` 文件中, 为空则继承管理器的标准输出/标准错误.
+///
+/// 伪终端模式下标准输入, 标准输出和标准错误都连接到伪终端, 输出按照标准输出的设置处理,
+/// `stderr` 相关的设置被忽略, 标准输入需要通过 [`OutputPumps::pty_input`] 写入. 如果设置了 `recording`, 终端会话会被录制到该文件中.
+///
+/// 管理器退出之后继续运行的任务 ([`OnExit::Detach`]) 见 [`redirect_detached`].
+pub(crate) async fn redirect(
+    cmd: &mut Command,
+    task: &Task,
+    capture: Option<&Path>,
+    recording: Option<&Path>,
+    tap: OutputTap,
+    steps: &StepReporter,
+) -> crate::Result<OutputPumps> {
+    let mut pumps = OutputPumps {
+        stdout: None,
+        stderr: None,
+        tails: Vec::new(),
+        tap,
+        pty: None,
+        recorder: None,
+    };
+    if task.on_exit == OnExit::Detach {
+        redirect_detached(cmd, task, capture, &mut pumps)?;
+        return Ok(pumps);
+    }
+    let capture = match capture {
+        Some(path) => Some(Pump {
+            file: Arc::new(Mutex::new(RotatingFile::open(path, false, None).await?)),
+            timestamp: false,
+        }),
+        None => None,
+    };
+
+    if let Some(pty) = &task.pty {
+        let master = pty::attach(cmd, pty, steps)?;
+        // 让程序输出颜色等终端控制序列
+        let term = match task.env_vars.get("TERM") {
+            Some(term) => term.as_str(),
+            None => {
+                cmd.env("TERM", "xterm-256color");
+                "xterm-256color"
+            }
+        };
+        if let Some(recording) = recording {
+            pumps.recorder = Some(CastRecorder::create(recording, pty, &task.name, term).await?);
+        }
+        pumps.stdout = match &task.stdout {
+            Some(stdout) => {
+                let opts = &task.stdout_options;
+                let file = RotatingFile::open(stdout, opts.append, opts.rotation.clone()).await?;
+                Some(Pump {
+                    file: Arc::new(Mutex::new(file)),
+                    timestamp: opts.timestamp,
+                })
+            }
+            None => capture,
+        };
+        pumps.pty = Some(tokio::fs::File::from_std(master));
+        return Ok(pumps);
+    }
+
+    let mut stdout_direct = None;
+    if let Some(stdout) = &task.stdout {
+        let opts = &task.stdout_options;
+        if opts.needs_pump() {
+            let file = RotatingFile::open(stdout, opts.append, opts.rotation.clone()).await?;
+            pumps.stdout = Some(Pump {
+                file: Arc::new(Mutex::new(file)),
+                timestamp: opts.timestamp,
+            });
+            cmd.stdout(Stdio::piped());
+        } else {
+            let (file, start) = open_direct(stdout, opts.append)?;
+            cmd.stdout(try_clone(&file, stdout)?);
+            pumps.tails.push(Tail {
+                path: stdout.clone(),
+                start,
+                stream: Stream::Stdout,
+            });
+            stdout_direct = Some(file);
+        }
+    } else if let Some(capture) = &capture {
+        pumps.stdout = Some(capture.clone());
+        cmd.stdout(Stdio::piped());
+    }
+
+    if task.merge_stderr {
+        // 合并到标准输出, 忽略标准错误自身的设置.
+        if let Some(pump) = &pumps.stdout {
+            pumps.stderr = Some(pump.clone());
+            cmd.stderr(Stdio::piped());
+        } else if let (Some(file), Some(stdout)) = (&stdout_direct, &task.stdout) {
+            cmd.stderr(try_clone(file, stdout)?);
+        }
+    } else if let Some(stderr) = &task.stderr {
+        let opts = &task.stderr_options;
+        if opts.needs_pump() {
+            let file = RotatingFile::open(stderr, opts.append, opts.rotation.clone()).await?;
+            pumps.stderr = Some(Pump {
+                file: Arc::new(Mutex::new(file)),
+                timestamp: opts.timestamp,
+            });
+            cmd.stderr(Stdio::piped());
+        } else {
+            let (file, start) = open_direct(stderr, opts.append)?;
+            cmd.stderr(file);
+            pumps.tails.push(Tail {
+                path: stderr.clone(),
+                start,
+                stream: Stream::Stderr,
+            });
+        }
+    } else if let Some(capture) = capture {
+        pumps.stderr = Some(capture);
+        cmd.stderr(Stdio::piped());
+    }
+
+    Ok(pumps)
+}
+
+/// 管理器退出之后继续运行的任务的输出不能经过管理器, 所有输出流都直接重定向到文件.
+///
+/// 保存任务时已经拒绝了伪终端, 时间戳和轮转设置.
+///
+/// 捕获到 `capture` 中的输出都作为标准输出推送.
+fn redirect_detached(
+    cmd: &mut Command,
+    task: &Task,
+    capture: Option<&Path>,
+    pumps: &mut OutputPumps,
+) -> crate::Result<()> {
+    let mut open = |path: &Path, append: bool, stream: Stream| -> crate::Result<File> {
+        let (file, start) = open_direct(path, append)?;
+        pumps.tails.push(Tail {
+            path: path.to_path_buf(),
+            start,
+            stream,
+        });
+        Ok(file)
+    };
+    let capture = match capture {
+        Some(path) => Some((open(path, false, Stream::Stdout)?, path)),
+        None => None,
+    };
+    let stdout = match &task.stdout {
+        Some(path) => Some((
+            open(path, task.stdout_options.append, Stream::Stdout)?,
+            path.as_path(),
+        )),
+        None => match &capture {
+            Some((file, path)) => Some((try_clone(file, path)?, *path)),
+            None => None,
+        },
+    };
+    let stderr = if task.merge_stderr {
+        match &stdout {
+            Some((file, path)) => Some(try_clone(file, path)?),
+            None => None,
+        }
+    } else {
+        match &task.stderr {
+            Some(path) => Some(open(path, task.stderr_options.append, Stream::Stderr)?),
+            None => capture.map(|(file, _)| file),
+        }
+    };
+    // 管理器退出之后标准输出/标准错误不能继承自管理器
+    match stdout {
+        Some((file, _)) => cmd.stdout(file),
+        None => cmd.stdout(Stdio::null()),
+    };
+    match stderr {
+        Some(file) => cmd.stderr(file),
+        None => cmd.stderr(Stdio::null()),
+    };
+    Ok(())
+}
+
+/// 继续推送管理器重启之前启动的进程的输出, 从输出文件当前的末尾开始.
+///
+/// 进程的输出按照 [`redirect_detached`] 重定向, `capture` 为运行记录的输出文件.
+pub(crate) async fn follow(task: &Task, capture: Option<&Path>, tap: OutputTap) -> TailGuard {
+    let mut paths = vec![(task.stdout.as_deref().or(capture), Stream::Stdout)];
+    if !task.merge_stderr {
+        paths.push((task.stderr.as_deref(), Stream::Stderr));
+    }
+    let mut tails = Vec::new();
+    for (path, stream) in paths {
+        let Some(path) = path else {
+            continue;
+        };
+        let start = tokio::fs::metadata(path)
+            .await
+            .map(|m| m.len())
+            .unwrap_or(0);
+        tails.push(Tail {
+            path: path.to_path_buf(),
+            start,
+            stream,
+        });
+    }
+    let (stop_tx, stop_rx) = oneshot::channel();
+    let mut handles = Vec::new();
+    if !tails.is_empty() {
+        handles.push(tokio::spawn(tail(tails, tap, stop_rx)));
+    }
+    TailGuard {
+        stop: Some(stop_tx),
+        handles,
+    }
+}
+
+impl OutputPumps {
+    /// 伪终端模式下返回可以写入子进程输入的主设备.
+    pub(crate) async fn pty_input(&self) -> crate::Result<Option<tokio::fs::File>> {
+        match &self.pty {
+            Some(master) => master.try_clone().await.map(Some).map_err(|e| {
+                crate::Error::with_source(crate::ErrorKind::Io, "failed to open pty", Box::new(e))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    /// 开始转发子进程的输出, 子进程关闭管道之后转发自动结束.
+    pub(crate) fn spawn(self, child: &mut Child) -> TailGuard {
+        let mut handles = Vec::new();
+        if let Some(master) = self.pty {
+            // 即使输出没有目标文件也需要读取, 否则子进程会在终端缓冲区满时阻塞.
+            handles.push(tokio::spawn(forward_pty(
+                master,
+                self.stdout,
+                self.tap.clone(),
+                self.recorder,
+            )));
+        } else if let Some(pump) = self.stdout
+            && let Some(out) = child.stdout.take()
+        {
+            handles.push(tokio::spawn(forward(
+                out,
+                pump,
+                self.tap.clone(),
+                Stream::Stdout,
+            )));
+        }
+        if let Some(pump) = self.stderr
+            && let Some(err) = child.stderr.take()
+        {
+            handles.push(tokio::spawn(forward(
+                err,
+                pump,
+                self.tap.clone(),
+                Stream::Stderr,
+            )));
+        }
+        let (stop_tx, stop_rx) = oneshot::channel();
+        if !self.tails.is_empty() {
+            handles.push(tokio::spawn(tail(self.tails, self.tap, stop_rx)));
+        }
+        TailGuard {
+            stop: Some(stop_tx),
+            handles,
+        }
+    }
+}
+
+async fn forward(reader: impl AsyncRead + Unpin, pump: Pump, tap: OutputTap, stream: Stream) {
+    let mut reader = BufReader::new(reader);
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        match reader.read_until(b'\n', &mut buf).await {
+            Ok(0) => break,
+            Ok(_) => (),
+            Err(e) => {
+                warn!("failed to read task output: {e:?}");
+                break;
+            }
+        }
+        emit_line(Some(&pump), &tap, stream, &buf).await;
+    }
+}
+
+/// 推送一行输出, 并写入输出文件.
+async fn emit_line(pump: Option<&Pump>, tap: &OutputTap, stream: Stream, buf: &[u8]) {
+    tap.send(stream, buf);
+    let Some(pump) = pump else {
+        return;
+    };
+    let result = if pump.timestamp {
+        let mut line = chrono::Local::now()
+            .format("[%Y-%m-%d %H:%M:%S%.3f] ")
+            .to_string()
+            .into_bytes();
+        line.extend_from_slice(buf);
+        pump.file.lock().await.write_line(&line).await
+    } else {
+        pump.file.lock().await.write_line(buf).await
+    };
+    if let Err(e) = result {
+        warn!("failed to write task output: {e:?}");
+    }
+}
+
+/// 转发伪终端主设备的输出.
+///
+/// 按读取到的原始数据块录制, 保留不以换行结尾的输出 (例如进度条) 的时间信息,
+/// 写入文件和推送仍然按行进行.
+async fn forward_pty(
+    mut master: tokio::fs::File,
+    pump: Option<Pump>,
+    tap: OutputTap,
+    mut recorder: Option<CastRecorder>,
+) {
+    let mut chunk = vec![0u8; 8192];
+    let mut buf = Vec::new();
+    loop {
+        let n = match master.read(&mut chunk).await {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if pty::is_closed(&e) => break,
+            Err(e) => {
+                warn!("failed to read task output: {e:?}");
+                break;
+            }
+        };
+        if let Some(r) = &mut recorder
+            && let Err(e) = r.output(&chunk[..n]).await
+        {
+            warn!("failed to record task output: {e:?}");
+            recorder = None;
+        }
+        buf.extend_from_slice(&chunk[..n]);
+        while let Some(pos) = buf.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buf.drain(..=pos).collect();
+            emit_line(pump.as_ref(), &tap, Stream::Stdout, &line).await;
+        }
+    }
+    if !buf.is_empty() {
+        emit_line(pump.as_ref(), &tap, Stream::Stdout, &buf).await;
+    }
+    if let Some(r) = recorder
+        && let Err(e) = r.finish().await
+    {
+        warn!("failed to record task output: {e:?}");
+    }
+}
+
+/// 轮询输出文件, 推送新增的完整行, `stop` 被关闭之后推送剩余内容并退出.
+async fn tail(tails: Vec<Tail>, tap: OutputTap, mut stop: oneshot::Receiver<()>) {
+    const POLL_INTERVAL: Duration = Duration::from_millis(300);
+
+    let mut readers = Vec::new();
+    for t in tails {
+        match tokio::fs::File::open(&t.path).await {
+            Ok(mut file) => {
+                if file.seek(SeekFrom::Start(t.start)).await.is_ok() {
+                    readers.push((BufReader::new(file), Vec::new(), t.stream));
+                }
+            }
+            Err(e) => warn!("failed to tail output file {}: {e:?}", t.path.display()),
+        }
+    }
+    if readers.is_empty() {
+        return;
+    }
+
+    loop {
+        let stopped = tokio::select! {
+            _ = &mut stop => true,
+            _ = tokio::time::sleep(POLL_INTERVAL) => false,
+        };
+        for (reader, buf, stream) in &mut readers {
+            // 读到文件末尾时, 不完整的行保留在 buf 中等待下一次读取.
+            while let Ok(n) = reader.read_until(b'\n', buf).await
+                && n > 0
+            {
+                if buf.ends_with(b"\n") {
+                    tap.send(*stream, buf);
+                    buf.clear();
+                }
+            }
+            if stopped && !buf.is_empty() {
+                tap.send(*stream, buf);
+            }
+        }
+        if stopped {
+            break;
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! 任务进程的 CPU/IO 调度优先级和 CPU 亲和性.
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+#[cfg(unix)]
+use crate::setup::Step;
+use crate::setup::StepReporter;
+
+/// 调度选项, 为空表示继承管理器的设置.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct Priority {
+    /// nice 值, -20 (最高) 到 19 (最低), 负值需要特权 (仅 Unix 下有效).
+    pub nice: Option<i32>,
+    /// IO 调度类别 (仅 Linux 下有效).
+    pub io_class: Option<IoClass>,
+    /// 允许运行的 CPU 编号, 为空表示不限制 (仅 Linux 下有效).
+    pub cpu_affinity: Vec<usize>,
+}
+
+impl Priority {
+    #[inline]
+    #[must_use]
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// IO 调度类别, 参考 ioprio_set(2).
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "tag", content = "content")]
+pub enum IoClass {
+    /// 实时, 优先级 0 (最高) 到 7, 需要特权.
+    RealTime(u8),
+    /// 尽力而为, 优先级 0 (最高) 到 7.
+    BestEffort(u8),
+    /// 只在磁盘空闲时进行 IO.
+    Idle,
+}
+
+#[cfg(target_os = "linux")]
+impl IoClass {
+    /// ioprio_set(2) 的 ioprio 参数.
+    fn ioprio(self) -> libc::c_int {
+        const IOPRIO_CLASS_SHIFT: libc::c_int = 13;
+        let (class, level) = match self {
+            IoClass::RealTime(level) => (1, level.min(7)),
+            IoClass::BestEffort(level) => (2, level.min(7)),
+            IoClass::Idle => (3, 0),
+        };
+        (class << IOPRIO_CLASS_SHIFT) | libc::c_int::from(level)
+    }
+}
+
+/// 在子进程 exec 之前设置调度优先级和 CPU 亲和性, 设置失败时进程启动失败.
+#[cfg(unix)]
+pub(crate) fn apply(
+    cmd: &mut Command,
+    priority: &Priority,
+    steps: &StepReporter,
+) -> crate::Result<()> {
+    if priority.is_empty() {
+        return Ok(());
+    }
+    let nice = priority.nice;
+
+    #[cfg(target_os = "linux")]
+    let (ioprio, affinity) = {
+        let affinity = if priority.cpu_affinity.is_empty() {
+            None
+        } else {
+            // SAFETY: cpu_set_t 是普通的位图, 全 0 是合法的值.
+            let mut set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+            for &cpu in &priority.cpu_affinity {
+                if cpu >= libc::CPU_SETSIZE as usize {
+                    return Err(crate::Error::with_message(
+                        crate::ErrorKind::Io,
+                        format!("invalid cpu number in affinity: {cpu}"),
+                    ));
+                }
+                // SAFETY: 上面已经检查了 cpu 编号不超过 cpu_set_t 的大小.
+                unsafe { libc::CPU_SET(cpu, &mut set) };
+            }
+            Some(set)
+        };
+        (priority.io_class.map(IoClass::ioprio), affinity)
+    };
+
+    let steps = steps.clone();
+    // SAFETY: 只调用了 async-signal-safe 的系统调用.
+    unsafe {
+        cmd.pre_exec(move || {
+            if let Some(nice) = nice
+                && libc::setpriority(libc::PRIO_PROCESS, 0, nice) == -1
+            {
+                return Err(steps.fail(Step::Priority, std::io::Error::last_os_error()));
+            }
+            #[cfg(target_os = "linux")]
+            {
+                const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+                if let Some(ioprio) = ioprio
+                    && libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio) == -1
+                {
+                    return Err(steps.fail(Step::Priority, std::io::Error::last_os_error()));
+                }
+                if let Some(set) = &affinity
+                    && libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), set) == -1
+                {
+                    return Err(steps.fail(Step::Priority, std::io::Error::last_os_error()));
+                }
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn apply(
+    _cmd: &mut Command,
+    _priority: &Priority,
+    _steps: &StepReporter,
+) -> crate::Result<()> {
+    Ok(())
+}
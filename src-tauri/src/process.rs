@@ -0,0 +1,192 @@
+//! 任务进程, 由管理器启动, 或者在管理器重启之后接管上一次留下的进程.
+
+use std::process::ExitStatus;
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Child;
+
+/// 管理器退出时如何处理正在运行的进程.
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnExit {
+    /// 结束进程.
+    #[default]
+    Kill,
+    /// 保持运行, 下一次启动时接管.
+    ///
+    /// 进程的输出不经过管理器, 直接写入文件, 时间戳, 日志轮转和伪终端不可用.
+    Detach,
+}
+
+/// 用于在管理器重启之后确认进程没有被替换: 进程 ID 可能被复用, 重启系统之后启动时间会重新计算.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ProcessIdentity {
+    pub(crate) pid: u32,
+    /// 进程启动的时间, `/proc/<pid>/stat` 中系统启动之后的 clock ticks.
+    pub(crate) start_time: Option<i64>,
+    /// 进程所在的系统的 boot id.
+    pub(crate) boot_id: Option<String>,
+}
+
+impl ProcessIdentity {
+    /// 读取刚刚启动的进程的信息.
+    #[must_use]
+    pub(crate) fn of(pid: u32) -> Self {
+        Self {
+            pid,
+            start_time: start_time(pid),
+            boot_id: boot_id(),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn boot_id() -> Option<String> {
+    std::fs::read_to_string("/proc/sys/kernel/random/boot_id")
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn boot_id() -> Option<String> {
+    None
+}
+
+/// 读取进程的启动时间, 进程不存在或者已经退出 (僵尸进程) 时返回 `None`.
+#[cfg(target_os = "linux")]
+fn start_time(pid: u32) -> Option<i64> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // 进程名可能包含空格和括号, 从最后一个 ')' 之后开始解析, 之后依次是 state, ..., 第 20 个字段是 starttime.
+    let (_, rest) = stat.rsplit_once(')')?;
+    let mut fields = rest.split_whitespace();
+    if fields.next()? == "Z" {
+        return None;
+    }
+    fields.nth(18)?.parse().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn start_time(_pid: u32) -> Option<i64> {
+    None
+}
+
+/// 管理器重启之后接管的进程, 通过 pidfd 监听退出.
+#[derive(Debug)]
+pub(crate) struct Adopted {
+    #[cfg(target_os = "linux")]
+    pidfd: tokio::io::unix::AsyncFd<std::os::fd::OwnedFd>,
+}
+
+impl Adopted {
+    /// 接管 `identity` 对应的进程, 进程已经退出或者被替换时返回错误.
+    #[cfg(target_os = "linux")]
+    pub(crate) fn open(identity: &ProcessIdentity) -> crate::Result<Self> {
+        use std::os::fd::{FromRawFd, OwnedFd};
+
+        use tokio::io::{Interest, unix::AsyncFd};
+
+        let not_found = || {
+            crate::Error::with_message(
+                crate::ErrorKind::Io,
+                format!("process {} is no longer running", identity.pid),
+            )
+        };
+        if identity.boot_id.is_none() || identity.boot_id != boot_id() {
+            return Err(not_found());
+        }
+        // SAFETY: pidfd_open 没有内存安全方面的要求.
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, identity.pid as libc::pid_t, 0) };
+        if fd == -1 {
+            return Err(crate::Error::with_source(
+                crate::ErrorKind::Io,
+                format!("failed to open pidfd of process {}", identity.pid),
+                Box::new(std::io::Error::last_os_error()),
+            ));
+        }
+        // SAFETY: fd 是 pidfd_open 刚刚返回的有效文件描述符.
+        let pidfd = unsafe { OwnedFd::from_raw_fd(fd as i32) };
+        // 打开 pidfd 之后再检查启动时间, 之后进程 ID 不会再被复用.
+        if identity.start_time.is_none() || start_time(identity.pid) != identity.start_time {
+            return Err(not_found());
+        }
+        let pidfd = AsyncFd::with_interest(pidfd, Interest::READABLE).map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Io,
+                format!("failed to watch process {}", identity.pid),
+                Box::new(e),
+            )
+        })?;
+        Ok(Self { pidfd })
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn open(_identity: &ProcessIdentity) -> crate::Result<Self> {
+        Err(crate::Error::with_message(
+            crate::ErrorKind::Io,
+            "adopting processes is only supported on Linux",
+        ))
+    }
+
+    /// 等待进程退出, pidfd 在进程退出之后变为可读.
+    async fn wait(&self) -> std::io::Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            self.pidfd.readable().await?.retain_ready();
+            Ok(())
+        }
+        #[cfg(not(target_os = "linux"))]
+        std::future::pending().await
+    }
+
+    fn kill(&self) -> std::io::Result<()> {
+        #[cfg(target_os = "linux")]
+        {
+            use std::os::fd::AsRawFd;
+
+            // SAFETY: pidfd_send_signal 没有内存安全方面的要求.
+            let ret = unsafe {
+                libc::syscall(
+                    libc::SYS_pidfd_send_signal,
+                    self.pidfd.as_raw_fd(),
+                    libc::SIGKILL,
+                    std::ptr::null::<libc::siginfo_t>(),
+                    0,
+                )
+            };
+            if ret == -1 {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        }
+        #[cfg(not(target_os = "linux"))]
+        Ok(())
+    }
+}
+
+/// 正在运行的任务进程.
+#[derive(Debug)]
+pub(crate) enum Process {
+    /// 由管理器启动的子进程.
+    Child(Child),
+    /// 管理器重启之后接管的进程, 不是管理器的子进程, 无法获取退出状态.
+    Adopted(Adopted),
+}
+
+impl Process {
+    /// 等待进程退出, 接管的进程返回 `None`.
+    pub(crate) async fn wait(&mut self) -> std::io::Result<Option<ExitStatus>> {
+        match self {
+            Process::Child(child) => child.wait().await.map(Some),
+            Process::Adopted(adopted) => adopted.wait().await.map(|()| None),
+        }
+    }
+
+    pub(crate) async fn kill(&mut self) -> std::io::Result<()> {
+        match self {
+            Process::Child(child) => child.kill().await,
+            Process::Adopted(adopted) => {
+                adopted.kill()?;
+                adopted.wait().await
+            }
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! 任务通过输出报告进度和状态的行协议.
+//!
+//! 任务在标准输出, 标准错误或者 [`Task::progress_fd`] 指定的文件描述符中打印:
+//!
+//! - `::bgm progress 42/100`, `::bgm progress 42%` 或者 `::bgm progress 42` (总数未知)
+//! - `::bgm status Uploading batch 3`, 没有内容时清除状态
+//!
+//! 其他以 `::bgm ` 开头的行作为普通输出处理.
+//!
+//! [`Task::progress_fd`]: crate::task::Task::progress_fd
+
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tokio::process::Command;
+
+#[cfg(unix)]
+use crate::setup::Step;
+use crate::{output::OutputTap, setup::StepReporter};
+
+/// 报告行的前缀.
+pub(crate) const PREFIX: &str = "::bgm ";
+
+/// 任务报告的最新进度和状态.
+#[derive(Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Progress {
+    /// 已完成的数量.
+    pub(crate) current: Option<u64>,
+    /// 总数, 为空表示未知.
+    pub(crate) total: Option<u64>,
+    /// 最近一次 `status` 的内容.
+    pub(crate) status: Option<String>,
+}
+
+/// 进度更新, 通过 `task-progress` 事件推送.
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct ProgressEvent {
+    pub(crate) task_id: i64,
+    pub(crate) run_id: i64,
+    pub(crate) progress: Progress,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Report {
+    Progress { current: u64, total: Option<u64> },
+    Status(Option<String>),
+}
+
+/// 解析一行报告, 不是有效的报告时返回 `None`.
+fn parse(line: &str) -> Option<Report> {
+    let rest = line.strip_prefix(PREFIX)?;
+    let (command, arg) = rest.split_once(' ').unwrap_or((rest, ""));
+    let arg = arg.trim();
+    match command {
+        "progress" => {
+            let (current, total) = match arg.strip_suffix('%') {
+                Some(percent) => (percent.trim(), Some(100)),
+                None => match arg.split_once('/') {
+                    Some((current, total)) => (current.trim(), Some(total.trim().parse().ok()?)),
+                    None => (arg, None),
+                },
+            };
+            Some(Report::Progress {
+                current: current.parse().ok()?,
+                total,
+            })
+        }
+        "status" => Some(Report::Status(
+            Some(arg).filter(|s| !s.is_empty()).map(str::to_string),
+        )),
+        _ => None,
+    }
+}
+
+/// 一次运行的进度, 由输出中的报告行更新.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ProgressTracker(Arc<Mutex<Progress>>);
+
+impl ProgressTracker {
+    /// 处理一行输出, 是有效的报告时更新进度并返回更新之后的进度.
+    pub(crate) fn feed(&self, line: &str) -> Option<Progress> {
+        let report = parse(line)?;
+        let mut progress = self.0.lock().unwrap();
+        match report {
+            Report::Progress { current, total } => {
+                progress.current = Some(current);
+                progress.total = total;
+            }
+            Report::Status(status) => progress.status = status,
+        }
+        Some(progress.clone())
+    }
+
+    /// 最新的进度, 还没有收到任何报告时为空.
+    #[must_use]
+    pub(crate) fn progress(&self) -> Option<Progress> {
+        let progress = self.0.lock().unwrap();
+        (*progress != Progress::default()).then(|| progress.clone())
+    }
+}
+
+/// 子进程的专用报告管道的读取端.
+#[derive(Debug)]
+pub(crate) struct ProgressPipe {
+    #[cfg(unix)]
+    receiver: tokio::net::unix::pipe::Receiver,
+}
+
+/// 创建管道, 子进程 exec 之前将写入端复制为 `fd`, 并设置环境变量 `BGM_PROGRESS_FD`.
+///
+/// 写入端随 `cmd` 一起关闭, 子进程启动之后将读取端交给 [`ProgressPipe::forward`].
+/// `fd` 不能是标准流, 保存任务时已经检查过.
+#[cfg(unix)]
+pub(crate) fn attach(
+    cmd: &mut Command,
+    fd: i32,
+    steps: &StepReporter,
+) -> crate::Result<ProgressPipe> {
+    use std::os::fd::{AsRawFd, OwnedFd};
+
+    let failed_to_create = |e| {
+        crate::Error::with_source(
+            crate::ErrorKind::Io,
+            "failed to create progress pipe",
+            Box::new(e),
+        )
+    };
+    let (reader, writer) = std::io::pipe().map_err(failed_to_create)?;
+    let receiver = tokio::net::unix::pipe::Receiver::from_owned_fd(OwnedFd::from(reader))
+        .map_err(failed_to_create)?;
+    let writer = OwnedFd::from(writer);
+    cmd.env("BGM_PROGRESS_FD", fd.to_string());
+    let steps = steps.clone();
+    // SAFETY: 只调用了 async-signal-safe 的 dup2, fcntl 和 write.
+    unsafe {
+        cmd.pre_exec(move || {
+            let raw = writer.as_raw_fd();
+            if raw == fd {
+                // 复制到自身不会清除 FD_CLOEXEC, 需要手动清除.
+                let flags = libc::fcntl(fd, libc::F_GETFD);
+                if flags == -1 || libc::fcntl(fd, libc::F_SETFD, flags & !libc::FD_CLOEXEC) == -1 {
+                    return Err(steps.fail(Step::Progress, std::io::Error::last_os_error()));
+                }
+            } else if libc::dup2(raw, fd) == -1 {
+                return Err(steps.fail(Step::Progress, std::io::Error::last_os_error()));
+            }
+            Ok(())
+        });
+    }
+    Ok(ProgressPipe { receiver })
+}
+
+#[cfg(not(unix))]
+pub(crate) fn attach(
+    _cmd: &mut Command,
+    _fd: i32,
+    _steps: &StepReporter,
+) -> crate::Result<ProgressPipe> {
+    Err(crate::Error::with_message(
+        crate::ErrorKind::Io,
+        "progress fd is only supported on Unix",
+    ))
+}
+
+impl ProgressPipe {
+    /// 逐行读取管道中的报告交给 `tap`, 子进程及其后代全部关闭写入端之后结束.
+    ///
+    /// 管道中不是报告的内容被忽略.
+    #[cfg(unix)]
+    pub(crate) fn forward(self, tap: OutputTap) {
+        use tokio::io::AsyncBufReadExt;
+
+        tokio::spawn(async move {
+            let mut reader = tokio::io::BufReader::new(self.receiver);
+            let mut buf = Vec::new();
+            loop {
+                buf.clear();
+                match reader.read_until(b'\n', &mut buf).await {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        let line = String::from_utf8_lossy(&buf);
+                        tap.report(line.trim_end_matches(['\n', '\r']));
+                    }
+                }
+            }
+        });
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn forward(self, _tap: OutputTap) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_progress() {
+        assert_eq!(
+            parse("::bgm progress 42/100"),
+            Some(Report::Progress {
+                current: 42,
+                total: Some(100)
+            })
+        );
+        assert_eq!(
+            parse("::bgm progress 42%"),
+            Some(Report::Progress {
+                current: 42,
+                total: Some(100)
+            })
+        );
+        assert_eq!(
+            parse("::bgm progress  7 "),
+            Some(Report::Progress {
+                current: 7,
+                total: None
+            })
+        );
+        assert_eq!(parse("::bgm progress abc"), None);
+        assert_eq!(parse("::bgm progress 1/x"), None);
+    }
+
+    #[test]
+    fn parse_status() {
+        assert_eq!(
+            parse("::bgm status Uploading batch 3"),
+            Some(Report::Status(Some("Uploading batch 3".to_string())))
+        );
+        assert_eq!(parse("::bgm status"), Some(Report::Status(None)));
+        assert_eq!(parse("::bgm status   "), Some(Report::Status(None)));
+    }
+
+    #[test]
+    fn parse_ignores_other_lines() {
+        assert_eq!(parse("progress 42"), None);
+        assert_eq!(parse("::bgm unknown 1"), None);
+        assert_eq!(parse("::bgmprogress 1"), None);
+    }
+
+    #[test]
+    fn tracker_keeps_status_across_progress() {
+        let tracker = ProgressTracker::default();
+        assert_eq!(tracker.progress(), None);
+        assert_eq!(tracker.feed("plain output"), None);
+        tracker.feed("::bgm status Working");
+        let progress = tracker.feed("::bgm progress 3/4").unwrap();
+        assert_eq!(progress.current, Some(3));
+        assert_eq!(progress.total, Some(4));
+        assert_eq!(progress.status.as_deref(), Some("Working"));
+    }
+}
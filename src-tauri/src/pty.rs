@@ -0,0 +1,93 @@
+//! 在伪终端 (PTY) 中运行任务程序.
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+#[cfg(unix)]
+use crate::setup::Step;
+use crate::setup::StepReporter;
+
+/// 伪终端选项.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct PtyOptions {
+    /// 终端行数.
+    pub rows: u16,
+    /// 终端列数.
+    pub cols: u16,
+}
+
+impl Default for PtyOptions {
+    fn default() -> Self {
+        Self { rows: 24, cols: 80 }
+    }
+}
+
+/// 打开伪终端, 并将子进程的标准输入, 标准输出和标准错误连接到伪终端的从设备,
+/// 子进程启动时会创建新的会话并将伪终端设置为控制终端.
+///
+/// 返回伪终端的主设备, 子进程的所有输出都从主设备读取, 向主设备写入的内容作为子进程的输入.
+#[cfg(unix)]
+pub(crate) fn attach(
+    cmd: &mut Command,
+    opts: &PtyOptions,
+    steps: &StepReporter,
+) -> crate::Result<std::fs::File> {
+    use std::{io, process::Stdio};
+
+    let failed_to_open = |e: io::Error| {
+        crate::Error::with_source(crate::ErrorKind::Io, "failed to open pty", Box::new(e))
+    };
+    let winsize = nix::pty::Winsize {
+        ws_row: opts.rows,
+        ws_col: opts.cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let pty = nix::pty::openpty(Some(&winsize), None).map_err(|e| failed_to_open(e.into()))?;
+    let slave = pty.slave;
+    cmd.stdin(Stdio::from(slave.try_clone().map_err(failed_to_open)?));
+    cmd.stdout(Stdio::from(slave.try_clone().map_err(failed_to_open)?));
+    cmd.stderr(Stdio::from(slave));
+    let steps = steps.clone();
+    // SAFETY: 只调用了 async-signal-safe 的 setsid, ioctl 和 write.
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::setsid() == -1 {
+                return Err(steps.fail(Step::Pty, io::Error::last_os_error()));
+            }
+            // 标准输入已经是伪终端的从设备, 将其设置为控制终端.
+            if libc::ioctl(0, libc::TIOCSCTTY as _, 0) == -1 {
+                return Err(steps.fail(Step::Pty, io::Error::last_os_error()));
+            }
+            Ok(())
+        });
+    }
+    Ok(pty.master.into())
+}
+
+#[cfg(not(unix))]
+pub(crate) fn attach(
+    _cmd: &mut Command,
+    _opts: &PtyOptions,
+    _steps: &StepReporter,
+) -> crate::Result<std::fs::File> {
+    Err(crate::Error::with_message(
+        crate::ErrorKind::Io,
+        "pty mode is not supported on this platform",
+    ))
+}
+
+/// 读取伪终端主设备时, 从设备全部关闭之后 Linux 会返回 EIO, 视为读取结束.
+#[must_use]
+pub(crate) fn is_closed(e: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        e.raw_os_error() == Some(libc::EIO)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = e;
+        false
+    }
+}
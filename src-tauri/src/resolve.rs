@@ -0,0 +1,221 @@
+//! 解析任务程序: 在搜索路径中查找程序名, 按扩展名选择解释器, 以及诊断启动失败的原因.
+
+use std::{
+    ffi::{OsStr, OsString},
+    fs, io,
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::{config::AppConfig, outcome::LaunchFailure};
+
+/// 实际执行的命令, 任务参数追加在 `args` 之后.
+#[derive(Debug)]
+pub(crate) struct Resolved {
+    pub(crate) program: PathBuf,
+    pub(crate) args: Vec<OsString>,
+    /// 任务程序的路径, 使用解释器时为脚本的路径.
+    pub(crate) script: PathBuf,
+}
+
+/// 启动失败的原因.
+#[derive(thiserror::Error, Debug)]
+#[error("{message}")]
+pub(crate) struct LaunchError {
+    pub(crate) failure: LaunchFailure,
+    message: String,
+    #[source]
+    source: Option<io::Error>,
+}
+
+impl LaunchError {
+    pub(crate) fn new(
+        failure: LaunchFailure,
+        message: impl Into<String>,
+        source: Option<io::Error>,
+    ) -> Self {
+        Self {
+            failure,
+            message: message.into(),
+            source,
+        }
+    }
+}
+
+/// 解析任务程序.
+///
+/// 只有文件名的程序依次在 [`AppConfig::search_path`] 和 `path` (任务的 `PATH` 环境变量) 中查找,
+/// 相对路径相对于 `working_dir`. 扩展名配置了解释器, 并且不是带有 shebang 的可执行文件时, 使用解释器运行.
+pub(crate) fn resolve(
+    program: &Path,
+    working_dir: Option<&Path>,
+    path: Option<&OsStr>,
+    config: &AppConfig,
+) -> Result<Resolved, LaunchError> {
+    let script = locate(program, working_dir, path, config)?;
+    let interpreter = script
+        .extension()
+        .and_then(OsStr::to_str)
+        .and_then(|ext| config.interpreter(ext))
+        .filter(|_| !(is_executable(&script) && shebang(&script).is_some()));
+    let Some(interpreter) = interpreter else {
+        return Ok(Resolved {
+            program: script.clone(),
+            args: vec![],
+            script,
+        });
+    };
+    let Some((name, rest)) = interpreter.split_first() else {
+        return Err(LaunchError::new(
+            LaunchFailure::BadInterpreter,
+            format!("empty interpreter configured for {}", script.display()),
+            None,
+        ));
+    };
+    let program = locate(Path::new(name), working_dir, path, config).map_err(|e| {
+        LaunchError::new(
+            LaunchFailure::BadInterpreter,
+            format!("interpreter of {}: {}", script.display(), e.message),
+            None,
+        )
+    })?;
+    let mut args: Vec<OsString> = rest.iter().map(OsString::from).collect();
+    args.push(script.clone().into_os_string());
+    Ok(Resolved {
+        program,
+        args,
+        script,
+    })
+}
+
+/// 程序是否可以运行: 能够找到, 并且是可执行文件或者配置了解释器.
+#[must_use]
+pub(crate) fn is_runnable(program: &Path, config: &AppConfig) -> bool {
+    match resolve(program, None, None, config) {
+        Ok(resolved) => resolved.program != resolved.script || is_executable(&resolved.script),
+        Err(_) => false,
+    }
+}
+
+/// 根据 `spawn` 返回的错误诊断程序无法执行的原因.
+///
+/// exec 之前的设置步骤失败时不使用, 见 [`Steps::failed`].
+///
+/// [`Steps::failed`]: crate::setup::Steps::failed
+#[must_use]
+pub(crate) fn diagnose(
+    e: io::Error,
+    resolved: &Resolved,
+    working_dir: Option<&Path>,
+) -> LaunchError {
+    if let Some(dir) = working_dir
+        && !dir.is_dir()
+    {
+        let message = format!("working directory {} does not exist", dir.display());
+        return LaunchError::new(LaunchFailure::Other, message, Some(e));
+    }
+    let program = resolved.program.display();
+    match e.kind() {
+        // 程序存在时, ENOENT 来自 shebang 中的解释器
+        io::ErrorKind::NotFound if resolved.program.exists() => {
+            let message = match shebang(&resolved.program) {
+                Some(line) => format!("bad interpreter `{line}` in {program}"),
+                None => format!("failed to execute {program}"),
+            };
+            LaunchError::new(LaunchFailure::BadInterpreter, message, Some(e))
+        }
+        io::ErrorKind::NotFound => LaunchError::new(
+            LaunchFailure::NotFound,
+            format!("{program} not found"),
+            Some(e),
+        ),
+        io::ErrorKind::PermissionDenied => LaunchError::new(
+            LaunchFailure::PermissionDenied,
+            format!("permission denied to execute {program}"),
+            Some(e),
+        ),
+        #[cfg(unix)]
+        _ if e.raw_os_error() == Some(libc::ENOEXEC) => LaunchError::new(
+            LaunchFailure::BadInterpreter,
+            format!("{program} is not a binary and has no shebang or configured interpreter"),
+            Some(e),
+        ),
+        _ => LaunchError::new(
+            LaunchFailure::Other,
+            format!("failed to execute {program}"),
+            Some(e),
+        ),
+    }
+}
+
+fn locate(
+    program: &Path,
+    working_dir: Option<&Path>,
+    path: Option<&OsStr>,
+    config: &AppConfig,
+) -> Result<PathBuf, LaunchError> {
+    // 带有目录的路径不查找
+    if program.components().count() > 1 || program.is_absolute() {
+        return Ok(match working_dir {
+            Some(dir) if program.is_relative() => dir.join(program),
+            _ => program.to_path_buf(),
+        });
+    }
+    let env_path = std::env::var_os("PATH");
+    let dirs = config
+        .search_path()
+        .iter()
+        .cloned()
+        .chain(std::env::split_paths(
+            path.or(env_path.as_deref()).unwrap_or_default(),
+        ));
+    for dir in dirs {
+        let candidate = dir.join(program);
+        if is_candidate(&candidate, config) {
+            return Ok(candidate);
+        }
+        if cfg!(windows) && candidate.extension().is_none() {
+            let candidate = candidate.with_extension("exe");
+            if candidate.is_file() {
+                return Ok(candidate);
+            }
+        }
+    }
+    Err(LaunchError::new(
+        LaunchFailure::NotFound,
+        format!("{} not found in search path or PATH", program.display()),
+        None,
+    ))
+}
+
+/// 和 `execvp` 一样跳过不可执行的文件, 配置了解释器的脚本除外.
+fn is_candidate(path: &Path, config: &AppConfig) -> bool {
+    path.is_file()
+        && (is_executable(path)
+            || path
+                .extension()
+                .and_then(OsStr::to_str)
+                .is_some_and(|ext| config.interpreter(ext).is_some()))
+}
+
+fn is_executable(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::metadata(path).is_ok_and(|md| md.permissions().mode() & 0o111 != 0)
+    }
+    #[cfg(not(unix))]
+    path.exists()
+}
+
+/// 读取脚本的 shebang 行, 不包括开头的 `#!`.
+fn shebang(path: &Path) -> Option<String> {
+    let mut buf = [0u8; 256];
+    let n = fs::File::open(path)
+        .and_then(|mut f| f.read(&mut buf))
+        .ok()?;
+    let line = buf[..n].strip_prefix(b"#!")?;
+    let line = line.split(|&b| b == b'\n').next()?;
+    Some(String::from_utf8_lossy(line).trim().to_string())
+}
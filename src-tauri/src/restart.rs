@@ -0,0 +1,171 @@
+//! `KeepAlive` 和 `UntilSucceed` 任务退出之后的重启策略.
+
+use std::time::Duration;
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+/// 重启策略, 连续失败时重启间隔按指数增长.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct RestartPolicy {
+    /// 第一次重启之前的等待时间 (毫秒).
+    pub initial_delay_ms: u64,
+    /// 每次重启之后等待时间的倍数.
+    pub multiplier: f64,
+    /// 等待时间的上限 (毫秒), 运行时间超过这个值的运行视为正常, 重置重启次数.
+    pub max_delay_ms: u64,
+    /// 连续重启的最大次数, 为空表示不限制.
+    pub max_attempts: Option<u32>,
+    /// 只在这些退出码时重启, 为空表示 `KeepAlive` 任何退出都重启, `UntilSucceed` 运行失败时重启.
+    /// 健康检查失败而被结束的 `KeepAlive` 任务总是重启.
+    pub restart_on: Vec<i64>,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay_ms: 1000,
+            multiplier: 2.0,
+            max_delay_ms: 60_000,
+            max_attempts: None,
+            restart_on: Vec::new(),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// 第 `attempt` 次重启 (从 1 开始) 之前的等待时间.
+    #[must_use]
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let factor = self
+            .multiplier
+            .max(1.0)
+            .powi(attempt.saturating_sub(1) as i32);
+        let delay = (self.initial_delay_ms as f64 * factor).min(self.max_delay_ms as f64);
+        Duration::from_millis(delay as u64)
+    }
+
+    /// 退出码为 `code` 时是否需要重启.
+    #[must_use]
+    pub(crate) fn restarts_on(&self, code: i64) -> bool {
+        self.restart_on.is_empty() || self.restart_on.contains(&code)
+    }
+}
+
+/// 重启的状态, 记录连续重启的次数和下一次重启的时间.
+#[derive(Debug, Default)]
+pub(crate) struct Backoff {
+    attempt: u32,
+    next: Option<(Instant, DateTime<FixedOffset>)>,
+}
+
+impl Backoff {
+    /// 当前是第几次连续重启, 0 表示没有在重启.
+    #[inline]
+    #[must_use]
+    pub(crate) fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// 下一次重启的时间, 没有等待中的重启时为空.
+    #[inline]
+    #[must_use]
+    pub(crate) fn next_retry_at(&self) -> Option<DateTime<FixedOffset>> {
+        self.next.map(|(_, at)| at)
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn is_pending(&self) -> bool {
+        self.next.is_some()
+    }
+
+    /// 安排下一次重启, 超出最大次数时返回 false.
+    ///
+    /// 运行时间 `ran_for` 超过等待时间上限时视为正常运行, 从第一次重启重新计数.
+    pub(crate) fn schedule(&mut self, policy: &RestartPolicy, ran_for: Duration) -> bool {
+        if ran_for >= Duration::from_millis(policy.max_delay_ms) {
+            self.attempt = 0;
+        }
+        if policy.max_attempts.is_some_and(|max| self.attempt >= max) {
+            self.next = None;
+            return false;
+        }
+        self.attempt += 1;
+        let delay = policy.delay(self.attempt);
+        let at = chrono::Local::now() + delay;
+        self.next = Some((Instant::now() + delay, at.into()));
+        true
+    }
+
+    /// 等待到下一次重启的时间, 没有等待中的重启时永远不会完成.
+    pub(crate) async fn wait(&self) {
+        match self.next {
+            Some((instant, _)) => tokio::time::sleep_until(instant).await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// 到达重启时间, 清除等待中的重启, 保留重启次数.
+    pub(crate) fn fire(&mut self) {
+        self.next = None;
+    }
+
+    /// 取消等待中的重启并重置重启次数.
+    pub(crate) fn reset(&mut self) {
+        self.attempt = 0;
+        self.next = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_grows_exponentially_up_to_max() {
+        let policy = RestartPolicy::default();
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(2), Duration::from_secs(2));
+        assert_eq!(policy.delay(4), Duration::from_secs(8));
+        assert_eq!(policy.delay(20), Duration::from_secs(60));
+        // 小于 1 的倍数不会让等待时间减少
+        let shrinking = RestartPolicy {
+            multiplier: 0.5,
+            ..Default::default()
+        };
+        assert_eq!(shrinking.delay(5), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn restarts_on_filter() {
+        let policy = RestartPolicy::default();
+        assert!(policy.restarts_on(0));
+        assert!(policy.restarts_on(-1));
+        let filtered = RestartPolicy {
+            restart_on: vec![75],
+            ..Default::default()
+        };
+        assert!(filtered.restarts_on(75));
+        assert!(!filtered.restarts_on(1));
+    }
+
+    #[test]
+    fn backoff_counts_attempts_until_max() {
+        let policy = RestartPolicy {
+            max_attempts: Some(2),
+            ..Default::default()
+        };
+        let mut backoff = Backoff::default();
+        assert!(backoff.schedule(&policy, Duration::ZERO));
+        assert!(backoff.schedule(&policy, Duration::ZERO));
+        assert_eq!(backoff.attempt(), 2);
+        assert!(!backoff.schedule(&policy, Duration::ZERO));
+        assert!(!backoff.is_pending());
+        // 运行时间超过等待时间上限时重新计数
+        assert!(backoff.schedule(&policy, Duration::from_secs(60)));
+        assert_eq!(backoff.attempt(), 1);
+    }
+}
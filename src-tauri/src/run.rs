@@ -0,0 +1,516 @@
+//! 任务的运行记录与自动捕获的输出.
+
+use std::{
+    io::SeekFrom,
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, FixedOffset};
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Unchanged, ColumnTrait, DatabaseConnection, EntityTrait, NotSet,
+    QueryFilter, QueryOrder, QuerySelect, Set, sea_query::Expr,
+};
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+use crate::{
+    cgroup::CgroupStats, config::runs_dir, limits::LimitViolation, outcome::Outcome,
+    process::ProcessIdentity, utils::EnsureDirExists,
+};
+
+/// 每个任务最多保留的运行记录数量, 超出的记录和对应的输出文件会被删除.
+pub(crate) const RUN_HISTORY_LIMIT: u64 = 100;
+
+/// 单次运行记录.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Run {
+    pub id: i64,
+    pub task_id: i64,
+    pub started_at: DateTime<FixedOffset>,
+    /// 为空表示仍在运行, 或者管理器在进程结束之前退出.
+    pub ended_at: Option<DateTime<FixedOffset>>,
+    pub exit_code: Option<i64>,
+    /// 运行的结束方式, 为空表示仍在运行.
+    pub outcome: Option<Outcome>,
+    /// 自动捕获的输出文件, 任务没有设置 stdout/stderr 时才会捕获.
+    pub output: Option<PathBuf>,
+    /// 伪终端会话的 asciicast 录制文件.
+    pub recording: Option<PathBuf>,
+    /// 进程因超出资源限制而结束.
+    pub limit_violation: Option<LimitViolation>,
+    /// 从运行的 cgroup 统计的 CPU 时间 (微秒), 不使用 cgroup 时为空.
+    pub cpu_usage_usec: Option<i64>,
+    /// 从运行的 cgroup 统计的内存使用峰值 (字节).
+    pub memory_peak: Option<i64>,
+    /// 运行期间被跳过的触发次数.
+    pub skipped_triggers: i64,
+    /// 按照任务的成功条件判断的结果, 为空表示仍在运行.
+    pub succeeded: Option<bool>,
+    /// 进程 ID, 启动失败时为空.
+    pub pid: Option<u32>,
+    /// 运行所属副本的编号, 任务不使用副本时为空.
+    pub instance: Option<u32>,
+    /// 进程的启动时间和系统的 boot id, 用于在管理器重启之后确认进程没有被替换.
+    #[serde(skip)]
+    pub pid_start_time: Option<i64>,
+    #[serde(skip)]
+    pub boot_id: Option<String>,
+}
+
+impl From<entity::runs::Model> for Run {
+    fn from(m: entity::runs::Model) -> Self {
+        Run {
+            id: m.id,
+            task_id: m.task_id,
+            started_at: DateTime::parse_from_rfc3339(&m.started_at).unwrap_or_default(),
+            ended_at: m
+                .ended_at
+                .and_then(|s| DateTime::parse_from_rfc3339(&s).ok()),
+            exit_code: m.exit_code,
+            outcome: m.outcome.and_then(|s| serde_json::from_str(&s).ok()),
+            output: m.output.map(PathBuf::from),
+            recording: m.recording.map(PathBuf::from),
+            limit_violation: m.limit_violation.as_deref().and_then(LimitViolation::parse),
+            cpu_usage_usec: m.cpu_usage_usec,
+            memory_peak: m.memory_peak,
+            skipped_triggers: m.skipped_triggers,
+            succeeded: m.succeeded,
+            pid: m.pid.map(|p| p as u32),
+            instance: m.instance.map(|i| i as u32),
+            pid_start_time: m.pid_start_time,
+            boot_id: m.boot_id,
+        }
+    }
+}
+
+fn run_dir(task_id: i64) -> crate::Result<PathBuf> {
+    runs_dir()?.join(task_id.to_string()).ensure_dir_exists()
+}
+
+/// 任务 `task_id` 的第 `run_id` 次运行的输出文件路径.
+pub(crate) fn run_output_path(task_id: i64, run_id: i64) -> crate::Result<PathBuf> {
+    Ok(run_dir(task_id)?.join(format!("{run_id}.log")))
+}
+
+/// 任务 `task_id` 的第 `run_id` 次运行的终端录制文件路径.
+pub(crate) fn run_recording_path(task_id: i64, run_id: i64) -> crate::Result<PathBuf> {
+    Ok(run_dir(task_id)?.join(format!("{run_id}.cast")))
+}
+
+/// 任务 `task_id` 的第 `run_id` 次运行的 notify socket 路径.
+pub(crate) fn run_notify_path(task_id: i64, run_id: i64) -> crate::Result<PathBuf> {
+    Ok(run_dir(task_id)?.join(format!("{run_id}.sock")))
+}
+
+pub trait RunDAO {
+    /// 创建一条新的运行记录, 返回运行记录的 id. `instance` 为运行所属副本的编号.
+    async fn create_run(
+        &self,
+        task_id: i64,
+        instance: Option<u32>,
+        started_at: DateTime<FixedOffset>,
+    ) -> crate::Result<i64>;
+    /// 设置运行记录的输出文件.
+    async fn set_run_output(&self, id: i64, output: &Path) -> crate::Result<()>;
+    /// 设置运行记录的终端录制文件.
+    async fn set_run_recording(&self, id: i64, recording: &Path) -> crate::Result<()>;
+    /// 记录运行的进程, 用于在管理器重启之后接管进程.
+    async fn set_run_process(&self, id: i64, process: &ProcessIdentity) -> crate::Result<()>;
+    /// 记录运行结束, `succeeded` 为按照任务的成功条件判断的结果, 无法判断 (例如接管的进程) 时为空.
+    async fn finish_run(
+        &self,
+        id: i64,
+        outcome: &Outcome,
+        succeeded: Option<bool>,
+        ended_at: DateTime<FixedOffset>,
+    ) -> crate::Result<()>;
+    /// 记录进程超出的资源限制.
+    async fn set_run_limit_violation(
+        &self,
+        id: i64,
+        violation: LimitViolation,
+    ) -> crate::Result<()>;
+    /// 记录从运行的 cgroup 统计的资源使用.
+    async fn set_run_usage(&self, id: i64, stats: &CgroupStats) -> crate::Result<()>;
+    /// 记录运行期间有一次触发被跳过.
+    async fn add_run_skipped_trigger(&self, id: i64) -> crate::Result<()>;
+    async fn get_run(&self, id: i64) -> crate::Result<Option<Run>>;
+    /// 列出任务还没有结束的运行记录.
+    async fn list_unfinished_runs(&self, task_id: i64) -> crate::Result<Vec<Run>>;
+    /// 按开始时间从新到旧列出任务的运行记录, `instance` 不为空时只列出该副本的运行记录.
+    async fn list_runs(
+        &self,
+        task_id: i64,
+        instance: Option<u32>,
+        limit: u64,
+    ) -> crate::Result<Vec<Run>>;
+    /// 删除任务最新的 `keep` 条以外的运行记录, 返回被删除的记录.
+    async fn prune_runs(&self, task_id: i64, keep: u64) -> crate::Result<Vec<Run>>;
+    /// 删除任务的所有运行记录, 返回被删除的记录.
+    async fn remove_runs(&self, task_id: i64) -> crate::Result<Vec<Run>>;
+}
+
+impl RunDAO for DatabaseConnection {
+    async fn create_run(
+        &self,
+        task_id: i64,
+        instance: Option<u32>,
+        started_at: DateTime<FixedOffset>,
+    ) -> crate::Result<i64> {
+        let am = entity::runs::ActiveModel {
+            id: NotSet,
+            task_id: Set(task_id),
+            started_at: Set(started_at.to_rfc3339()),
+            ended_at: Set(None),
+            exit_code: Set(None),
+            output: Set(None),
+            recording: Set(None),
+            limit_violation: Set(None),
+            cpu_usage_usec: Set(None),
+            memory_peak: Set(None),
+            skipped_triggers: Set(0),
+            succeeded: Set(None),
+            pid: Set(None),
+            pid_start_time: Set(None),
+            boot_id: Set(None),
+            outcome: Set(None),
+            instance: Set(instance.map(i64::from)),
+        };
+        let m = am.insert(self).await.map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                format!("failed to create run of task id: {task_id}"),
+                Box::new(e),
+            )
+        })?;
+        Ok(m.id)
+    }
+
+    async fn set_run_output(&self, id: i64, output: &Path) -> crate::Result<()> {
+        let am = entity::runs::ActiveModel {
+            id: Unchanged(id),
+            output: Set(Some(output.to_string_lossy().into_owned())),
+            ..Default::default()
+        };
+        am.update(self).await.map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                format!("failed to update output of run id: {id}"),
+                Box::new(e),
+            )
+        })?;
+        Ok(())
+    }
+
+    async fn set_run_recording(&self, id: i64, recording: &Path) -> crate::Result<()> {
+        let am = entity::runs::ActiveModel {
+            id: Unchanged(id),
+            recording: Set(Some(recording.to_string_lossy().into_owned())),
+            ..Default::default()
+        };
+        am.update(self).await.map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                format!("failed to update recording of run id: {id}"),
+                Box::new(e),
+            )
+        })?;
+        Ok(())
+    }
+
+    async fn set_run_process(&self, id: i64, process: &ProcessIdentity) -> crate::Result<()> {
+        let am = entity::runs::ActiveModel {
+            id: Unchanged(id),
+            pid: Set(Some(i64::from(process.pid))),
+            pid_start_time: Set(process.start_time),
+            boot_id: Set(process.boot_id.clone()),
+            ..Default::default()
+        };
+        am.update(self).await.map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                format!("failed to update process of run id: {id}"),
+                Box::new(e),
+            )
+        })?;
+        Ok(())
+    }
+
+    async fn finish_run(
+        &self,
+        id: i64,
+        outcome: &Outcome,
+        succeeded: Option<bool>,
+        ended_at: DateTime<FixedOffset>,
+    ) -> crate::Result<()> {
+        let am = entity::runs::ActiveModel {
+            id: Unchanged(id),
+            ended_at: Set(Some(ended_at.to_rfc3339())),
+            exit_code: Set(outcome.code()),
+            outcome: Set(serde_json::to_string(outcome).ok()),
+            succeeded: Set(succeeded),
+            ..Default::default()
+        };
+        am.update(self).await.map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                format!("failed to finish run id: {id}"),
+                Box::new(e),
+            )
+        })?;
+        Ok(())
+    }
+
+    async fn set_run_limit_violation(
+        &self,
+        id: i64,
+        violation: LimitViolation,
+    ) -> crate::Result<()> {
+        let am = entity::runs::ActiveModel {
+            id: Unchanged(id),
+            limit_violation: Set(Some(violation.as_str().to_string())),
+            ..Default::default()
+        };
+        am.update(self).await.map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                format!("failed to update limit violation of run id: {id}"),
+                Box::new(e),
+            )
+        })?;
+        Ok(())
+    }
+
+    async fn set_run_usage(&self, id: i64, stats: &CgroupStats) -> crate::Result<()> {
+        let am = entity::runs::ActiveModel {
+            id: Unchanged(id),
+            cpu_usage_usec: Set(Some(stats.cpu_usage_usec as i64)),
+            memory_peak: Set(stats.memory_peak.map(|p| p as i64)),
+            ..Default::default()
+        };
+        am.update(self).await.map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                format!("failed to update usage of run id: {id}"),
+                Box::new(e),
+            )
+        })?;
+        Ok(())
+    }
+
+    async fn add_run_skipped_trigger(&self, id: i64) -> crate::Result<()> {
+        use entity::runs::Column;
+        use sea_orm::sea_query::ExprTrait;
+
+        entity::runs::Entity::update_many()
+            .col_expr(
+                Column::SkippedTriggers,
+                Expr::col(Column::SkippedTriggers).add(1),
+            )
+            .filter(Column::Id.eq(id))
+            .exec(self)
+            .await
+            .map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Db,
+                    format!("failed to update skipped triggers of run id: {id}"),
+                    Box::new(e),
+                )
+            })?;
+        Ok(())
+    }
+
+    async fn get_run(&self, id: i64) -> crate::Result<Option<Run>> {
+        let run = entity::runs::Entity::find_by_id(id)
+            .one(self)
+            .await
+            .map_err(|e| {
+                crate::Error::with_source(crate::ErrorKind::Db, "failed to get run", Box::new(e))
+            })?;
+        Ok(run.map(|r| r.into()))
+    }
+
+    async fn list_unfinished_runs(&self, task_id: i64) -> crate::Result<Vec<Run>> {
+        let runs = entity::runs::Entity::find()
+            .filter(entity::runs::Column::TaskId.eq(task_id))
+            .filter(entity::runs::Column::EndedAt.is_null())
+            .order_by_asc(entity::runs::Column::Id)
+            .all(self)
+            .await
+            .map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Db,
+                    format!("failed to list unfinished runs of task id: {task_id}"),
+                    Box::new(e),
+                )
+            })?;
+        Ok(runs.into_iter().map(|r| r.into()).collect())
+    }
+
+    async fn list_runs(
+        &self,
+        task_id: i64,
+        instance: Option<u32>,
+        limit: u64,
+    ) -> crate::Result<Vec<Run>> {
+        let mut query =
+            entity::runs::Entity::find().filter(entity::runs::Column::TaskId.eq(task_id));
+        if let Some(instance) = instance {
+            query = query.filter(entity::runs::Column::Instance.eq(i64::from(instance)));
+        }
+        let runs = query
+            .order_by_desc(entity::runs::Column::Id)
+            .limit(limit)
+            .all(self)
+            .await
+            .map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Db,
+                    format!("failed to list runs of task id: {task_id}"),
+                    Box::new(e),
+                )
+            })?;
+        Ok(runs.into_iter().map(|r| r.into()).collect())
+    }
+
+    async fn prune_runs(&self, task_id: i64, keep: u64) -> crate::Result<Vec<Run>> {
+        let failed_to_prune = |e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                format!("failed to prune runs of task id: {task_id}"),
+                Box::new(e),
+            )
+        };
+        let stale: Vec<Run> = entity::runs::Entity::find()
+            .filter(entity::runs::Column::TaskId.eq(task_id))
+            .order_by_desc(entity::runs::Column::Id)
+            .offset(keep)
+            .all(self)
+            .await
+            .map_err(failed_to_prune)?
+            .into_iter()
+            .map(|r| r.into())
+            .collect();
+        if !stale.is_empty() {
+            entity::runs::Entity::delete_many()
+                .filter(entity::runs::Column::Id.is_in(stale.iter().map(|r| r.id)))
+                .exec(self)
+                .await
+                .map_err(failed_to_prune)?;
+        }
+        Ok(stale)
+    }
+
+    async fn remove_runs(&self, task_id: i64) -> crate::Result<Vec<Run>> {
+        self.prune_runs(task_id, 0).await
+    }
+}
+
+/// 删除运行记录对应的输出文件和录制文件.
+pub(crate) async fn remove_run_files(runs: &[Run]) {
+    for run in runs {
+        for path in [&run.output, &run.recording].into_iter().flatten() {
+            tokio::fs::remove_file(path).await.ok();
+        }
+    }
+}
+
+/// 输出文件中的一段内容.
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct OutputChunk {
+    /// 读取到的内容, 非 UTF-8 字符会被替换.
+    pub(crate) content: String,
+    /// 下一次读取的起始位置.
+    pub(crate) next_offset: u64,
+    /// 是否已经读取到文件末尾.
+    pub(crate) eof: bool,
+}
+
+fn failed_to_read(path: &Path, e: std::io::Error) -> crate::Error {
+    crate::Error::with_source(
+        crate::ErrorKind::Io,
+        format!("failed to read output file: {}", path.display()),
+        Box::new(e),
+    )
+}
+
+/// 从 `offset` 字节处开始读取最多 `limit` 字节的输出.
+pub(crate) async fn read_output(
+    path: &Path,
+    offset: u64,
+    limit: u64,
+) -> crate::Result<OutputChunk> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| failed_to_read(path, e))?;
+    let len = file
+        .metadata()
+        .await
+        .map_err(|e| failed_to_read(path, e))?
+        .len();
+    let offset = offset.min(len);
+    file.seek(SeekFrom::Start(offset))
+        .await
+        .map_err(|e| failed_to_read(path, e))?;
+    let mut buf = Vec::new();
+    file.take(limit)
+        .read_to_end(&mut buf)
+        .await
+        .map_err(|e| failed_to_read(path, e))?;
+    let next_offset = offset + buf.len() as u64;
+    Ok(OutputChunk {
+        content: String::from_utf8_lossy(&buf).into_owned(),
+        next_offset,
+        eof: next_offset >= len,
+    })
+}
+
+/// 读取输出文件的最后 `lines` 行.
+pub(crate) async fn tail_output(path: &Path, lines: usize) -> crate::Result<String> {
+    const BLOCK: u64 = 64 * 1024;
+
+    if lines == 0 {
+        return Ok(String::new());
+    }
+
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| failed_to_read(path, e))?;
+    let len = file
+        .metadata()
+        .await
+        .map_err(|e| failed_to_read(path, e))?
+        .len();
+
+    // 从文件末尾向前成块读取, 直到包含足够的行.
+    let mut start = len;
+    let mut buf: Vec<u8> = Vec::new();
+    while start > 0 {
+        let read_from = start.saturating_sub(BLOCK);
+        let mut block = vec![0u8; (start - read_from) as usize];
+        file.seek(SeekFrom::Start(read_from))
+            .await
+            .map_err(|e| failed_to_read(path, e))?;
+        file.read_exact(&mut block)
+            .await
+            .map_err(|e| failed_to_read(path, e))?;
+        block.extend_from_slice(&buf);
+        buf = block;
+        start = read_from;
+        // 末尾的换行符不算作新的一行.
+        let body = buf.strip_suffix(b"\n").unwrap_or(&buf);
+        if body.iter().filter(|&&b| b == b'\n').count() >= lines {
+            break;
+        }
+    }
+
+    let body = buf.strip_suffix(b"\n").unwrap_or(&buf);
+    let skip = body
+        .iter()
+        .enumerate()
+        .rev()
+        .filter(|(_, b)| **b == b'\n')
+        .nth(lines - 1)
+        .map_or(0, |(pos, _)| pos + 1);
+    Ok(String::from_utf8_lossy(&buf[skip..]).into_owned())
+}
@@ -0,0 +1,278 @@
+//! 在轻量级沙箱中运行任务程序 (仅 Linux 下有效).
+//!
+//! 子进程在 exec 之前进入新的网络命名空间 (没有网络) 和挂载命名空间 (私有的 `/tmp`),
+//! 设置 `no_new_privs`, 再通过 Landlock 将文件系统限制为只读, 声明的可写路径除外.
+//! 管理器不是 root 时同时创建用户命名空间, 当前用户映射为自身.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+#[cfg(target_os = "linux")]
+use crate::setup::Step;
+use crate::setup::StepReporter;
+
+/// 沙箱选项.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct SandboxOptions {
+    /// 可写的文件或目录, 必须已经存在. 私有的 `/tmp` 和 `/dev/null` 总是可写,
+    /// 原来 `/tmp` 下的路径会被私有的 `/tmp` 遮住, 不能在沙箱中访问.
+    pub writable_paths: Vec<PathBuf>,
+}
+
+fn sandbox_error(msg: String) -> crate::Error {
+    crate::Error::with_message(crate::ErrorKind::Io, msg)
+}
+
+#[cfg(target_os = "linux")]
+mod landlock {
+    //! Landlock 的系统调用和常量, 参考 landlock(7).
+
+    pub(super) const CREATE_RULESET_VERSION: u32 = 1 << 0;
+    pub(super) const RULE_PATH_BENEATH: libc::c_int = 1;
+
+    pub(super) const EXECUTE: u64 = 1 << 0;
+    pub(super) const WRITE_FILE: u64 = 1 << 1;
+    pub(super) const READ_FILE: u64 = 1 << 2;
+    pub(super) const READ_DIR: u64 = 1 << 3;
+    /// ABI 1 中所有和目录相关的权限 (删除, 创建各种文件).
+    pub(super) const DIR_V1: u64 = 0b1_1111_1111 << 4;
+    /// ABI 2, 跨目录移动和链接文件.
+    pub(super) const REFER: u64 = 1 << 13;
+    /// ABI 3, 截断文件.
+    pub(super) const TRUNCATE: u64 = 1 << 14;
+
+    /// 只能用于普通文件的权限.
+    pub(super) const FILE: u64 = EXECUTE | WRITE_FILE | READ_FILE | TRUNCATE;
+    /// 只读视图中允许的权限.
+    pub(super) const READ_ONLY: u64 = EXECUTE | READ_FILE | READ_DIR;
+
+    #[repr(C)]
+    pub(super) struct RulesetAttr {
+        pub(super) handled_access_fs: u64,
+    }
+
+    #[repr(C, packed)]
+    pub(super) struct PathBeneathAttr {
+        pub(super) allowed_access: u64,
+        pub(super) parent_fd: libc::c_int,
+    }
+
+    /// 内核支持的 Landlock ABI 版本, 不支持时返回错误.
+    pub(super) fn abi_version() -> std::io::Result<libc::c_long> {
+        // SAFETY: 查询版本时属性指针为空, 大小为 0.
+        let version = unsafe {
+            libc::syscall(
+                libc::SYS_landlock_create_ruleset,
+                std::ptr::null::<RulesetAttr>(),
+                0usize,
+                CREATE_RULESET_VERSION,
+            )
+        };
+        if version < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(version)
+    }
+
+    /// 对应 ABI 版本可以处理的文件系统权限.
+    pub(super) fn handled_access(abi: libc::c_long) -> u64 {
+        let mut access = EXECUTE | WRITE_FILE | READ_FILE | READ_DIR | DIR_V1;
+        if abi >= 2 {
+            access |= REFER;
+        }
+        if abi >= 3 {
+            access |= TRUNCATE;
+        }
+        access
+    }
+}
+
+/// 在子进程 exec 之前进入沙箱.
+///
+/// 必须在切换用户之前调用, 创建命名空间和挂载 `/tmp` 需要在切换用户之前完成.
+/// 子进程中失败的步骤通过 `steps` 报告, 作为启动失败的原因.
+#[cfg(target_os = "linux")]
+pub(crate) fn apply(
+    cmd: &mut Command,
+    opts: &SandboxOptions,
+    steps: &StepReporter,
+) -> crate::Result<()> {
+    use std::os::{
+        fd::{AsRawFd, OwnedFd},
+        unix::fs::OpenOptionsExt,
+    };
+
+    let abi = landlock::abi_version().map_err(|e| {
+        crate::Error::with_source(
+            crate::ErrorKind::Io,
+            "the sandbox requires Landlock, which is not supported or not enabled by the kernel",
+            Box::new(e),
+        )
+    })?;
+    let handled = landlock::handled_access(abi);
+
+    // 可写路径在进入沙箱之前打开, 私有的 /tmp 会遮住原来 /tmp 下的路径.
+    let mut writable = Vec::new();
+    for path in std::iter::once(Path::new("/dev/null"))
+        .chain(opts.writable_paths.iter().map(PathBuf::as_path))
+    {
+        if !path.is_absolute() {
+            return Err(sandbox_error(format!(
+                "sandbox writable path must be absolute: {}",
+                path.display()
+            )));
+        }
+        let file = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_PATH)
+            .open(path)
+            .map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Io,
+                    format!("failed to open sandbox writable path: {}", path.display()),
+                    Box::new(e),
+                )
+            })?;
+        let is_dir = file.metadata().is_ok_and(|m| m.is_dir());
+        let access = if is_dir {
+            handled
+        } else {
+            handled & landlock::FILE
+        };
+        writable.push((OwnedFd::from(file), access));
+    }
+
+    // SAFETY: geteuid, getuid 和 getgid 总是成功.
+    let (euid, uid, gid) = unsafe { (libc::geteuid(), libc::getuid(), libc::getgid()) };
+    // 不是 root 时需要用户命名空间才能创建其他命名空间, 映射内容需要在 fork 之前准备好.
+    let user_ns = (euid != 0).then(|| {
+        (
+            format!("{uid} {uid} 1").into_bytes(),
+            format!("{gid} {gid} 1").into_bytes(),
+        )
+    });
+
+    let steps = steps.clone();
+    // SAFETY: 只调用了 async-signal-safe 的系统调用, 闭包中没有分配内存.
+    unsafe {
+        cmd.pre_exec(move || {
+            let check = |step: Step, ret: libc::c_long| {
+                if ret < 0 {
+                    Err(steps.fail(step, std::io::Error::last_os_error()))
+                } else {
+                    Ok(ret)
+                }
+            };
+            let write_file = |path: &[u8], content: &[u8]| {
+                let fd = check(
+                    Step::IdMap,
+                    libc::open(path.as_ptr().cast(), libc::O_WRONLY | libc::O_CLOEXEC).into(),
+                )?;
+                let ret = libc::write(fd as libc::c_int, content.as_ptr().cast(), content.len());
+                libc::close(fd as libc::c_int);
+                check(Step::IdMap, ret as libc::c_long).map(drop)
+            };
+
+            // 命名空间和私有的 /tmp
+            let mut flags = libc::CLONE_NEWNET | libc::CLONE_NEWNS;
+            if user_ns.is_some() {
+                flags |= libc::CLONE_NEWUSER;
+            }
+            check(Step::Unshare, libc::unshare(flags).into())?;
+            if let Some((uid_map, gid_map)) = &user_ns {
+                write_file(b"/proc/self/setgroups\0", b"deny")?;
+                write_file(b"/proc/self/uid_map\0", uid_map)?;
+                write_file(b"/proc/self/gid_map\0", gid_map)?;
+            }
+            check(
+                Step::Mount,
+                libc::mount(
+                    std::ptr::null(),
+                    c"/".as_ptr(),
+                    std::ptr::null(),
+                    libc::MS_REC | libc::MS_PRIVATE,
+                    std::ptr::null(),
+                )
+                .into(),
+            )?;
+            check(
+                Step::Mount,
+                libc::mount(
+                    c"tmpfs".as_ptr(),
+                    c"/tmp".as_ptr(),
+                    c"tmpfs".as_ptr(),
+                    libc::MS_NOSUID | libc::MS_NODEV,
+                    c"mode=1777".as_ptr().cast(),
+                )
+                .into(),
+            )?;
+
+            check(
+                Step::NoNewPrivs,
+                libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0).into(),
+            )?;
+
+            // Landlock: 整个文件系统只读, 可写路径允许所有操作
+            let attr = landlock::RulesetAttr {
+                handled_access_fs: handled,
+            };
+            let ruleset = check(
+                Step::Landlock,
+                libc::syscall(
+                    libc::SYS_landlock_create_ruleset,
+                    &attr,
+                    std::mem::size_of::<landlock::RulesetAttr>(),
+                    0u32,
+                ),
+            )? as libc::c_int;
+            let add_rule = |fd: libc::c_int, access: u64| {
+                let rule = landlock::PathBeneathAttr {
+                    allowed_access: access,
+                    parent_fd: fd,
+                };
+                check(
+                    Step::Landlock,
+                    libc::syscall(
+                        libc::SYS_landlock_add_rule,
+                        ruleset,
+                        landlock::RULE_PATH_BENEATH,
+                        &rule,
+                        0u32,
+                    ),
+                )
+                .map(drop)
+            };
+            // 根目录和挂载之后的 /tmp 在这里打开
+            for (path, access) in [(c"/", landlock::READ_ONLY), (c"/tmp", handled)] {
+                let fd = check(
+                    Step::Landlock,
+                    libc::open(path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC).into(),
+                )? as libc::c_int;
+                let ret = add_rule(fd, access);
+                libc::close(fd);
+                ret?;
+            }
+            for (file, access) in &writable {
+                add_rule(file.as_raw_fd(), *access)?;
+            }
+            let ret = libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0u32);
+            libc::close(ruleset);
+            check(Step::Landlock, ret).map(drop)
+        });
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn apply(
+    _cmd: &mut Command,
+    _opts: &SandboxOptions,
+    _steps: &StepReporter,
+) -> crate::Result<()> {
+    Err(sandbox_error(
+        "the sandbox is only supported on Linux".to_string(),
+    ))
+}
@@ -1,26 +1,68 @@
 //! 调度任务的执行.
 
-use std::{collections::HashMap, ffi::OsStr, time::Duration};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    future::Future,
+    path::{Path, PathBuf},
+    process::ExitStatus,
+    sync::Arc,
+    task::Poll,
+    time::Duration,
+};
 
+use chrono::{DateTime, FixedOffset};
 use sea_orm::DatabaseConnection;
 use serde::Serialize;
 use tokio::{
     process::{self, Child},
-    sync::{mpsc, oneshot},
+    sync::{broadcast, mpsc, oneshot},
     task::JoinHandle,
     time::Instant,
 };
-use tracing::warn;
+use tracing::{info, warn};
+
+use crate::{
+    cgroup::Cgroup,
+    config::AppConfig,
+    environment,
+    expand::Vars,
+    health::{Health, HealthMonitor},
+    hooks, identity, limits,
+    notify::{NotifyListener, NotifyState},
+    outcome::Outcome,
+    output::{self, OutputLine, OutputSender, OutputTap, TailGuard},
+    priority,
+    process::{Adopted, OnExit, Process, ProcessIdentity},
+    progress::{self, Progress, ProgressEvent, ProgressTracker},
+    resolve::{self, Resolved},
+    restart::Backoff,
+    run::{
+        RUN_HISTORY_LIMIT, RunDAO, remove_run_files, run_notify_path, run_output_path,
+        run_recording_path,
+    },
+    sandbox,
+    setup::Steps,
+    shell_env::{self, BaseEnvReceiver},
+    stdin,
+    success::{OutputMatcher, SuccessCriteria},
+    suspension::SuspensionDetector,
+    task::{ConcurrencyPolicy, Task, TaskDAO, Trigger},
+};
 
-use crate::task::{Task, TaskDAO, Trigger};
+/// 进程退出之后等待剩余输出处理完毕的最长时间.
+const OUTPUT_DRAIN_TIMEOUT: Duration = Duration::from_secs(1);
 
 #[derive(Debug)]
 enum Msg {
     Reconnect(DatabaseConnection),
+    UpdateConfig(Box<AppConfig>),
+    // 旧名称, 新名称
+    RenameEnvironment(String, String),
     // id
     RemoveTask(i64),
-    // id
-    RunTaskManually(i64),
+    // id, 手动运行时提供的输入
+    RunTaskManually(i64, Option<String>),
     // id, enabled
     SwitchTask(i64, bool),
     SaveTask(Box<Task>),
@@ -33,76 +75,227 @@ enum Msg {
 #[derive(Debug)]
 enum GuardMsg {
     Reconnect(DatabaseConnection),
+    UpdateConfig(Box<AppConfig>),
+    RenameEnvironment(String, String),
     RemoveTask,
     SwitchTask(bool),
-    RunTaskManually,
+    RunTaskManually(Option<String>),
     QueryRunning(oneshot::Sender<TaskStatus>),
     Close,
     StopTask,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
+#[serde(tag = "tag", content = "content")]
 pub(crate) enum TaskStatus {
-    Suspended,
-    Running,
-    Idle,
+    /// 连续失败而暂停自动执行, `resume_at` 为自动恢复的时间.
+    Suspended {
+        since: DateTime<FixedOffset>,
+        reason: String,
+        resume_at: Option<DateTime<FixedOffset>>,
+    },
+    /// 正在运行的实例数量, 设置了健康检查时包含健康状态,
+    /// 使用 `sd_notify` 时包含最新启动的实例报告的状态,
+    /// `progress` 为最新启动的实例通过 `::bgm` 报告的进度和状态.
+    Running {
+        instances: usize,
+        health: Option<Health>,
+        notify: Option<NotifyState>,
+        progress: Option<Progress>,
+    },
+    /// 进程退出之后等待重启, `attempt` 为连续重启的次数.
+    Restarting {
+        attempt: u32,
+        next_retry_at: DateTime<FixedOffset>,
+        last_outcome: Option<Outcome>,
+    },
+    /// 没有运行, `last_outcome` 为上一次运行的结束方式.
+    Idle { last_outcome: Option<Outcome> },
+    /// 使用副本的任务, 按编号列出每个副本的状态.
+    Replicated { replicas: Vec<TaskStatus> },
 }
 
-/// Suspension 逻辑: 在指定秒数内任务触发失败次数达到指定次数则触发 suspension, 暂停任务的自动执行.
-#[derive(Default, Debug)]
-struct SuspensionDetector {
-    start_time: Option<Instant>,
-    failures: usize,
-    has_suspended: bool,
+/// 正在运行的任务进程.
+#[derive(Debug)]
+struct Running {
+    process: Process,
+    run_id: i64,
+    /// 进程 ID, 进程退出之后 `process.id()` 不再可用.
+    pid: Option<u32>,
+    started: Instant,
+    /// 本次运行的 cgroup, 没有设置 `AppConfig::cgroup_root` 时为空.
+    cgroup: Option<Cgroup>,
+    tail: TailGuard,
+    /// 检查输出是否匹配成功条件中的正则表达式, 没有设置正则表达式时为空.
+    matcher: Option<Arc<OutputMatcher>>,
+    /// 接收 `sd_notify` 消息, 任务没有设置 `notify` 时为空.
+    notify: Option<NotifyListener>,
+    /// 输出中报告的进度.
+    progress: ProgressTracker,
+    /// 管理器结束进程的原因, 进程自己退出时为空.
+    killed: Option<Outcome>,
+    /// 本次运行的完整环境变量, 退出之后的 hook 同样使用.
+    env_vars: HashMap<String, String>,
 }
 
-impl SuspensionDetector {
-    const SUSPENSION_TIMESPAN: Duration = Duration::from_secs(3);
-    const SUSPENSION_FAILURES: usize = 6;
+impl Running {
+    /// 结束进程, 使用 cgroup 时同时结束 cgroup 中的所有进程.
+    ///
+    /// `reason` 记录为本次运行的结束方式, 多次结束时保留第一次的原因.
+    async fn kill(&mut self, reason: Outcome) {
+        self.killed.get_or_insert(reason);
+        self.process.kill().await.ok();
+        if let Some(cgroup) = &self.cgroup {
+            cgroup.kill().await;
+        }
+    }
 
-    #[inline]
-    #[must_use]
-    fn new() -> Self {
-        Self::default()
+    /// 等待剩余的输出处理完毕, 然后按照 `criteria` 判断结束方式为 `outcome` 的本次运行是否成功.
+    async fn verdict(&mut self, criteria: &SuccessCriteria, outcome: &Outcome) -> bool {
+        self.tail.finish(OUTPUT_DRAIN_TIMEOUT).await;
+        criteria.verdict(outcome, self.matcher.as_deref())
+    }
+
+    /// 记录 cgroup 统计的资源使用, 然后删除本次运行的 cgroup.
+    ///
+    /// 没有被结束 (`killed` 为 false) 且还有后台进程留在 cgroup 中时保留 cgroup, 在删除任务时清理.
+    async fn release_cgroup(&mut self, db: &DatabaseConnection, killed: bool) {
+        let Some(cgroup) = self.cgroup.take() else {
+            return;
+        };
+        match cgroup.stats().await {
+            Ok(stats) => {
+                db.set_run_usage(self.run_id, &stats).await.ok();
+            }
+            Err(e) => warn!("failed to read cgroup stats of run {}: {e:?}", self.run_id),
+        }
+        if killed || !cgroup.is_populated().await {
+            cgroup.remove().await;
+        }
     }
+}
+
+/// 任务正在运行的实例和排队等待的运行.
+#[derive(Debug, Default)]
+struct Instances {
+    /// 按启动时间从旧到新排列.
+    running: Vec<Running>,
+    /// 排队等待的运行及其输入, 只在 [`ConcurrencyPolicy::Queue`] 时使用.
+    queued: Option<Option<String>>,
+    /// 上一次运行的结束方式.
+    last_outcome: Option<Outcome>,
+}
 
+impl Instances {
     #[inline]
     #[must_use]
-    fn suspended(&self) -> bool {
-        self.has_suspended
+    fn is_empty(&self) -> bool {
+        self.running.is_empty()
     }
 
-    /// 记录一次失败, 并返回是否为 suspended 状态.
-    fn fail(&mut self) -> bool {
-        if self.has_suspended {
-            return true;
+    /// 等待任意一个实例退出, 返回实例的位置和退出状态, 没有实例时永远不会完成.
+    ///
+    /// 接管的进程退出之后没有退出状态.
+    async fn wait_any(&mut self) -> (usize, std::io::Result<Option<ExitStatus>>) {
+        let mut waits: Vec<_> = self
+            .running
+            .iter_mut()
+            .map(|r| Box::pin(r.process.wait()))
+            .collect();
+        std::future::poll_fn(|cx| {
+            for (i, wait) in waits.iter_mut().enumerate() {
+                if let Poll::Ready(status) = wait.as_mut().poll(cx) {
+                    return Poll::Ready((i, status));
+                }
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// 等待任意一个实例的看门狗超时, 返回实例的位置, 没有使用看门狗时永远不会完成.
+    ///
+    /// 返回的 future 不借用 `self`, 可以和 [`Instances::wait_any`] 同时等待.
+    fn wait_watchdog(&self) -> impl Future<Output = usize> + use<> {
+        let mut waits: Vec<_> = self
+            .running
+            .iter()
+            .enumerate()
+            .filter_map(|(i, r)| r.notify.as_ref().map(|n| (i, Box::pin(n.expired()))))
+            .collect();
+        std::future::poll_fn(move |cx| {
+            for (i, wait) in &mut waits {
+                if wait.as_mut().poll(cx).is_ready() {
+                    return Poll::Ready(*i);
+                }
+            }
+            Poll::Pending
+        })
+    }
+
+    /// 记录一次被跳过的触发, 计入最新启动的实例的运行记录.
+    async fn skip(&self, db: &DatabaseConnection, task_id: i64) {
+        info!("task {task_id} is already running, trigger skipped");
+        if let Some(r) = self.running.last() {
+            db.add_run_skipped_trigger(r.run_id).await.ok();
         }
-        self.failures += 1;
-        if let Some(start_time) = self.start_time
-            && Instant::now().duration_since(start_time) <= Self::SUSPENSION_TIMESPAN
-            && self.failures >= Self::SUSPENSION_FAILURES
-        {
-            self.has_suspended = true;
-            return true;
-        } else {
-            self.start_time = Some(Instant::now());
-            if self.failures >= Self::SUSPENSION_FAILURES {
-                self.has_suspended = true;
-                return true;
+    }
+
+    /// 结束所有实例并清空队列, 然后运行退出之后的 hook.
+    ///
+    /// `update_task` 为 false 时只记录运行结束, 不更新任务的结束方式, 任务可能已经被删除或者被重新创建.
+    async fn stop(&mut self, db: &DatabaseConnection, task: &Task, update_task: bool) {
+        self.queued = None;
+        for mut r in self.running.drain(..) {
+            r.kill(Outcome::Stopped).await;
+            let status = r.process.wait().await.ok().flatten();
+            let outcome = Outcome::new(status.as_ref(), r.killed.take());
+            let succeeded = Some(r.verdict(&task.success, &outcome).await);
+            hooks::after_exit(
+                task,
+                std::mem::take(&mut r.env_vars),
+                r.run_id,
+                r.pid,
+                status,
+                succeeded,
+            );
+            match task.id {
+                Some(id) if update_task => {
+                    Scheduler::record_exit(db, id, task.instance, r.run_id, &outcome, succeeded)
+                        .await;
+                    self.last_outcome = Some(outcome);
+                }
+                _ => {
+                    db.finish_run(r.run_id, &outcome, succeeded, chrono::Local::now().into())
+                        .await
+                        .ok();
+                }
             }
+            r.release_cgroup(db, true).await;
         }
-        false
     }
 
-    fn reset(&mut self) {
-        self.start_time = None;
-        self.failures = 0;
-        self.has_suspended = false;
+    /// 管理器退出时保留所有实例, 进程继续运行, 运行记录保持未结束, 在下一次启动时接管.
+    fn detach(&mut self, task_id: i64) {
+        self.queued = None;
+        for r in self.running.drain(..) {
+            info!("leaving run {} of task {task_id} running", r.run_id);
+        }
     }
 }
 
+/// 触发没有启动运行的原因.
+#[derive(Debug, PartialEq, Eq)]
+enum NotLaunched {
+    /// 任务已经在运行, 按照 [`ConcurrencyPolicy`] 跳过了本次触发.
+    Skipped,
+    /// 任务被禁用, 或者启动失败.
+    Failed,
+}
+
 pub(crate) struct Scheduler {
     tx: mpsc::Sender<Msg>,
+    output_tx: OutputSender,
     schedule_handle: JoinHandle<crate::Result<()>>,
 }
 
@@ -112,6 +305,44 @@ impl Drop for Scheduler {
     }
 }
 
+/// 检查管理器退出之后继续运行的任务 ([`OnExit::Detach`]) 的选项.
+///
+/// 只有 Linux 能够在管理器重启之后接管进程. 进程的输出不能经过管理器, 见 [`output::redirect`].
+fn check_detached(task: &Task) -> crate::Result<()> {
+    let pumped = (task.stdout.is_some() && task.stdout_options.needs_pump())
+        || (task.stderr.is_some() && !task.merge_stderr && task.stderr_options.needs_pump());
+    let msg = if !cfg!(target_os = "linux") {
+        "tasks can only be left running on exit on Linux"
+    } else if task.pty.is_some() {
+        "tasks left running on exit cannot use a pty"
+    } else if task.progress_fd.is_some() {
+        // 管理器退出之后管道被关闭, 任务继续写入时会收到 SIGPIPE
+        "tasks left running on exit cannot use a progress fd"
+    } else if pumped {
+        "tasks left running on exit cannot add timestamps to or rotate output"
+    } else {
+        return Ok(());
+    };
+    Err(crate::Error::with_message(crate::ErrorKind::Io, msg))
+}
+
+/// 检查使用副本的任务的输出文件, 每个副本必须写入不同的文件.
+fn check_replicated(task: &Task) -> crate::Result<()> {
+    let stderr = task.stderr.as_ref().filter(|_| !task.merge_stderr);
+    for path in task.stdout.iter().chain(stderr) {
+        if !path.to_string_lossy().contains("${INSTANCE}") {
+            return Err(crate::Error::with_message(
+                crate::ErrorKind::Io,
+                format!(
+                    "output file {} of replicated task must contain ${{INSTANCE}}",
+                    path.display()
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn failed_to_send(e: mpsc::error::SendError<Msg>) -> crate::Error {
     crate::Error::with_source(
         crate::ErrorKind::Io,
@@ -129,11 +360,20 @@ fn failed_to_recv(e: oneshot::error::RecvError) -> crate::Error {
 }
 
 impl Scheduler {
-    pub(crate) async fn bind(db: DatabaseConnection) -> Self {
+    pub(crate) async fn bind(
+        db: DatabaseConnection,
+        config: AppConfig,
+        base_env: BaseEnvReceiver,
+    ) -> Self {
         let (tx, rx) = mpsc::channel(100);
-        let schedule_handle = tokio::spawn(async move { Self::schedule(rx, db).await });
+        let output_tx = OutputSender::new(1024);
+        let schedule_output_tx = output_tx.clone();
+        let schedule_handle = tokio::spawn(async move {
+            Self::schedule(rx, db, config, base_env, schedule_output_tx).await
+        });
         Scheduler {
             tx,
+            output_tx,
             schedule_handle,
         }
     }
@@ -141,40 +381,69 @@ impl Scheduler {
     async fn schedule(
         mut rx: mpsc::Receiver<Msg>,
         mut db: DatabaseConnection,
+        mut config: AppConfig,
+        base_env: BaseEnvReceiver,
+        output_tx: OutputSender,
     ) -> crate::Result<()> {
-        let mut guards: HashMap<i64, mpsc::Sender<GuardMsg>> = HashMap::new();
+        // 每个任务的每个副本各有一个 guard, 按副本编号排列
+        let mut guards: HashMap<i64, Vec<mpsc::Sender<GuardMsg>>> = HashMap::new();
 
         for task in db.list_tasks().await? {
             let Some(id) = task.id else {
                 continue;
             };
-            let (guard_tx, guard_rx) = mpsc::channel(10);
-            guards.insert(id, guard_tx);
-            let db = db.clone();
-            tokio::spawn(async move { Self::task_guard(db, task, guard_rx).await });
+            let replicas = Self::spawn_guards(&db, &task, &config, &base_env, &output_tx, true);
+            guards.insert(id, replicas);
         }
 
         while let Some(msg) = rx.recv().await {
             match msg {
                 Msg::Reconnect(conn) => {
                     db = conn.clone();
-                    for guard_tx in guards.values() {
+                    for guard_tx in guards.values().flatten() {
                         guard_tx.send(GuardMsg::Reconnect(conn.clone())).await.ok();
                     }
                 }
+                Msg::UpdateConfig(new_config) => {
+                    for guard_tx in guards.values().flatten() {
+                        guard_tx
+                            .send(GuardMsg::UpdateConfig(new_config.clone()))
+                            .await
+                            .ok();
+                    }
+                    config = *new_config;
+                }
+                Msg::RenameEnvironment(old, new) => {
+                    for guard_tx in guards.values().flatten() {
+                        guard_tx
+                            .send(GuardMsg::RenameEnvironment(old.clone(), new.clone()))
+                            .await
+                            .ok();
+                    }
+                }
                 Msg::RemoveTask(id) => {
-                    if let Some(guard_tx) = guards.get(&id) {
+                    for guard_tx in guards.remove(&id).into_iter().flatten() {
                         guard_tx.send(GuardMsg::RemoveTask).await.ok();
-                    };
-                    guards.remove(&id);
+                    }
                     if let Err(e) = db.remove_task(id).await {
                         warn!("failed to remove task {id}: {e:?}");
                     }
+                    match db.remove_runs(id).await {
+                        Ok(runs) => remove_run_files(&runs).await,
+                        Err(e) => warn!("failed to remove runs of task {id}: {e:?}"),
+                    }
+                    // 清理任务的 cgroup, 包括留在其中的后台进程
+                    if let Some(root) = config.cgroup_root() {
+                        tokio::spawn(Cgroup::of_task(root, id).remove());
+                    }
                 }
-                Msg::RunTaskManually(id) => {
-                    if let Some(guard_tx) = guards.get(&id) {
-                        guard_tx.send(GuardMsg::RunTaskManually).await.ok();
-                    };
+                Msg::RunTaskManually(id, payload) => {
+                    for guard_tx in guards.get(&id).into_iter().flatten() {
+                        guard_tx
+                            .send(GuardMsg::RunTaskManually(payload.clone()))
+                            .await
+                            .ok();
+                    }
                 }
                 Msg::SaveTask(task) => {
                     // 不管是添加还是修改 task, 都删除原来的 guard, 创建新的 guard.
@@ -190,36 +459,66 @@ impl Scheduler {
                             continue;
                         }
                     };
-                    if let Some(guard_tx) = guards.get(&id) {
+                    for guard_tx in guards.get(&id).into_iter().flatten() {
                         guard_tx.send(GuardMsg::RemoveTask).await.ok();
                     }
                     task.id = Some(id);
-                    let (guard_tx, guard_rx) = mpsc::channel(10);
-                    guards.insert(id, guard_tx);
-                    let db = db.clone();
-                    tokio::spawn(async move { Self::task_guard(db, task, guard_rx).await });
+                    // 修改之后的任务和所有副本重新开始统计失败
+                    task.suspended = None;
+                    if let Err(e) = db.clear_task_suspensions(id).await {
+                        warn!("failed to clear suspensions of task {id}: {e:?}");
+                    }
+                    // 保存的任务不包含副本的状态, 从数据库中读取副本上一次的结束方式
+                    task.replica_states = match db.get_task(id).await {
+                        Ok(Some(saved)) => saved.replica_states,
+                        _ => HashMap::new(),
+                    };
+                    let replicas =
+                        Self::spawn_guards(&db, &task, &config, &base_env, &output_tx, false);
+                    guards.insert(id, replicas);
                 }
                 Msg::SwitchTask(id, enabled) => {
-                    if let Some(guard_tx) = guards.get(&id) {
+                    for guard_tx in guards.get(&id).into_iter().flatten() {
                         guard_tx.send(GuardMsg::SwitchTask(enabled)).await.ok();
                     }
                     if let Err(e) = db.switch_task(id, enabled).await {
                         warn!("failed to switch task {id}: {e:?}");
                     }
                 }
-                Msg::QueryRunning(id, tx) => {
-                    if let Some(guard_tx) = guards.get(&id) {
+                Msg::QueryRunning(id, tx) => match guards.get(&id).map(Vec::as_slice) {
+                    Some([guard_tx]) => {
                         guard_tx.send(GuardMsg::QueryRunning(tx)).await.ok();
                     }
-                }
+                    Some(replicas) => {
+                        let mut pending = Vec::with_capacity(replicas.len());
+                        for guard_tx in replicas {
+                            let (replica_tx, replica_rx) = oneshot::channel();
+                            guard_tx.send(GuardMsg::QueryRunning(replica_tx)).await.ok();
+                            pending.push(replica_rx);
+                        }
+                        // 在单独的协程中等待所有副本回复, 不阻塞调度
+                        tokio::spawn(async move {
+                            let mut replicas = Vec::with_capacity(pending.len());
+                            for replica_rx in pending {
+                                replicas.push(
+                                    replica_rx
+                                        .await
+                                        .unwrap_or(TaskStatus::Idle { last_outcome: None }),
+                                );
+                            }
+                            tx.send(TaskStatus::Replicated { replicas }).ok();
+                        });
+                    }
+                    None => {}
+                },
                 Msg::Close => {
-                    for guard_tx in guards.values() {
+                    for guard_tx in guards.values().flatten() {
                         guard_tx.send(GuardMsg::Close).await.ok();
                     }
                     break;
                 }
                 Msg::StopTask(id) => {
-                    if let Some(guard_tx) = guards.get(&id) {
+                    for guard_tx in guards.get(&id).into_iter().flatten() {
                         guard_tx.send(GuardMsg::StopTask).await.ok();
                     }
                 }
@@ -228,28 +527,99 @@ impl Scheduler {
         Ok(())
     }
 
+    /// 为任务的每个副本创建 guard, 返回按副本编号排列的发送端.
+    fn spawn_guards(
+        db: &DatabaseConnection,
+        task: &Task,
+        config: &AppConfig,
+        base_env: &BaseEnvReceiver,
+        output_tx: &OutputSender,
+        adopt: bool,
+    ) -> Vec<mpsc::Sender<GuardMsg>> {
+        task.instances()
+            .into_iter()
+            .map(|task| {
+                let (guard_tx, guard_rx) = mpsc::channel(10);
+                let db = db.clone();
+                let config = config.clone();
+                let base_env = base_env.clone();
+                let output_tx = output_tx.clone();
+                tokio::spawn(async move {
+                    Self::task_guard(db, task, config, base_env, guard_rx, output_tx, adopt).await
+                });
+                guard_tx
+            })
+            .collect()
+    }
+
+    /// 管理一个任务 (或者任务的一个副本) 的触发和运行.
+    ///
+    /// `adopt` 为 true 时先接管管理器上一次退出时留下的进程, 只在管理器启动时使用.
+    /// 任务的环境变量以 `base_env` 为基础, 见 [`environment::compose`], 第一次启动时等待捕获完成.
     async fn task_guard(
         mut db: DatabaseConnection,
         mut task: Task,
+        mut config: AppConfig,
+        base_env: BaseEnvReceiver,
         mut rx: mpsc::Receiver<GuardMsg>,
+        output_tx: OutputSender,
+        adopt: bool,
     ) -> crate::Result<()> {
         let id = task.id.unwrap();
-        let mut child: Option<Child> = None;
-        let mut suspension_detector = SuspensionDetector::new();
+        let mut instances = Instances {
+            last_outcome: task.last_outcome.take(),
+            ..Default::default()
+        };
+        let mut suspension_detector = SuspensionDetector::new(task.suspended.take());
+        let mut backoff = Backoff::default();
+        let mut health_monitor = HealthMonitor::default();
+        // 健康检查针对的运行, 即最新启动的实例
+        let mut health_run = None;
         let mut instant_launched = false;
+        // Instant 触发启动失败之后的重试次数
+        let mut instant_attempts = 0;
+
+        if adopt {
+            Self::adopt(&mut instances, &db, &task, &config, &output_tx).await;
+        }
 
         // 初始化触发器
         let mut interval = None;
         let mut instant = None;
         match task.trigger {
+            // 接管了上一次留下的进程时不再启动新的进程
+            Trigger::Startup | Trigger::KeepAlive | Trigger::UntilSucceed
+                if !instances.is_empty() => {}
             Trigger::Routine(d) => {
                 interval = Some(tokio::time::interval(d));
             }
             Trigger::Startup => {
-                Self::run_and_record(&mut child, &db, &task).await.ok();
+                Self::run_and_record(
+                    &mut instances,
+                    &db,
+                    &task,
+                    &config,
+                    &base_env,
+                    &output_tx,
+                    None,
+                )
+                .await
+                .ok();
             }
             Trigger::KeepAlive => {
-                Self::run_and_record(&mut child, &db, &task).await.ok();
+                if !suspension_detector.suspended() {
+                    Self::run_and_record(
+                        &mut instances,
+                        &db,
+                        &task,
+                        &config,
+                        &base_env,
+                        &output_tx,
+                        None,
+                    )
+                    .await
+                    .ok();
+                }
             }
             Trigger::Manual => (),
             Trigger::Instant(date_time) => {
@@ -263,60 +633,100 @@ impl Scheduler {
                 }
             }
             Trigger::UntilSucceed => {
-                Self::run_and_record(&mut child, &db, &task).await.ok();
+                Self::run_and_record(
+                    &mut instances,
+                    &db,
+                    &task,
+                    &config,
+                    &base_env,
+                    &output_tx,
+                    None,
+                )
+                .await
+                .ok();
             }
         }
 
         loop {
+            // 新启动的进程 (包括替换和排队的运行) 重新开始健康检查
+            let newest = instances.running.last();
+            if newest.map(|r| r.run_id) != health_run {
+                health_run = newest.map(|r| r.run_id);
+                health_monitor.reset();
+                if let (Some(r), Trigger::KeepAlive, Some(check)) =
+                    (newest, &task.trigger, &task.health_check)
+                {
+                    health_monitor.start(check, &task.identity, &r.env_vars);
+                }
+            }
             tokio::select! {
                 // 监听外部控制消息
                 Some(msg) = rx.recv() => {
                     match msg {
                         GuardMsg::Reconnect(new_conn) => db = new_conn,
-                        GuardMsg::RemoveTask => {
-                            if let Some(c) = &mut child {
-                                c.kill().await.ok();
+                        GuardMsg::UpdateConfig(new_config) => config = *new_config,
+                        GuardMsg::RenameEnvironment(old, new) => {
+                            if task.environment.as_ref() == Some(&old) {
+                                task.environment = Some(new);
                             }
-                            break; // 退出 guard, 这里的 exit_code 不需要记录到数据库, 因为数据已经删除了.
+                        }
+                        GuardMsg::RemoveTask => {
+                            // 这里的 exit_code 不需要记录到任务中, 任务可能已经被删除或者被重新创建.
+                            instances.stop(&db, &task, false).await;
+                            break; // 退出 guard
                         },
-                        GuardMsg::RunTaskManually => {
-                            suspension_detector.reset();
-                            Self::run_and_record(&mut child, &db, &task).await.ok();
+                        GuardMsg::RunTaskManually(payload) => {
+                            Self::resume(&mut suspension_detector, &db, id, task.instance).await;
+                            backoff.reset();
+                            Self::run_and_record(&mut instances, &db, &task, &config, &base_env, &output_tx, payload.as_deref()).await.ok();
                         }
                         GuardMsg::SwitchTask(enabled) => {
-                            suspension_detector.reset();
+                            Self::resume(&mut suspension_detector, &db, id, task.instance).await;
+                            backoff.reset();
                             task.enabled = enabled;
                             if !enabled {
-                                if let Some(child) = &mut child  {
-                                    child.kill().await.ok();
+                                instances.queued = None;
+                                for r in &mut instances.running {
+                                    r.kill(Outcome::Stopped).await;
                                 }
                             } else if let Trigger::KeepAlive = task.trigger {
-                                Self::run_and_record(&mut child, &db, &task).await.ok();
+                                Self::run_and_record(&mut instances, &db, &task, &config, &base_env, &output_tx, None).await.ok();
                             }
                         }
                         GuardMsg::QueryRunning(tx) => {
-                            tx.send(if child.is_some() {
-                                TaskStatus::Running
-                            } else if suspension_detector.suspended() {
-                                TaskStatus::Suspended
+                            tx.send(if !instances.is_empty() {
+                                TaskStatus::Running {
+                                    instances: instances.running.len(),
+                                    health: task.health_check.as_ref().map(|_| health_monitor.health().clone()),
+                                    notify: instances.running.last().and_then(|r| r.notify.as_ref()).map(NotifyListener::state),
+                                    progress: instances.running.last().and_then(|r| r.progress.progress()),
+                                }
+                            } else if let Some(s) = suspension_detector.suspension() {
+                                TaskStatus::Suspended {
+                                    since: s.since,
+                                    reason: s.reason.clone(),
+                                    resume_at: s.resume_at(task.suspension.as_ref().unwrap_or(config.suspension())),
+                                }
+                            } else if let Some(next_retry_at) = backoff.next_retry_at() {
+                                TaskStatus::Restarting {
+                                    attempt: backoff.attempt(),
+                                    next_retry_at,
+                                    last_outcome: instances.last_outcome.clone(),
+                                }
                             } else {
-                                TaskStatus::Idle
+                                TaskStatus::Idle { last_outcome: instances.last_outcome.clone() }
                             }).ok();
                         }
                         GuardMsg::Close => {
-                            if let Some(mut c) = child.take() {
-                                c.kill().await.ok();
-                                let code = c.wait().await.ok().and_then(|s| s.code()).unwrap_or(-1);
-                                db.update_task_exit_code(id, code as i64).await.ok();
+                            match task.on_exit {
+                                OnExit::Kill => instances.stop(&db, &task, true).await,
+                                OnExit::Detach => instances.detach(id),
                             }
                             break;
                         }
                         GuardMsg::StopTask => {
-                            if let Some(mut c) = child.take() {
-                                c.kill().await.ok();
-                                let code = c.wait().await.ok().and_then(|s| s.code()).unwrap_or(-1);
-                                db.update_task_exit_code(id, code as i64).await.ok();
-                            }
+                            backoff.reset();
+                            instances.stop(&db, &task, true).await;
                         }
                     }
                 }
@@ -329,7 +739,7 @@ impl Scheduler {
                         None
                     }
                 } => {
-                    Self::run_and_record(&mut child, &db, &task).await.ok();
+                    Self::run_and_record(&mut instances, &db, &task, &config, &base_env, &output_tx, None).await.ok();
                 }
 
                 // 指定时间触发 (Instant)
@@ -341,36 +751,111 @@ impl Scheduler {
                         None
                     }
                 }, if !instant_launched => {
-                    if Self::run_and_record(&mut child, &db, &task).await.is_ok() {
-                        instant_launched = true;
+                    match Self::run_and_record(&mut instances, &db, &task, &config, &base_env, &output_tx, None).await {
+                        // 被并发策略跳过说明任务已经在运行, 这次触发已经没有意义, 不再重试
+                        Ok(()) | Err(NotLaunched::Skipped) => instant_launched = true,
+                        Err(NotLaunched::Failed) => {
+                            // 按照重启策略的间隔重试, 避免立即重试
+                            instant_attempts += 1;
+                            if task.restart.max_attempts.is_some_and(|max| instant_attempts > max) {
+                                warn!("task {id} reached the maximum number of launch attempts");
+                                instant_launched = true;
+                            } else {
+                                instant = Some(Instant::now() + task.restart.delay(instant_attempts));
+                            }
+                        }
+                    }
+                }
+
+                // 暂停之后自动恢复
+                _ = suspension_detector.wait_resume(task.suspension.as_ref().unwrap_or(config.suspension())), if suspension_detector.suspended() => {
+                    info!("task {id} resumed after suspension cooldown");
+                    Self::resume(&mut suspension_detector, &db, id, task.instance).await;
+                    if let Trigger::KeepAlive = task.trigger && !backoff.is_pending() {
+                        Self::run_and_record(&mut instances, &db, &task, &config, &base_env, &output_tx, None).await.ok();
+                    }
+                }
+
+                // 健康检查 (KeepAlive), 连续失败时结束进程, 由重启策略重新启动
+                healthy = health_monitor.next(), if !instances.is_empty() => {
+                    if !healthy {
+                        if let Health::Unhealthy { reason, .. } = health_monitor.health() {
+                            warn!("task {id} is unhealthy, restarting: {reason}");
+                        }
+                        for r in &mut instances.running {
+                            r.kill(Outcome::Unhealthy).await;
+                        }
                     }
                 }
 
+                // sd_notify 看门狗超时, 结束进程, 由重启策略重新启动
+                index = instances.wait_watchdog(), if !instances.is_empty() => {
+                    let r = &mut instances.running[index];
+                    warn!("task {id} missed watchdog deadline, killing run {}", r.run_id);
+                    r.kill(Outcome::TimedOut).await;
+                }
+
+                // 等待重启 (KeepAlive/UntilSucceed)
+                _ = backoff.wait(), if backoff.is_pending() => {
+                    backoff.fire();
+                    Self::run_and_record(&mut instances, &db, &task, &config, &base_env, &output_tx, None).await.ok();
+                }
+
                 // 监控进程退出 (KeepAlive/UntilSucceed 逻辑)
-                // 注意：只有当 child 存在时才激活此分支
-                status = async {
-                    if let Some(c) = &mut child {
-                        Some(c.wait().await)
-                    } else {
+                // 注意：只有当存在运行中的实例时才激活此分支
+                (index, status) = instances.wait_any(), if !instances.is_empty() => {
+                    let mut r = instances.running.remove(index);
+                    let exit_status = status.ok().flatten();
+                    let outcome = Outcome::new(exit_status.as_ref(), r.killed.take());
+                    // 没有退出码 (例如被信号结束) 时按照 -1 匹配重启策略中的退出码
+                    let code = outcome.code().unwrap_or(-1);
+                    // 接管的进程退出之后没有退出状态, 无法判断是否成功, 不运行 on_failure 也不计入暂停的失败次数
+                    let succeeded = if outcome == Outcome::Unknown {
                         None
+                    } else {
+                        Some(r.verdict(&task.success, &outcome).await)
+                    };
+                    Self::record_exit(&db, id, task.instance, r.run_id, &outcome, succeeded).await;
+                    hooks::after_exit(&task, std::mem::take(&mut r.env_vars), r.run_id, r.pid, exit_status, succeeded);
+                    // 管理器结束的进程不是因为超出限制
+                    if let Outcome::Signaled { .. } = outcome
+                        && let Some(violation) = exit_status.and_then(|s| limits::violation(&s, &task.limits))
+                    {
+                        warn!("task {id} exceeded resource limit: {violation:?}");
+                        db.set_run_limit_violation(r.run_id, violation).await.ok();
                     }
-                }, if child.is_some() => {
-                    if let Some(exit_status) = status {
-                        let code = exit_status.ok().and_then(|s| s.code()).unwrap_or(-1) as i64;
-                        db.update_task_exit_code(id, code).await.ok();
-                        child = None;
-
-                        // 如果是 KeepAlive，立即重新启动
-                        if let Trigger::KeepAlive = task.trigger {
-                            if code != 0 {
-                                suspension_detector.fail();
-                            }
-                            if !suspension_detector.suspended() {
-                                Self::run_and_record(&mut child, &db, &task).await.ok();
+                    r.release_cgroup(&db, false).await;
+
+                    // KeepAlive 和 UntilSucceed 按照重启策略等待之后重新启动
+                    let restart = match task.trigger {
+                        Trigger::KeepAlive => {
+                            let policy = task.suspension.as_ref().unwrap_or(config.suspension());
+                            if succeeded == Some(false)
+                                && let Some(suspension) = suspension_detector.fail(policy, &outcome)
+                            {
+                                warn!("task {id} suspended: {}", suspension.reason);
+                                if let Err(e) = db.set_task_suspension(id, task.instance, Some(suspension)).await {
+                                    warn!("failed to save suspension of task {id}: {e:?}");
+                                }
                             }
-                        } else if let Trigger::UntilSucceed = task.trigger && code != 0 {
-                            Self::run_and_record(&mut child, &db, &task).await.ok();
+                            // 健康检查失败而被结束的进程总是重启, 不受退出码的限制
+                            !suspension_detector.suspended()
+                                && (outcome == Outcome::Unhealthy || task.restart.restarts_on(code))
                         }
+                        Trigger::UntilSucceed => succeeded != Some(true) && task.restart.restarts_on(code),
+                        _ => false,
+                    };
+                    instances.last_outcome = Some(outcome);
+                    if !restart {
+                        backoff.reset();
+                    } else if task.enabled && !backoff.schedule(&task.restart, r.started.elapsed()) {
+                        warn!("task {id} reached the maximum number of restart attempts");
+                    }
+                    // 启动排队的运行
+                    if instances.is_empty()
+                        && let Some(payload) = instances.queued.take()
+                    {
+                        Self::run_and_record(&mut instances, &db, &task, &config, &base_env, &output_tx, payload.as_deref()).await.ok();
                     }
                 }
             }
@@ -378,65 +863,438 @@ impl Scheduler {
         Ok(())
     }
 
-    /// 辅助函数：运行程序并更新数据库中的最后运行时间, 不会等待子进程结束.
+    /// 辅助函数：运行程序并更新数据库中的最后运行时间, 创建运行记录, 不会等待子进程结束.
+    ///
+    /// `payload` 为触发本次运行的事件提供的内容, 可以作为标准输入.
+    ///
+    /// 任务已经在运行时按照任务的 [`ConcurrencyPolicy`] 跳过, 排队, 替换或者并行运行.
     ///
     /// # Returns
-    /// 是否执行, 执行成功则返回 Ok
+    /// 是否执行, 执行成功或者已排队则返回 Ok, 否则返回没有启动的原因
     async fn run_and_record(
-        child: &mut Option<Child>,
+        instances: &mut Instances,
         db: &DatabaseConnection,
         task: &Task,
-    ) -> Result<(), ()> {
-        if child.is_some() {
-            return Err(());
-        }
+        config: &AppConfig,
+        base_env: &BaseEnvReceiver,
+        output_tx: &OutputSender,
+        payload: Option<&str>,
+    ) -> Result<(), NotLaunched> {
         if !task.enabled {
-            return Err(());
+            return Err(NotLaunched::Failed);
         }
 
         let id = task.id.unwrap();
+        if !instances.is_empty() {
+            match task.concurrency {
+                ConcurrencyPolicy::Skip => {
+                    instances.skip(db, id).await;
+                    return Err(NotLaunched::Skipped);
+                }
+                ConcurrencyPolicy::Queue => {
+                    if instances.queued.is_some() {
+                        instances.skip(db, id).await;
+                        return Err(NotLaunched::Skipped);
+                    }
+                    instances.queued = Some(payload.map(str::to_string));
+                    return Ok(());
+                }
+                ConcurrencyPolicy::Replace => instances.stop(db, task, true).await,
+                ConcurrencyPolicy::Parallel(max) => {
+                    if instances.running.len() >= max.max(1) {
+                        instances.skip(db, id).await;
+                        return Err(NotLaunched::Skipped);
+                    }
+                }
+            }
+        }
+
+        // 管理器启动之后的第一次运行等待登录 shell 的环境变量捕获完成
+        let base_env = shell_env::wait_base_env(base_env).await;
+        let now = chrono::Local::now().into();
         // 更新最后运行时间
-        db.update_task_run_at(id, chrono::Local::now().into())
-            .await
-            .ok();
+        db.update_task_run_at(id, now).await.ok();
+        // 创建运行记录
+        let run_id = match db.create_run(id, task.instance, now).await {
+            Ok(run_id) => run_id,
+            Err(e) => {
+                warn!("failed to create run of task {id}: {e:?}");
+                return Err(NotLaunched::Failed);
+            }
+        };
+        match db.prune_runs(id, RUN_HISTORY_LIMIT).await {
+            Ok(stale) => remove_run_files(&stale).await,
+            Err(e) => warn!("failed to prune runs of task {id}: {e:?}"),
+        }
+        // 每次启动时重新生成完整的环境变量, 任务引用的环境修改之后立即生效
+        let task = &match environment::compose(db, task, &base_env).await {
+            Ok(env_vars) => Task {
+                env_vars,
+                ..task.clone()
+            },
+            Err(e) => {
+                warn!("failed to prepare environment of task {id}: {e:?}");
+                Self::record_launch_failure(instances, db, task, run_id, &e).await;
+                return Err(NotLaunched::Failed);
+            }
+        };
+        // 没有设置重定向文件的输出捕获到运行记录的输出文件中
+        let capture = if output::needs_capture(task) {
+            match run_output_path(id, run_id) {
+                Ok(path) => {
+                    db.set_run_output(run_id, &path).await.ok();
+                    Some(path)
+                }
+                Err(e) => {
+                    warn!("failed to determine output path of run {run_id}: {e:?}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        // 伪终端会话录制到运行记录的录制文件中
+        let recording = if task.pty.is_some() {
+            match run_recording_path(id, run_id) {
+                Ok(path) => {
+                    db.set_run_recording(run_id, &path).await.ok();
+                    Some(path)
+                }
+                Err(e) => {
+                    warn!("failed to determine recording path of run {run_id}: {e:?}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        // 启动之前的 hook 失败时放弃本次运行
+        if let Err(e) = hooks::pre_start(task, run_id).await {
+            warn!("pre_start hook of task {id} failed: {e:?}");
+            Self::record_launch_failure(instances, db, task, run_id, &e).await;
+            return Err(NotLaunched::Failed);
+        }
+        // 使用 sd_notify 时每次运行创建独立的 socket
+        let notify_socket = if task.notify.is_some() {
+            match run_notify_path(id, run_id) {
+                Ok(path) => Some(path),
+                Err(e) => {
+                    warn!("failed to determine notify socket path of run {run_id}: {e:?}");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        // 设置了 cgroup 委派子树时, 本次运行放在任务 cgroup 下独立的子 cgroup 中
+        let cgroup = match config.cgroup_root() {
+            Some(root) => {
+                let created = async {
+                    Cgroup::prepare(root, id, &task.cgroup)
+                        .await?
+                        .create_run(run_id)
+                        .await
+                };
+                match created.await {
+                    Ok(cgroup) => Some(cgroup),
+                    Err(e) => {
+                        warn!("failed to create cgroup of run {run_id}: {e:?}");
+                        Self::record_launch_failure(instances, db, task, run_id, &e).await;
+                        return Err(NotLaunched::Failed);
+                    }
+                }
+            }
+            None => None,
+        };
+        // 成功条件中的正则表达式在保存任务时已经检查过
+        let matcher = match OutputMatcher::new(&task.success) {
+            Ok(matcher) => matcher.map(Arc::new),
+            Err(e) => {
+                warn!("failed to prepare output matcher of task {id}: {e:?}");
+                None
+            }
+        };
         // 启动进程
-        match Self::run_task(task.clone()).await {
-            Ok(new_child) => {
-                *child = Some(new_child);
+        let progress = ProgressTracker::default();
+        let tap = OutputTap::new(
+            output_tx.clone(),
+            id,
+            run_id,
+            matcher.clone(),
+            progress.clone(),
+        );
+        match Self::run_task(
+            task.clone(),
+            config,
+            capture.as_deref(),
+            recording.as_deref(),
+            notify_socket,
+            cgroup.as_ref(),
+            tap,
+            payload,
+        )
+        .await
+        {
+            Ok((child, tail, notify)) => {
+                let pid = child.id();
+                // 记录进程, 管理器重启之后据此接管或者清理进程
+                if let Some(pid) = pid {
+                    db.set_run_process(run_id, &ProcessIdentity::of(pid))
+                        .await
+                        .ok();
+                }
+                instances.running.push(Running {
+                    pid,
+                    process: Process::Child(child),
+                    run_id,
+                    started: Instant::now(),
+                    cgroup,
+                    tail,
+                    matcher,
+                    notify,
+                    progress,
+                    killed: None,
+                    env_vars: task.env_vars.clone(),
+                });
                 Ok(())
             }
             Err(e) => {
                 warn!("failed to launch task: {e:?}");
-                Err(())
+                Self::record_launch_failure(instances, db, task, run_id, &e).await;
+                if let Some(cgroup) = cgroup {
+                    cgroup.remove().await;
+                }
+                Err(NotLaunched::Failed)
+            }
+        }
+    }
+
+    /// 接管管理器上一次退出时留下的进程, 进程已经退出的运行记录为运行结束.
+    ///
+    /// 任务不是 [`OnExit::Detach`] 时进程不应该留下 (例如管理器崩溃), 结束这些进程.
+    async fn adopt(
+        instances: &mut Instances,
+        db: &DatabaseConnection,
+        task: &Task,
+        config: &AppConfig,
+        output_tx: &OutputSender,
+    ) {
+        let id = task.id.unwrap();
+        let runs = match db.list_unfinished_runs(id).await {
+            Ok(runs) => runs,
+            Err(e) => {
+                warn!("failed to list unfinished runs of task {id}: {e:?}");
+                return;
             }
+        };
+        for run in runs.into_iter().filter(|run| task.adopts(run.instance)) {
+            let adopted = run.pid.map(|pid| {
+                Adopted::open(&ProcessIdentity {
+                    pid,
+                    start_time: run.pid_start_time,
+                    boot_id: run.boot_id.clone(),
+                })
+            });
+            let mut process = match adopted {
+                Some(Ok(adopted)) => Process::Adopted(adopted),
+                Some(Err(e)) => {
+                    info!("run {} of task {id} is no longer running: {e:?}", run.id);
+                    db.finish_run(run.id, &Outcome::Unknown, None, chrono::Local::now().into())
+                        .await
+                        .ok();
+                    continue;
+                }
+                None => {
+                    db.finish_run(run.id, &Outcome::Unknown, None, chrono::Local::now().into())
+                        .await
+                        .ok();
+                    continue;
+                }
+            };
+            if task.on_exit != OnExit::Detach {
+                warn!("killing process left by run {} of task {id}", run.id);
+                process.kill().await.ok();
+                db.finish_run(
+                    run.id,
+                    &Outcome::Stopped,
+                    Some(false),
+                    chrono::Local::now().into(),
+                )
+                .await
+                .ok();
+                continue;
+            }
+            info!(
+                "adopted process {:?} of run {} of task {id}",
+                run.pid, run.id
+            );
+            let cgroup = match config.cgroup_root() {
+                Some(root) => {
+                    let cgroup = Cgroup::of_run(root, id, run.id);
+                    cgroup.is_populated().await.then_some(cgroup)
+                }
+                None => None,
+            };
+            let matcher = OutputMatcher::new(&task.success)
+                .ok()
+                .flatten()
+                .map(Arc::new);
+            // 接管的进程之前报告的进度不会恢复
+            let progress = ProgressTracker::default();
+            let tap = OutputTap::new(
+                output_tx.clone(),
+                id,
+                run.id,
+                matcher.clone(),
+                progress.clone(),
+            );
+            let tail = output::follow(task, run.output.as_deref(), tap).await;
+            // 进程仍然使用原来的 NOTIFY_SOCKET, 在原来的路径重新创建 socket
+            let notify = match &task.notify {
+                Some(options) => {
+                    match run_notify_path(id, run.id)
+                        .and_then(|path| NotifyListener::rebind(path, options))
+                    {
+                        Ok(notify) => Some(notify),
+                        Err(e) => {
+                            warn!("failed to rebind notify socket of run {}: {e:?}", run.id);
+                            None
+                        }
+                    }
+                }
+                None => None,
+            };
+            instances.running.push(Running {
+                pid: run.pid,
+                process,
+                run_id: run.id,
+                started: Instant::now(),
+                cgroup,
+                tail,
+                matcher,
+                notify,
+                progress,
+                killed: None,
+                // 接管的进程无法得知启动时引用的环境, 只使用任务自己的环境变量
+                env_vars: task.env_vars.clone(),
+            });
         }
     }
 
+    /// 解除任务 (或者副本 `instance`) 的 suspension 并清除持久化的状态.
+    async fn resume(
+        detector: &mut SuspensionDetector,
+        db: &DatabaseConnection,
+        id: i64,
+        instance: Option<u32>,
+    ) {
+        if detector.suspended()
+            && let Err(e) = db.set_task_suspension(id, instance, None).await
+        {
+            warn!("failed to clear suspension of task {id}: {e:?}");
+        }
+        detector.reset();
+    }
+
+    /// 记录进程退出, 更新任务 (和副本 `instance`) 的结束方式, 运行结果和运行记录.
+    async fn record_exit(
+        db: &DatabaseConnection,
+        id: i64,
+        instance: Option<u32>,
+        run_id: i64,
+        outcome: &Outcome,
+        succeeded: Option<bool>,
+    ) {
+        if let Err(e) = db
+            .update_task_outcome(id, instance, outcome, succeeded)
+            .await
+        {
+            warn!("failed to update outcome of task {id}: {e:?}");
+        }
+        db.finish_run(run_id, outcome, succeeded, chrono::Local::now().into())
+            .await
+            .ok();
+    }
+
+    /// 记录本次运行没有启动, 失败的原因作为运行的结束方式.
+    async fn record_launch_failure(
+        instances: &mut Instances,
+        db: &DatabaseConnection,
+        task: &Task,
+        run_id: i64,
+        e: &crate::Error,
+    ) {
+        let outcome = Outcome::launch_failure(e);
+        Self::record_exit(
+            db,
+            task.id.unwrap(),
+            task.instance,
+            run_id,
+            &outcome,
+            Some(false),
+        )
+        .await;
+        instances.last_outcome = Some(outcome);
+    }
+
     /// 执行任务程序, 对于macos .app 程序, 使用 open 工具打开, 不支持标准流重定向和获取退出码.
     ///
+    /// 没有设置重定向文件的输出会被捕获到 `capture` 中, 伪终端会话会被录制到 `recording` 中,
+    /// 所有输出通过 `tap` 实时推送. 设置了 `cgroup` 时进程在 exec 之前加入该 cgroup.
+    /// 设置了 `notify_socket` 时在该路径创建 socket 接收 `sd_notify` 消息.
+    /// 输出中的 `::bgm` 报告行由 `tap` 解析为进度.
+    /// 标准输入无法打开时返回错误.
+    ///
     /// # Note
     ///
     /// 不会操作 database 数据, 需要手动修改.
-    async fn run_task(task: Task) -> crate::Result<Child> {
-        let mut cmd = if cfg!(target_os = "macos")
+    #[allow(clippy::too_many_arguments)]
+    async fn run_task(
+        task: Task,
+        config: &AppConfig,
+        capture: Option<&Path>,
+        recording: Option<&Path>,
+        notify_socket: Option<PathBuf>,
+        cgroup: Option<&Cgroup>,
+        tap: OutputTap,
+        payload: Option<&str>,
+    ) -> crate::Result<(Child, TailGuard, Option<NotifyListener>)> {
+        let resolved = if cfg!(target_os = "macos")
             && task.program.is_dir()
             && matches!(
                 task.program.extension().and_then(OsStr::to_str),
                 Some("app")
             ) {
-            let mut cmd = process::Command::new("/usr/bin/open");
-            cmd.arg("-a").arg(&task.program);
-            cmd
+            Resolved {
+                program: PathBuf::from("/usr/bin/open"),
+                args: vec!["-a".into(), task.program.clone().into_os_string()],
+                script: task.program.clone(),
+            }
         } else {
-            process::Command::new(&task.program)
+            let path = task.env_vars.get("PATH").map(OsStr::new);
+            resolve::resolve(&task.program, task.working_dir.as_deref(), path, config).map_err(
+                |e| {
+                    crate::Error::with_source(
+                        crate::ErrorKind::Io,
+                        "failed to resolve task program",
+                        Box::new(e),
+                    )
+                },
+            )?
         };
+        let mut cmd = process::Command::new(&resolved.program);
+        cmd.args(&resolved.args);
         cmd.args(&task.args);
         // 设置环境变量
         cmd.envs(&task.env_vars);
-        if let Some(working_dir) = &task.working_dir {
+        // 没有设置工作目录时使用程序所在的目录, 在搜索路径中找到的程序除外
+        let working_dir = task.working_dir.as_deref().or_else(|| {
+            task.program
+                .parent()
+                .filter(|parent| !parent.as_os_str().is_empty())
+        });
+        if let Some(working_dir) = working_dir {
             cmd.current_dir(working_dir);
-        } else if let Some(parent) = task.program.parent() {
-            cmd.current_dir(parent);
         }
         #[cfg(windows)]
         {
@@ -445,32 +1303,94 @@ impl Scheduler {
                 cmd.creation_flags(CREATE_NO_WINDOW);
             }
         }
-        cmd.kill_on_drop(true);
-
-        if let Some(stdin) = &task.stdin
-            && let Ok(file) = std::fs::File::open(stdin)
-        {
-            cmd.stdin(file);
+        // 管理器退出之后继续运行的进程放在独立的进程组中, 不会收到发给管理器的终端信号
+        cmd.kill_on_drop(task.on_exit == OnExit::Kill);
+        #[cfg(unix)]
+        if task.on_exit == OnExit::Detach {
+            cmd.process_group(0);
         }
-        if let Some(stdout) = &task.stdout
-            && let Ok(file) = std::fs::File::create(stdout)
-        {
-            cmd.stdout(file);
+        // exec 之前的设置步骤失败时报告是哪一步, 和程序本身无法执行区分
+        let steps = Steps::new()?;
+        limits::apply(&mut cmd, &task.limits, steps.reporter());
+        priority::apply(&mut cmd, &task.priority, steps.reporter())?;
+        if let Some(cgroup) = cgroup {
+            cgroup.attach(&mut cmd, steps.reporter())?;
         }
-
-        if let Some(stderr) = &task.stderr
-            && let Ok(file) = std::fs::File::create(stderr)
-        {
-            cmd.stderr(file);
+        if let Some(sandbox) = &task.sandbox {
+            sandbox::apply(&mut cmd, sandbox, steps.reporter())?;
         }
+        // 放在最后, 前面的操作可能需要特权
+        identity::apply(&mut cmd, &task.identity, &task.env_vars, steps.reporter())?;
+        let notify = match (&task.notify, notify_socket) {
+            (Some(options), Some(path)) => Some(NotifyListener::bind(&mut cmd, path, options)?),
+            _ => None,
+        };
+
+        // 设置了专用的进度报告文件描述符时, 管道中的报告和输出中的报告一样交给 `tap`
+        let progress_pipe = match task.progress_fd {
+            Some(fd) => Some(progress::attach(&mut cmd, fd, steps.reporter())?),
+            None => None,
+        };
+
+        let vars = Vars::new(&task, payload);
+        let input = stdin::resolve(&task, &vars, payload)?;
+        // 伪终端模式下输入写入伪终端, 否则交给子进程的标准输入
+        let pending_input = match input {
+            Some(input) if task.pty.is_some() => Some(input.into_bytes()?),
+            Some(input) => input.apply(&mut cmd),
+            None => None,
+        };
+        let pumps = output::redirect(
+            &mut cmd,
+            &task,
+            capture,
+            recording,
+            tap.clone(),
+            steps.reporter(),
+        )
+        .await?;
+        let pty_input = match &pending_input {
+            Some(_) => pumps.pty_input().await?,
+            None => None,
+        };
 
-        cmd.spawn().map_err(|e| {
+        let mut child = cmd.spawn().map_err(|e| {
+            let e = steps
+                .failed()
+                .unwrap_or_else(|| resolve::diagnose(e, &resolved, working_dir));
             crate::Error::with_source(
                 crate::ErrorKind::Io,
                 "failed to run task program",
                 Box::new(e),
             )
-        })
+        })?;
+        let tail = pumps.spawn(&mut child);
+        if let Some(pipe) = progress_pipe {
+            pipe.forward(tap);
+        }
+        if let Some(bytes) = pending_input {
+            if let Some(master) = pty_input {
+                stdin::feed(master, bytes);
+            } else if let Some(writer) = child.stdin.take() {
+                stdin::feed(writer, bytes);
+            }
+        }
+        Ok((child, tail, notify))
+    }
+
+    pub(crate) async fn refresh_config(&self, config: AppConfig) -> crate::Result<()> {
+        self.tx
+            .send(Msg::UpdateConfig(Box::new(config)))
+            .await
+            .map_err(failed_to_send)
+    }
+
+    /// 环境改名之后更新引用它的任务, 数据库中的任务已经修改.
+    pub(crate) async fn rename_environment(&self, old: String, new: String) -> crate::Result<()> {
+        self.tx
+            .send(Msg::RenameEnvironment(old, new))
+            .await
+            .map_err(failed_to_send)
     }
 
     pub(crate) async fn refresh_connection(&self, db: DatabaseConnection) -> crate::Result<()> {
@@ -480,9 +1400,16 @@ impl Scheduler {
             .map_err(failed_to_send)
     }
 
-    pub(crate) async fn manually_run_task(&self, id: i64) -> crate::Result<()> {
+    /// 手动运行任务, `payload` 作为来源为 [`StdinSource::Trigger`] 的任务的标准输入.
+    ///
+    /// [`StdinSource::Trigger`]: crate::stdin::StdinSource::Trigger
+    pub(crate) async fn manually_run_task(
+        &self,
+        id: i64,
+        payload: Option<String>,
+    ) -> crate::Result<()> {
         self.tx
-            .send(Msg::RunTaskManually(id))
+            .send(Msg::RunTaskManually(id, payload))
             .await
             .map_err(failed_to_send)
     }
@@ -494,7 +1421,34 @@ impl Scheduler {
             .map_err(failed_to_send)
     }
 
+    /// 保存任务, 成功条件中的正则表达式无效或者选项互相冲突时返回错误.
     pub(crate) async fn save_task(&self, task: Task) -> crate::Result<()> {
+        OutputMatcher::new(&task.success)?;
+        if task.on_exit == OnExit::Detach {
+            check_detached(&task)?;
+        }
+        if task.replicas > 1 {
+            check_replicated(&task)?;
+        }
+        // notify socket 在管理器的数据目录中, 以其他用户运行的任务通常无法访问
+        if task.notify.is_some()
+            && (task.identity.user.is_some()
+                || task.identity.group.is_some()
+                || !task.identity.groups.is_empty())
+        {
+            return Err(crate::Error::with_message(
+                crate::ErrorKind::Io,
+                "tasks running as another user or group cannot use sd_notify",
+            ));
+        }
+        if let Some(fd) = task.progress_fd
+            && fd <= 2
+        {
+            return Err(crate::Error::with_message(
+                crate::ErrorKind::Io,
+                format!("progress fd {fd} conflicts with standard streams"),
+            ));
+        }
         self.tx
             .send(Msg::SaveTask(Box::new(task)))
             .await
@@ -517,6 +1471,16 @@ impl Scheduler {
         rx.await.map_err(failed_to_recv)
     }
 
+    /// 订阅所有任务的实时输出.
+    pub(crate) fn subscribe_output(&self) -> broadcast::Receiver<OutputLine> {
+        self.output_tx.subscribe()
+    }
+
+    /// 订阅所有任务通过 `::bgm` 报告的进度.
+    pub(crate) fn subscribe_progress(&self) -> broadcast::Receiver<ProgressEvent> {
+        self.output_tx.subscribe_progress()
+    }
+
     /// 终止正在运行的 task.
     pub(crate) async fn stop_task(&self, id: i64) -> crate::Result<()> {
         self.tx
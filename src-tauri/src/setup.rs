@@ -0,0 +1,169 @@
+//! 子进程在 exec 之前的设置步骤 (`pre_exec`), 以及报告失败的步骤.
+//!
+//! `pre_exec` 中的错误和 exec 的错误都从 `spawn` 返回, 无法区分. 设置步骤失败时先将步骤和 errno
+//! 写入一个 CLOEXEC 的管道, `spawn` 失败之后从管道中读取, 读不到时才是程序本身无法执行.
+
+use std::{fmt, io};
+
+use crate::{outcome::LaunchFailure, resolve::LaunchError};
+
+/// exec 之前的设置步骤.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub(crate) enum Step {
+    Limits = 1,
+    Priority,
+    Cgroup,
+    /// 沙箱: 创建命名空间.
+    Unshare,
+    /// 沙箱: 写入用户命名空间的 `uid_map` 和 `gid_map`.
+    IdMap,
+    /// 沙箱: 挂载私有的 `/tmp`.
+    Mount,
+    /// 沙箱: 设置 `no_new_privs`.
+    NoNewPrivs,
+    /// 沙箱: 应用 Landlock 规则.
+    Landlock,
+    Identity,
+    Pty,
+    Progress,
+}
+
+impl Step {
+    const ALL: [Step; 11] = [
+        Step::Limits,
+        Step::Priority,
+        Step::Cgroup,
+        Step::Unshare,
+        Step::IdMap,
+        Step::Mount,
+        Step::NoNewPrivs,
+        Step::Landlock,
+        Step::Identity,
+        Step::Pty,
+        Step::Progress,
+    ];
+}
+
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Step::Limits => "failed to set resource limits",
+            Step::Priority => "failed to set scheduling priority or cpu affinity",
+            Step::Cgroup => "failed to join the cgroup",
+            Step::Unshare => "failed to create sandbox namespaces (unshare)",
+            Step::IdMap => "failed to write uid_map/gid_map of the sandbox user namespace",
+            Step::Mount => "failed to mount private /tmp in the sandbox",
+            Step::NoNewPrivs => "failed to set no_new_privs for the sandbox",
+            Step::Landlock => "failed to apply Landlock rules of the sandbox",
+            Step::Identity => "failed to switch user or group",
+            Step::Pty => "failed to set the pty as controlling terminal",
+            Step::Progress => "failed to set up the progress fd",
+        })
+    }
+}
+
+/// 子进程中报告失败的步骤, 由 `pre_exec` 闭包持有.
+#[derive(Clone, Debug)]
+pub(crate) struct StepReporter {
+    #[cfg(unix)]
+    writer: std::sync::Arc<std::os::fd::OwnedFd>,
+}
+
+impl StepReporter {
+    /// 报告 `step` 失败, 返回原来的错误, 只在子进程中 exec 之前调用.
+    ///
+    /// 只调用了 async-signal-safe 的 write.
+    #[cfg(unix)]
+    pub(crate) fn fail(&self, step: Step, e: io::Error) -> io::Error {
+        use std::os::fd::AsRawFd;
+
+        let mut buf = [0u8; 5];
+        buf[0] = step as u8;
+        buf[1..].copy_from_slice(&e.raw_os_error().unwrap_or(0).to_ne_bytes());
+        // SAFETY: buf 在写入期间有效, 写入失败时只是无法报告步骤.
+        unsafe {
+            libc::write(self.writer.as_raw_fd(), buf.as_ptr().cast(), buf.len());
+        }
+        e
+    }
+}
+
+/// 一次启动的设置步骤, 在 `spawn` 之前创建.
+#[derive(Debug)]
+pub(crate) struct Steps {
+    #[cfg(unix)]
+    reader: std::fs::File,
+    reporter: StepReporter,
+}
+
+impl Steps {
+    #[cfg(unix)]
+    pub(crate) fn new() -> crate::Result<Self> {
+        use std::os::fd::{AsRawFd, OwnedFd};
+
+        let failed_to_create = |e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Io,
+                "failed to create launch status pipe",
+                Box::new(e),
+            )
+        };
+        // 两端都是 CLOEXEC, exec 成功之后子进程中的写入端被关闭
+        let (reader, writer) = io::pipe().map_err(failed_to_create)?;
+        let reader = OwnedFd::from(reader);
+        // spawn 返回之后子进程已经 exec 或者退出, 读取不需要等待
+        // SAFETY: fcntl 只修改自己持有的文件描述符的标志.
+        let nonblocking = unsafe {
+            let flags = libc::fcntl(reader.as_raw_fd(), libc::F_GETFL);
+            flags != -1
+                && libc::fcntl(reader.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) != -1
+        };
+        if !nonblocking {
+            return Err(failed_to_create(io::Error::last_os_error()));
+        }
+        Ok(Self {
+            reader: reader.into(),
+            reporter: StepReporter {
+                writer: std::sync::Arc::new(OwnedFd::from(writer)),
+            },
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn new() -> crate::Result<Self> {
+        Ok(Self {
+            reporter: StepReporter {},
+        })
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn reporter(&self) -> &StepReporter {
+        &self.reporter
+    }
+
+    /// `spawn` 失败之后读取失败的设置步骤, 没有步骤失败 (程序本身无法执行) 时为空.
+    #[must_use]
+    pub(crate) fn failed(self) -> Option<LaunchError> {
+        #[cfg(unix)]
+        {
+            use std::io::Read;
+
+            let mut buf = [0u8; 5];
+            let mut reader = self.reader;
+            if reader.read(&mut buf).ok()? != buf.len() {
+                return None;
+            }
+            let step = Step::ALL.into_iter().find(|s| *s as u8 == buf[0])?;
+            let errno = i32::from_ne_bytes([buf[1], buf[2], buf[3], buf[4]]);
+            Some(LaunchError::new(
+                LaunchFailure::Setup,
+                step.to_string(),
+                Some(io::Error::from_raw_os_error(errno)),
+            ))
+        }
+        #[cfg(not(unix))]
+        None
+    }
+}
@@ -0,0 +1,213 @@
+//! 捕获用户登录 shell 的环境变量, 以及执行命名环境的激活脚本.
+//!
+//! 从桌面环境或者开机自启动时, 管理器没有 `~/.profile`, `~/.bashrc` 等文件中设置的环境变量,
+//! 开启 [`AppConfig::login_shell_env`] 之后, 这些变量作为任务环境变量的基础.
+//!
+//! [`AppConfig::login_shell_env`]: crate::config::AppConfig::login_shell_env
+
+use std::{collections::HashMap, process::Stdio, time::Duration};
+
+use tokio::{process::Command, sync::watch};
+
+use crate::identity::Identity;
+#[cfg(unix)]
+use crate::setup::Steps;
+
+/// 登录 shell 的环境变量, 任务的环境变量在此基础上覆盖.
+pub(crate) type BaseEnv = std::sync::Arc<HashMap<String, String>>;
+
+/// 在后台捕获的基础环境变量, 第一次捕获完成之前为空.
+pub(crate) type BaseEnvReceiver = watch::Receiver<Option<BaseEnv>>;
+
+/// 等待第一次捕获完成, 返回最新的基础环境变量.
+pub(crate) async fn wait_base_env(rx: &BaseEnvReceiver) -> BaseEnv {
+    let mut rx = rx.clone();
+    match rx.wait_for(Option::is_some).await {
+        Ok(env) => env.clone().unwrap_or_default(),
+        // 发送端已经关闭, 不会再捕获
+        Err(_) => BaseEnv::default(),
+    }
+}
+
+/// 等待 shell 输出环境变量的最长时间, 交互式 shell 的配置文件可能等待输入.
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 包围 `env` 的输出, 忽略配置文件打印的其他内容.
+const MARKER: &str = "__BGM_SHELL_ENV__";
+
+/// 与 shell 进程本身相关的变量, 以及由 [`identity`](crate::identity) 设置的变量, 不作为基础环境变量.
+const SKIPPED: &[&str] = &[
+    "_", "PWD", "OLDPWD", "SHLVL", "HOME", "USER", "LOGNAME", "SHELL",
+];
+
+/// 以登录, 交互模式运行 `$SHELL`, 读取配置文件设置之后的环境变量.
+#[cfg(unix)]
+pub(crate) async fn capture() -> crate::Result<HashMap<String, String>> {
+    let shell = std::env::var_os("SHELL").unwrap_or_else(|| "/bin/sh".into());
+    run_shell(shell.as_ref(), &["-l", "-i"], "", &HashMap::new(), None).await
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn capture() -> crate::Result<HashMap<String, String>> {
+    Err(crate::Error::with_message(
+        crate::ErrorKind::Io,
+        "capturing the login shell environment is only supported on Unix",
+    ))
+}
+
+/// 在 `env` 之上由 `/bin/sh` 以任务的身份 `identity` 执行激活脚本, 返回脚本新设置或者修改的环境变量.
+///
+/// 脚本在 `set -e` 下执行, 任何命令失败都视为激活失败.
+#[cfg(unix)]
+pub(crate) async fn activate(
+    script: &str,
+    env: &HashMap<String, String>,
+    identity: &Identity,
+) -> crate::Result<HashMap<String, String>> {
+    let script = format!("set -e\n{script}");
+    let mut activated = run_shell("/bin/sh".as_ref(), &[], &script, env, Some(identity)).await?;
+    activated.retain(|k, v| match env.get(k) {
+        Some(old) => old != v,
+        None => std::env::var(k).ok().as_ref() != Some(v),
+    });
+    Ok(activated)
+}
+
+#[cfg(not(unix))]
+pub(crate) async fn activate(
+    _script: &str,
+    _env: &HashMap<String, String>,
+    _identity: &Identity,
+) -> crate::Result<HashMap<String, String>> {
+    Err(crate::Error::with_message(
+        crate::ErrorKind::Io,
+        "activation scripts are only supported on Unix",
+    ))
+}
+
+/// 运行 `shell`, 执行 `script` 之后打印环境变量, 设置了 `identity` 时切换到该身份运行.
+#[cfg(unix)]
+async fn run_shell(
+    shell: &std::path::Path,
+    args: &[&str],
+    script: &str,
+    env: &HashMap<String, String>,
+    identity: Option<&Identity>,
+) -> crate::Result<HashMap<String, String>> {
+    let script = format!("{script}\nprintf '%s' {MARKER}; env -0; printf '%s' {MARKER}");
+    let mut cmd = Command::new(shell);
+    cmd.args(args)
+        .arg("-c")
+        .arg(&script)
+        .envs(env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let steps = Steps::new()?;
+    if let Some(identity) = identity {
+        crate::identity::apply(&mut cmd, identity, env, steps.reporter())?;
+    }
+    let output = match tokio::time::timeout(CAPTURE_TIMEOUT, cmd.output()).await {
+        Ok(output) => output.map_err(|e| {
+            let source: Box<dyn std::error::Error + Send + Sync> = match steps.failed() {
+                Some(failed) => Box::new(failed),
+                None => Box::new(e),
+            };
+            crate::Error::with_source(
+                crate::ErrorKind::Io,
+                format!("failed to run shell {}", shell.display()),
+                source,
+            )
+        })?,
+        Err(_) => {
+            return Err(crate::Error::with_message(
+                crate::ErrorKind::Io,
+                format!(
+                    "shell {} did not print its environment within {}s",
+                    shell.display(),
+                    CAPTURE_TIMEOUT.as_secs()
+                ),
+            ));
+        }
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some(env) = stdout
+        .split_once(MARKER)
+        .and_then(|(_, rest)| rest.rsplit_once(MARKER))
+        .map(|(env, _)| env)
+    else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(crate::Error::with_message(
+            crate::ErrorKind::Io,
+            format!(
+                "shell {} exited with {} without printing its environment: {}",
+                shell.display(),
+                output.status,
+                stderr.trim()
+            ),
+        ));
+    };
+    Ok(parse(env))
+}
+
+/// 解析 `env -0` 的输出.
+#[cfg(unix)]
+fn parse(env: &str) -> HashMap<String, String> {
+    env.split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .filter(|(key, _)| !key.is_empty() && !SKIPPED.contains(key))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// 合并基础环境变量和任务的环境变量, 任务的优先.
+#[must_use]
+pub(crate) fn merge(
+    base: &HashMap<String, String>,
+    env_vars: &HashMap<String, String>,
+) -> HashMap<String, String> {
+    let mut merged = base.clone();
+    merged.extend(env_vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+    merged
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_env_output() {
+        let env = parse("A=1\0B=x=y\0EMPTY=\0=skipped\0not an entry\0");
+        assert_eq!(env.len(), 3);
+        assert_eq!(env["A"], "1");
+        assert_eq!(env["B"], "x=y");
+        assert_eq!(env["EMPTY"], "");
+    }
+
+    #[test]
+    fn parse_keeps_multiline_values() {
+        let env = parse("FUNC=() {\n echo hi\n}\0PATH=/bin\0");
+        assert_eq!(env["FUNC"], "() {\n echo hi\n}");
+        assert_eq!(env["PATH"], "/bin");
+    }
+
+    #[test]
+    fn parse_skips_shell_state() {
+        for key in SKIPPED {
+            assert!(parse(&format!("{key}=1\0")).is_empty());
+        }
+    }
+
+    #[test]
+    fn merge_prefers_task_vars() {
+        let base = HashMap::from([
+            ("A".to_string(), "base".to_string()),
+            ("B".to_string(), "base".to_string()),
+        ]);
+        let task = HashMap::from([("A".to_string(), "task".to_string())]);
+        let merged = merge(&base, &task);
+        assert_eq!(merged["A"], "task");
+        assert_eq!(merged["B"], "base");
+    }
+}
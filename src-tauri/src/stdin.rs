@@ -0,0 +1,102 @@
+//! 任务的标准输入.
+
+use std::{fs::File, path::Path, process::Stdio};
+
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncWrite, AsyncWriteExt},
+    process::Command,
+};
+use tracing::warn;
+
+use crate::{expand::Vars, task::Task};
+
+/// 标准输入的来源.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "tag", content = "content")]
+pub enum StdinSource {
+    /// 读取 `Task::stdin` 指定的文件, 为空则不提供标准输入.
+    #[default]
+    File,
+    /// 随任务保存的文本, 支持 `${NAME}` 变量展开.
+    Inline(String),
+    /// 由触发本次运行的事件提供, 例如手动运行时填写的内容, 没有提供时为空.
+    Trigger,
+}
+
+/// 解析之后的标准输入.
+pub(crate) enum Input {
+    File(File),
+    Bytes(Vec<u8>),
+}
+
+fn failed_to_open(path: &Path, e: std::io::Error) -> crate::Error {
+    crate::Error::with_source(
+        crate::ErrorKind::Io,
+        format!("failed to open stdin file: {}", path.display()),
+        Box::new(e),
+    )
+}
+
+/// 根据任务的标准输入设置生成本次运行的输入, 文件无法打开时返回错误.
+pub(crate) fn resolve(
+    task: &Task,
+    vars: &Vars,
+    payload: Option<&str>,
+) -> crate::Result<Option<Input>> {
+    Ok(match &task.stdin_source {
+        StdinSource::File => match &task.stdin {
+            Some(path) => Some(Input::File(
+                File::open(path).map_err(|e| failed_to_open(path, e))?,
+            )),
+            None => None,
+        },
+        StdinSource::Inline(template) => Some(Input::Bytes(vars.expand(template).into_bytes())),
+        StdinSource::Trigger => payload.map(|p| Input::Bytes(p.as_bytes().to_vec())),
+    })
+}
+
+impl Input {
+    /// 读取全部内容, 用于写入伪终端.
+    pub(crate) fn into_bytes(self) -> crate::Result<Vec<u8>> {
+        match self {
+            Input::File(mut file) => {
+                let mut buf = Vec::new();
+                std::io::Read::read_to_end(&mut file, &mut buf).map_err(|e| {
+                    crate::Error::with_source(
+                        crate::ErrorKind::Io,
+                        "failed to read stdin file",
+                        Box::new(e),
+                    )
+                })?;
+                Ok(buf)
+            }
+            Input::Bytes(bytes) => Ok(bytes),
+        }
+    }
+
+    /// 设置子进程的标准输入, 文件直接交给子进程, 文本需要在进程启动之后通过 [`feed`] 写入.
+    pub(crate) fn apply(self, cmd: &mut Command) -> Option<Vec<u8>> {
+        match self {
+            Input::File(file) => {
+                cmd.stdin(file);
+                None
+            }
+            Input::Bytes(bytes) => {
+                cmd.stdin(Stdio::piped());
+                Some(bytes)
+            }
+        }
+    }
+}
+
+/// 在后台将 `bytes` 写入子进程, 写完之后关闭 `writer`.
+pub(crate) fn feed(mut writer: impl AsyncWrite + Unpin + Send + 'static, bytes: Vec<u8>) {
+    tokio::spawn(async move {
+        // 子进程可能不读取标准输入就退出, 此时写入失败.
+        if let Err(e) = writer.write_all(&bytes).await {
+            warn!("failed to write task stdin: {e:?}");
+        }
+        writer.shutdown().await.ok();
+    });
+}
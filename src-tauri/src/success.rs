@@ -0,0 +1,195 @@
+//! 判断一次运行是否成功的条件.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::outcome::Outcome;
+
+/// 运行成功的条件, 默认只有退出码为 0 时成功.
+///
+/// 被管理器结束 (停止, 健康检查失败, 看门狗超时) 的运行总是失败. 其次是输出匹配:
+/// 匹配 `failure_pattern` 时总是失败, 否则匹配 `success_pattern` 时总是成功,
+/// 都没有匹配时再按照退出码和信号判断.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct SuccessCriteria {
+    /// 视为成功的退出码.
+    pub exit_codes: Vec<i64>,
+    /// 被这些信号结束时视为成功.
+    pub signals: Vec<i32>,
+    /// 输出中有一行匹配该正则表达式时视为失败.
+    pub failure_pattern: Option<String>,
+    /// 输出中有一行匹配该正则表达式时视为成功.
+    pub success_pattern: Option<String>,
+}
+
+impl Default for SuccessCriteria {
+    fn default() -> Self {
+        Self {
+            exit_codes: vec![0],
+            signals: Vec::new(),
+            failure_pattern: None,
+            success_pattern: None,
+        }
+    }
+}
+
+impl SuccessCriteria {
+    /// 根据结束方式和输出的匹配结果判断是否成功, 无法获取退出状态时视为失败.
+    #[must_use]
+    pub(crate) fn verdict(&self, outcome: &Outcome, matcher: Option<&OutputMatcher>) -> bool {
+        if matches!(
+            outcome,
+            Outcome::Stopped | Outcome::TimedOut | Outcome::Unhealthy
+        ) {
+            return false;
+        }
+        if let Some(matcher) = matcher {
+            if matcher.failed.load(Ordering::Relaxed) {
+                return false;
+            }
+            if matcher.succeeded.load(Ordering::Relaxed) {
+                return true;
+            }
+        }
+        match outcome {
+            Outcome::Exited { code } => self.exit_codes.contains(code),
+            Outcome::Signaled { signal, .. } => self.signals.contains(signal),
+            _ => false,
+        }
+    }
+}
+
+fn compile(pattern: &str) -> crate::Result<Regex> {
+    Regex::new(pattern).map_err(|e| {
+        crate::Error::with_source(
+            crate::ErrorKind::Regex,
+            format!("invalid output pattern: {pattern}"),
+            Box::new(e),
+        )
+    })
+}
+
+/// 在运行期间检查输出中的每一行是否匹配成功或失败的正则表达式.
+#[derive(Debug)]
+pub(crate) struct OutputMatcher {
+    failure: Option<Regex>,
+    success: Option<Regex>,
+    failed: AtomicBool,
+    succeeded: AtomicBool,
+}
+
+impl OutputMatcher {
+    /// 编译 `criteria` 中的正则表达式, 没有设置任何正则表达式时返回空.
+    pub(crate) fn new(criteria: &SuccessCriteria) -> crate::Result<Option<Self>> {
+        let failure = criteria
+            .failure_pattern
+            .as_deref()
+            .map(compile)
+            .transpose()?;
+        let success = criteria
+            .success_pattern
+            .as_deref()
+            .map(compile)
+            .transpose()?;
+        if failure.is_none() && success.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            failure,
+            success,
+            failed: AtomicBool::new(false),
+            succeeded: AtomicBool::new(false),
+        }))
+    }
+
+    /// 检查一行输出, 不包含行尾换行符.
+    pub(crate) fn feed(&self, line: &str) {
+        if let Some(failure) = &self.failure
+            && !self.failed.load(Ordering::Relaxed)
+            && failure.is_match(line)
+        {
+            self.failed.store(true, Ordering::Relaxed);
+        }
+        if let Some(success) = &self.success
+            && !self.succeeded.load(Ordering::Relaxed)
+            && success.is_match(line)
+        {
+            self.succeeded.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exited(code: i64) -> Outcome {
+        Outcome::Exited { code }
+    }
+
+    #[test]
+    fn verdict_by_exit_code_and_signal() {
+        let criteria = SuccessCriteria {
+            exit_codes: vec![0, 2],
+            signals: vec![15],
+            ..Default::default()
+        };
+        assert!(criteria.verdict(&exited(0), None));
+        assert!(criteria.verdict(&exited(2), None));
+        assert!(!criteria.verdict(&exited(1), None));
+        let signaled = |signal| Outcome::Signaled {
+            signal,
+            core_dumped: false,
+        };
+        assert!(criteria.verdict(&signaled(15), None));
+        assert!(!criteria.verdict(&signaled(9), None));
+        assert!(!criteria.verdict(&Outcome::Unknown, None));
+    }
+
+    #[test]
+    fn verdict_output_patterns() {
+        let criteria = SuccessCriteria {
+            failure_pattern: Some("^ERROR".to_string()),
+            success_pattern: Some("done$".to_string()),
+            ..Default::default()
+        };
+        let matcher = OutputMatcher::new(&criteria).unwrap().unwrap();
+        matcher.feed("working");
+        assert!(!criteria.verdict(&exited(1), Some(&matcher)));
+        matcher.feed("all done");
+        assert!(criteria.verdict(&exited(1), Some(&matcher)));
+        // 失败的匹配优先
+        matcher.feed("ERROR: disk full");
+        assert!(!criteria.verdict(&exited(0), Some(&matcher)));
+    }
+
+    #[test]
+    fn verdict_manager_kills_always_fail() {
+        let criteria = SuccessCriteria {
+            success_pattern: Some("ready".to_string()),
+            ..Default::default()
+        };
+        let matcher = OutputMatcher::new(&criteria).unwrap().unwrap();
+        matcher.feed("ready");
+        for outcome in [Outcome::Stopped, Outcome::TimedOut, Outcome::Unhealthy] {
+            assert!(!criteria.verdict(&outcome, Some(&matcher)));
+        }
+    }
+
+    #[test]
+    fn matcher_requires_pattern_and_valid_regex() {
+        assert!(
+            OutputMatcher::new(&SuccessCriteria::default())
+                .unwrap()
+                .is_none()
+        );
+        let invalid = SuccessCriteria {
+            failure_pattern: Some("(".to_string()),
+            ..Default::default()
+        };
+        assert!(OutputMatcher::new(&invalid).is_err());
+    }
+}
@@ -0,0 +1,188 @@
+//! 任务连续失败时暂停自动执行 (suspension).
+
+use std::{collections::VecDeque, time::Duration};
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use crate::outcome::Outcome;
+
+/// Suspension 的触发条件, 在 `timespan_ms` 毫秒内失败 `failures` 次时暂停任务.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct SuspensionPolicy {
+    /// 统计失败次数的时间窗口 (毫秒).
+    pub timespan_ms: u64,
+    /// 时间窗口内触发暂停的失败次数.
+    pub failures: usize,
+    /// 暂停之后自动恢复的等待时间 (毫秒), 为空表示需要手动恢复.
+    pub cooldown_ms: Option<u64>,
+}
+
+/// 默认在 60 秒内失败 6 次时暂停.
+///
+/// 按照默认的重启策略 (从 1 秒开始翻倍), 连续 6 次启动即失败大约需要 31 秒, 时间窗口必须大于这个值,
+/// 否则重启的等待时间会让失败永远达不到暂停的次数.
+impl Default for SuspensionPolicy {
+    fn default() -> Self {
+        Self {
+            timespan_ms: 60_000,
+            failures: 6,
+            cooldown_ms: None,
+        }
+    }
+}
+
+/// 任务被暂停的时间和原因, 持久化到数据库中.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Suspension {
+    pub since: DateTime<FixedOffset>,
+    pub reason: String,
+}
+
+impl Suspension {
+    /// 按照 `policy` 自动恢复的时间, 需要手动恢复时为空.
+    #[must_use]
+    pub(crate) fn resume_at(&self, policy: &SuspensionPolicy) -> Option<DateTime<FixedOffset>> {
+        policy
+            .cooldown_ms
+            .map(|ms| self.since + Duration::from_millis(ms))
+    }
+}
+
+/// Suspension 逻辑: 滑动时间窗口内的失败次数达到指定次数则触发 suspension, 暂停任务的自动执行.
+#[derive(Default, Debug)]
+pub(crate) struct SuspensionDetector {
+    /// 时间窗口内每次失败的时间, 从旧到新.
+    failures: VecDeque<Instant>,
+    suspension: Option<Suspension>,
+}
+
+impl SuspensionDetector {
+    /// 恢复持久化的 suspension 状态.
+    #[inline]
+    #[must_use]
+    pub(crate) fn new(suspension: Option<Suspension>) -> Self {
+        Self {
+            failures: VecDeque::new(),
+            suspension,
+        }
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn suspension(&self) -> Option<&Suspension> {
+        self.suspension.as_ref()
+    }
+
+    #[inline]
+    #[must_use]
+    pub(crate) fn suspended(&self) -> bool {
+        self.suspension.is_some()
+    }
+
+    /// 记录一次结束方式为 `outcome` 的失败, 本次失败触发了 suspension 时返回新的 suspension.
+    pub(crate) fn fail(
+        &mut self,
+        policy: &SuspensionPolicy,
+        outcome: &Outcome,
+    ) -> Option<&Suspension> {
+        if self.suspension.is_some() {
+            return None;
+        }
+        let now = Instant::now();
+        let timespan = Duration::from_millis(policy.timespan_ms);
+        while self
+            .failures
+            .front()
+            .is_some_and(|&t| now.duration_since(t) > timespan)
+        {
+            self.failures.pop_front();
+        }
+        self.failures.push_back(now);
+        if self.failures.len() < policy.failures.max(1) {
+            return None;
+        }
+        self.failures.clear();
+        self.suspension = Some(Suspension {
+            since: chrono::Local::now().into(),
+            reason: format!(
+                "failed {} times within {:.1}s, last run {outcome}",
+                policy.failures.max(1),
+                timespan.as_secs_f64()
+            ),
+        });
+        self.suspension.as_ref()
+    }
+
+    /// 等待到自动恢复的时间, 没有暂停或者需要手动恢复时永远不会完成.
+    pub(crate) async fn wait_resume(&self, policy: &SuspensionPolicy) {
+        let Some(resume_at) = self.suspension.as_ref().and_then(|s| s.resume_at(policy)) else {
+            return std::future::pending().await;
+        };
+        let remaining = resume_at
+            .signed_duration_since(chrono::Local::now())
+            .to_std()
+            .unwrap_or_default();
+        tokio::time::sleep(remaining).await;
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.failures.clear();
+        self.suspension = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::restart::RestartPolicy;
+
+    #[test]
+    fn suspends_after_failures_within_timespan() {
+        let policy = SuspensionPolicy {
+            failures: 3,
+            ..Default::default()
+        };
+        let outcome = Outcome::Exited { code: 1 };
+        let mut detector = SuspensionDetector::default();
+        assert!(detector.fail(&policy, &outcome).is_none());
+        assert!(detector.fail(&policy, &outcome).is_none());
+        let suspension = detector.fail(&policy, &outcome).unwrap();
+        assert!(suspension.reason.starts_with("failed 3 times"));
+        assert!(suspension.reason.ends_with("exited with code 1"));
+        assert!(detector.suspended());
+        // 已经暂停时不再产生新的 suspension
+        assert!(detector.fail(&policy, &outcome).is_none());
+        detector.reset();
+        assert!(!detector.suspended());
+        assert!(detector.fail(&policy, &outcome).is_none());
+    }
+
+    #[test]
+    fn resume_at_after_cooldown() {
+        let suspension = Suspension {
+            since: chrono::DateTime::parse_from_rfc3339("2026-10-18T12:00:00+08:00").unwrap(),
+            reason: String::new(),
+        };
+        assert_eq!(suspension.resume_at(&SuspensionPolicy::default()), None);
+        let policy = SuspensionPolicy {
+            cooldown_ms: Some(90_000),
+            ..Default::default()
+        };
+        assert_eq!(
+            suspension.resume_at(&policy).map(|t| t.to_rfc3339()),
+            Some("2026-10-18T12:01:30+08:00".to_string())
+        );
+    }
+
+    #[test]
+    fn default_policy_triggers_under_default_backoff() {
+        // 第一次失败之后还需要 failures - 1 次重启, 这些等待时间之和必须在时间窗口之内
+        let policy = SuspensionPolicy::default();
+        let restart = RestartPolicy::default();
+        let waited: Duration = (1..policy.failures as u32).map(|i| restart.delay(i)).sum();
+        assert!(waited < Duration::from_millis(policy.timespan_ms));
+    }
+}
@@ -1,13 +1,37 @@
-use std::{collections::HashMap, path::PathBuf, time::Duration};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use chrono::{DateTime, FixedOffset};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue::Unchanged, DatabaseConnection, EntityTrait, NotSet, QueryOrder,
-    Set, TransactionTrait,
+    ActiveModelTrait, ActiveValue::Unchanged, ColumnTrait, DatabaseConnection, EntityTrait, NotSet,
+    QueryFilter, QueryOrder, Set, TransactionTrait, sea_query::OnConflict,
 };
 use serde::{Deserialize, Serialize};
 
-/// 触发模式, 所有模式之中, 如果任务程序已经在执行, 那么不会再被触发.
+use crate::{
+    cgroup::CgroupLimits,
+    expand::expand_var,
+    health::HealthCheck,
+    hooks::Hooks,
+    identity::Identity,
+    limits::ResourceLimits,
+    notify::NotifyOptions,
+    outcome::Outcome,
+    output::OutputOptions,
+    priority::Priority,
+    process::OnExit,
+    pty::PtyOptions,
+    restart::RestartPolicy,
+    sandbox::SandboxOptions,
+    stdin::StdinSource,
+    success::SuccessCriteria,
+    suspension::{Suspension, SuspensionPolicy},
+};
+
+/// 触发模式, 所有模式之中, 如果任务程序已经在执行, 按照任务的 [`ConcurrencyPolicy`] 处理.
 #[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
 #[serde(tag = "tag", content = "content")]
 pub enum Trigger {
@@ -15,17 +39,32 @@ pub enum Trigger {
     Routine(Duration),
     /// 在应用开启时启动一次.
     Startup,
-    /// 保证进程活性, 在子进程退出之后重新启动, 随应用开启时自动启动.
-    KeepAlive, // todo 重启并失败过多次时自动停止.
+    /// 保证进程活性, 在子进程退出之后按照重启策略重新启动, 随应用开启时自动启动.
+    KeepAlive,
     /// 手动启动.
     #[default]
     Manual,
     /// 指定时间点后启动一次.
     Instant(DateTime<FixedOffset>),
-    /// 应用开启时自动启动, 重复创建进程直到进程返回状态为 0.
+    /// 应用开启时自动启动, 按照重启策略重复创建进程直到运行满足任务的成功条件.
     UntilSucceed,
 }
 
+/// 任务已经在运行时再次被触发的处理方式.
+#[derive(Deserialize, Serialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "tag", content = "content")]
+pub enum ConcurrencyPolicy {
+    /// 跳过本次触发, 记录到正在进行的运行中.
+    #[default]
+    Skip,
+    /// 排队等待正在运行的实例结束, 最多排队一次, 排队期间的其他触发被跳过.
+    Queue,
+    /// 结束正在运行的实例, 重新启动.
+    Replace,
+    /// 最多同时运行指定数量的实例, 超出时跳过.
+    Parallel(usize),
+}
+
 #[derive(Deserialize, Serialize, bon::Builder, Clone, Debug)]
 pub struct Task {
     /// Task id, 不能重复, 在数据库中自动递增.
@@ -40,15 +79,33 @@ pub struct Task {
     pub args: Vec<String>,
     #[builder(into)]
     pub stdin: Option<PathBuf>,
+    /// 标准输入的来源, 默认读取 `stdin` 指定的文件.
+    #[builder(default)]
+    pub stdin_source: StdinSource,
     #[builder(into)]
     pub stdout: Option<PathBuf>,
     #[builder(into)]
     pub stderr: Option<PathBuf>,
+    /// 标准输出的重定向选项.
+    #[builder(default)]
+    pub stdout_options: OutputOptions,
+    /// 标准错误的重定向选项.
+    #[builder(default)]
+    pub stderr_options: OutputOptions,
+    /// 将标准错误合并到标准输出, 此时 `stderr` 和 `stderr_options` 被忽略.
+    #[builder(default = false)]
+    pub merge_stderr: bool,
     pub trigger: Trigger,
     #[builder(default = true)]
     pub enabled: bool,
     #[builder(skip)]
     pub last_exit_code: Option<i64>,
+    /// 上一次运行是否满足 `success` 条件.
+    #[builder(skip)]
+    pub last_succeeded: Option<bool>,
+    /// 上一次运行的结束方式, 使用副本时为最近结束的副本的结束方式.
+    #[builder(skip)]
+    pub last_outcome: Option<Outcome>,
     #[builder(skip)]
     pub last_run_at: Option<DateTime<FixedOffset>>,
     /// 是否在启动的时候不创建终端窗口 (仅 Windows 下有效).
@@ -60,6 +117,162 @@ pub struct Task {
     /// 环境变量, 存储为 HashMap<String, String>
     #[builder(default)]
     pub env_vars: HashMap<String, String>,
+    /// 引用的命名环境的名称, 任务自己的环境变量优先于环境中的变量.
+    pub environment: Option<String>,
+    /// 在伪终端中运行程序, 为空则使用管道或者文件 (仅 Unix 下有效).
+    pub pty: Option<PtyOptions>,
+    /// 资源限制 (仅 Unix 下有效).
+    #[builder(default)]
+    pub limits: ResourceLimits,
+    /// cgroup 资源限制, 设置了 `AppConfig::cgroup_root` 时生效 (仅 Linux 下有效).
+    #[builder(default)]
+    pub cgroup: CgroupLimits,
+    /// 调度优先级和 CPU 亲和性.
+    #[builder(default)]
+    pub priority: Priority,
+    /// 运行任务的用户, 组和 umask.
+    #[builder(default)]
+    pub identity: Identity,
+    /// 沙箱选项, 为空表示不使用沙箱 (仅 Linux 下有效).
+    pub sandbox: Option<SandboxOptions>,
+    /// 任务已经在运行时再次被触发的处理方式.
+    #[builder(default)]
+    pub concurrency: ConcurrencyPolicy,
+    /// `KeepAlive` 和 `UntilSucceed` 任务的重启策略.
+    #[builder(default)]
+    pub restart: RestartPolicy,
+    /// Suspension 的触发条件, 为空表示使用 `AppConfig` 中的默认条件.
+    pub suspension: Option<SuspensionPolicy>,
+    /// 当前的 suspension 状态, 保存任务时忽略.
+    #[builder(skip)]
+    pub suspended: Option<Suspension>,
+    /// 判断运行是否成功的条件.
+    #[builder(default)]
+    pub success: SuccessCriteria,
+    /// `KeepAlive` 任务的健康检查, 为空表示不检查.
+    pub health_check: Option<HealthCheck>,
+    /// 通过 `NOTIFY_SOCKET` 接收 `sd_notify` 消息, 为空表示不提供 (仅 Unix 下有效).
+    /// 以其他用户或者组运行的任务无法访问 socket, 保存任务时拒绝.
+    pub notify: Option<NotifyOptions>,
+    /// 启动之前和退出之后运行的 hook.
+    #[builder(default)]
+    pub hooks: Hooks,
+    /// 管理器退出时如何处理正在运行的进程.
+    #[builder(default)]
+    pub on_exit: OnExit,
+    /// 同时运行的副本数量, 每个副本独立触发和重启.
+    ///
+    /// 大于 1 时每个副本的参数, 环境变量和路径中的 `${INSTANCE}` 展开为副本的编号 (从 0 开始),
+    /// 同时设置环境变量 `INSTANCE`. 每个副本的上一次结束方式和暂停状态分别保存在
+    /// [`Task::replica_states`] 中.
+    /// 标准输出和标准错误的重定向文件必须包含 `${INSTANCE}`, 保存任务时检查.
+    #[serde(default = "default_replicas")]
+    #[builder(default = 1)]
+    pub replicas: u32,
+    /// 专用的进度报告文件描述符, 任务向其中写入 `::bgm` 报告行, 见 [`progress`](crate::progress).
+    /// 为空表示只从输出中读取报告 (仅 Unix 下有效).
+    pub progress_fd: Option<i32>,
+    /// 副本的编号, 只在运行时由 [`Task::instances`] 设置.
+    #[serde(skip)]
+    #[builder(skip)]
+    pub instance: Option<u32>,
+    /// 每个副本的状态, 按副本编号索引, 保存任务时忽略.
+    #[serde(skip)]
+    #[builder(skip)]
+    pub replica_states: HashMap<u32, ReplicaState>,
+}
+
+/// 一个副本的上一次结束方式和暂停状态, 持久化到数据库中, 每个副本一行.
+#[derive(Deserialize, Serialize, Default, Clone, Debug, PartialEq, Eq)]
+pub struct ReplicaState {
+    pub last_outcome: Option<Outcome>,
+    pub suspended: Option<Suspension>,
+}
+
+fn default_replicas() -> u32 {
+    1
+}
+
+impl Task {
+    /// 任务的所有副本, 不使用副本时只有任务本身.
+    #[must_use]
+    pub(crate) fn instances(&self) -> Vec<Task> {
+        if self.replicas <= 1 {
+            return vec![self.clone()];
+        }
+        (0..self.replicas).map(|i| self.for_instance(i)).collect()
+    }
+
+    /// 第 `instance` 个副本, 展开参数, 环境变量和路径中的 `${INSTANCE}`.
+    pub(crate) fn for_instance(&self, instance: u32) -> Task {
+        let state = self
+            .replica_states
+            .get(&instance)
+            .cloned()
+            .unwrap_or_default();
+        let mut replica = Task {
+            instance: Some(instance),
+            last_outcome: state.last_outcome,
+            suspended: state.suspended,
+            ..self.clone()
+        };
+        let expand_path = |p: &PathBuf| replica.expand_instance_path(p);
+        let program = expand_path(&self.program);
+        let stdin = self.stdin.as_ref().map(expand_path);
+        let stdout = self.stdout.as_ref().map(expand_path);
+        let stderr = self.stderr.as_ref().map(expand_path);
+        let working_dir = self.working_dir.as_ref().map(expand_path);
+        let args = self
+            .args
+            .iter()
+            .map(|a| replica.expand_instance(a))
+            .collect();
+        let mut env_vars: HashMap<String, String> = self
+            .env_vars
+            .iter()
+            .map(|(k, v)| (k.clone(), replica.expand_instance(v)))
+            .collect();
+        env_vars.insert("INSTANCE".to_string(), instance.to_string());
+        replica.program = program;
+        replica.args = args;
+        replica.stdin = stdin;
+        replica.stdout = stdout;
+        replica.stderr = stderr;
+        replica.working_dir = working_dir;
+        replica.env_vars = env_vars;
+        replica
+    }
+
+    /// 展开 `s` 中的副本编号 `${INSTANCE}`, 不是副本时原样返回.
+    #[must_use]
+    pub(crate) fn expand_instance(&self, s: &str) -> String {
+        match self.instance {
+            Some(instance) => expand_var(s, "INSTANCE", &instance.to_string()),
+            None => s.to_string(),
+        }
+    }
+
+    /// 展开路径中的 `${INSTANCE}`, 不是 UTF-8 的路径原样保留.
+    #[must_use]
+    pub(crate) fn expand_instance_path(&self, path: &Path) -> PathBuf {
+        path.to_str()
+            .map_or_else(|| path.to_path_buf(), |s| self.expand_instance(s).into())
+    }
+
+    /// 是否接管副本 `instance` 留下的运行.
+    ///
+    /// 不属于任何现有副本的运行 (例如减少副本数量之前留下的) 由第一个副本接管.
+    #[must_use]
+    pub(crate) fn adopts(&self, instance: Option<u32>) -> bool {
+        if instance == self.instance {
+            return true;
+        }
+        let orphaned = match instance {
+            Some(i) => self.replicas <= 1 || i >= self.replicas,
+            None => self.replicas > 1,
+        };
+        orphaned && self.instance.unwrap_or(0) == 0
+    }
 }
 
 impl From<entity::tasks::Model> for Task {
@@ -89,16 +302,42 @@ impl From<entity::tasks::Model> for Task {
             // 将 JSON 字符串解析回 Vec<String>
             args: serde_json::from_str(&m.args).unwrap_or_default(),
             stdin: m.stdin.map(PathBuf::from),
+            stdin_source: serde_json::from_str(&m.stdin_source).unwrap_or_default(),
             stdout: m.stdout.map(PathBuf::from),
             stderr: m.stderr.map(PathBuf::from),
+            stdout_options: serde_json::from_str(&m.stdout_options).unwrap_or_default(),
+            stderr_options: serde_json::from_str(&m.stderr_options).unwrap_or_default(),
+            merge_stderr: m.merge_stderr,
             trigger,
             enabled: m.enabled,
             last_exit_code: m.last_exit_code,
+            last_succeeded: m.last_succeeded,
+            last_outcome: m.last_outcome.and_then(|s| serde_json::from_str(&s).ok()),
             last_run_at: m.last_run_at.and_then(|s| serde_json::from_str(&s).ok()),
             no_console: m.no_console,
             working_dir: m.working_dir.map(PathBuf::from),
             // 将 JSON 字符串解析回 HashMap<String, String>
             env_vars: serde_json::from_str(&m.env_vars).unwrap_or_default(),
+            environment: m.environment,
+            pty: m.pty.and_then(|p| serde_json::from_str(&p).ok()),
+            limits: serde_json::from_str(&m.limits).unwrap_or_default(),
+            cgroup: serde_json::from_str(&m.cgroup).unwrap_or_default(),
+            priority: serde_json::from_str(&m.priority).unwrap_or_default(),
+            identity: serde_json::from_str(&m.identity).unwrap_or_default(),
+            sandbox: m.sandbox.and_then(|s| serde_json::from_str(&s).ok()),
+            concurrency: serde_json::from_str(&m.concurrency).unwrap_or_default(),
+            restart: serde_json::from_str(&m.restart).unwrap_or_default(),
+            suspension: m.suspension.and_then(|s| serde_json::from_str(&s).ok()),
+            suspended: m.suspended.and_then(|s| serde_json::from_str(&s).ok()),
+            success: serde_json::from_str(&m.success).unwrap_or_default(),
+            health_check: m.health_check.and_then(|s| serde_json::from_str(&s).ok()),
+            notify: m.notify.and_then(|s| serde_json::from_str(&s).ok()),
+            hooks: serde_json::from_str(&m.hooks).unwrap_or_default(),
+            on_exit: serde_json::from_str(&m.on_exit).unwrap_or_default(),
+            replicas: u32::try_from(m.replicas).unwrap_or(1).max(1),
+            progress_fd: m.progress_fd.and_then(|fd| i32::try_from(fd).ok()),
+            instance: None,
+            replica_states: HashMap::new(),
         }
     }
 }
@@ -126,17 +365,49 @@ impl From<Task> for entity::tasks::ActiveModel {
             // 将 Vec<String> 序列化为 JSON 字符串
             args: Set(serde_json::to_string(&t.args).unwrap_or_else(|_| "[]".to_string())),
             stdin: Set(t.stdin.map(|p| p.to_string_lossy().into_owned())),
+            stdin_source: Set(serde_json::to_string(&t.stdin_source)
+                .unwrap_or_else(|_| r#"{"tag":"File"}"#.to_string())),
             stdout: Set(t.stdout.map(|p| p.to_string_lossy().into_owned())),
             stderr: Set(t.stderr.map(|p| p.to_string_lossy().into_owned())),
+            stdout_options: Set(
+                serde_json::to_string(&t.stdout_options).unwrap_or_else(|_| "{}".to_string())
+            ),
+            stderr_options: Set(
+                serde_json::to_string(&t.stderr_options).unwrap_or_else(|_| "{}".to_string())
+            ),
+            merge_stderr: Set(t.merge_stderr),
+            pty: Set(t.pty.and_then(|p| serde_json::to_string(&p).ok())),
+            limits: Set(serde_json::to_string(&t.limits).unwrap_or_else(|_| "{}".to_string())),
+            cgroup: Set(serde_json::to_string(&t.cgroup).unwrap_or_else(|_| "{}".to_string())),
+            priority: Set(serde_json::to_string(&t.priority).unwrap_or_else(|_| "{}".to_string())),
+            identity: Set(serde_json::to_string(&t.identity).unwrap_or_else(|_| "{}".to_string())),
+            sandbox: Set(t.sandbox.and_then(|s| serde_json::to_string(&s).ok())),
+            concurrency: Set(serde_json::to_string(&t.concurrency)
+                .unwrap_or_else(|_| r#"{"tag":"Skip"}"#.to_string())),
+            restart: Set(serde_json::to_string(&t.restart).unwrap_or_else(|_| "{}".to_string())),
+            suspension: Set(t.suspension.and_then(|s| serde_json::to_string(&s).ok())),
+            suspended: NotSet,
+            success: Set(serde_json::to_string(&t.success).unwrap_or_else(|_| "{}".to_string())),
+            health_check: Set(t.health_check.and_then(|h| serde_json::to_string(&h).ok())),
+            notify: Set(t.notify.and_then(|n| serde_json::to_string(&n).ok())),
+            hooks: Set(serde_json::to_string(&t.hooks).unwrap_or_else(|_| "{}".to_string())),
+            on_exit: Set(
+                serde_json::to_string(&t.on_exit).unwrap_or_else(|_| r#""Kill""#.to_string())
+            ),
             enabled: NotSet,
             trigger_tag: Set(tag.to_string()),
             trigger_content: Set(content),
             last_exit_code: NotSet,
+            last_succeeded: NotSet,
+            last_outcome: NotSet,
             last_run_at: NotSet,
             no_console: Set(t.no_console),
             working_dir: Set(t.working_dir.map(|p| p.to_string_lossy().into_owned())),
             // 将 HashMap<String, String> 序列化为 JSON 字符串
             env_vars: Set(serde_json::to_string(&t.env_vars).unwrap_or_else(|_| "{}".to_string())),
+            environment: Set(t.environment),
+            replicas: Set(i64::from(t.replicas.max(1))),
+            progress_fd: Set(t.progress_fd.map(i64::from)),
         }
     }
 }
@@ -149,7 +420,8 @@ pub trait TaskDAO {
     /// - `task` 中的 id 为 None 的时候, 添加新的 Task.
     /// - `task` 中的 id 为 Some 的时候, 修改已有 Task 的内容, 如果指定 id 的 task 不存在, 那么返回错误.
     ///
-    /// 其中的 `last_exit_code`, `last_run_at`, `enabled` 字段将被忽略.
+    /// 其中的 `last_exit_code`, `last_outcome`, `last_run_at`, `enabled`, `suspended`,
+    /// `replica_states` 字段将被忽略.
     async fn save_task(&self, task: Task) -> crate::Result<i64>;
     /// 如果成功删除 `id`, 返回 `Ok(true)`,
     /// 如果指定 `id` 对应的 task 不存在, 那么返回 `Ok(false)`.
@@ -162,13 +434,32 @@ pub trait TaskDAO {
     ///
     /// 任务执行状态和执行时间不是严格对应的, 当任务正在执行而没有退出的时候,
     /// 任务执行状态为上一次执行结束的结果, 运行时间为本次的执行时间.
-    async fn update_task_exit_code(&self, id: i64, exit_code: i64) -> crate::Result<()>;
+    ///
+    /// `succeeded` 为按照任务的成功条件判断的结果, 无法判断时为空.
+    /// 进程自己退出时同时更新退出码, 否则退出码为空.
+    /// `instance` 不为空时同时更新该副本的结束方式.
+    async fn update_task_outcome(
+        &self,
+        id: i64,
+        instance: Option<u32>,
+        outcome: &Outcome,
+        succeeded: Option<bool>,
+    ) -> crate::Result<()>;
     /// 更新任务的执行时间.
     async fn update_task_run_at(
         &self,
         id: i64,
         run_at: chrono::DateTime<FixedOffset>,
     ) -> crate::Result<()>;
+    /// 更新任务 (`instance` 不为空时为该副本) 的 suspension 状态, 为空表示解除 suspension.
+    async fn set_task_suspension(
+        &self,
+        id: i64,
+        instance: Option<u32>,
+        suspension: Option<&Suspension>,
+    ) -> crate::Result<()>;
+    /// 解除任务和所有副本的 suspension.
+    async fn clear_task_suspensions(&self, id: i64) -> crate::Result<()>;
     /// 按给定顺序重排任务. 传入顺序中的第 1 个任务将排在最前面.
     async fn reorder_tasks(&self, ordered_ids: Vec<i64>) -> crate::Result<()>;
 }
@@ -187,7 +478,17 @@ impl TaskDAO for DatabaseConnection {
                     Box::new(e),
                 )
             })?;
-        Ok(tasks.into_iter().map(|t| t.into()).collect())
+        let mut states = replica_states(self, None).await?;
+        Ok(tasks
+            .into_iter()
+            .map(|t| {
+                let id = t.id;
+                Task {
+                    replica_states: states.remove(&id).unwrap_or_default(),
+                    ..t.into()
+                }
+            })
+            .collect())
     }
 
     async fn get_task(&self, id: i64) -> crate::Result<Option<Task>> {
@@ -197,7 +498,14 @@ impl TaskDAO for DatabaseConnection {
             .map_err(|e| {
                 crate::Error::with_source(crate::ErrorKind::Db, "failed to get task", Box::new(e))
             })?;
-        Ok(task.map(|t| t.into()))
+        let Some(task) = task else {
+            return Ok(None);
+        };
+        let mut states = replica_states(self, Some(id)).await?;
+        Ok(Some(Task {
+            replica_states: states.remove(&id).unwrap_or_default(),
+            ..task.into()
+        }))
     }
 
     async fn save_task(&self, task: Task) -> crate::Result<i64> {
@@ -225,6 +533,17 @@ impl TaskDAO for DatabaseConnection {
     }
 
     async fn remove_task(&self, id: i64) -> crate::Result<bool> {
+        entity::replica_states::Entity::delete_many()
+            .filter(entity::replica_states::Column::TaskId.eq(id))
+            .exec(self)
+            .await
+            .map_err(|e| {
+                crate::Error::with_source(
+                    crate::ErrorKind::Db,
+                    "failed to remove replica states of task",
+                    Box::new(e),
+                )
+            })?;
         let rst = entity::tasks::Entity::delete_by_id(id)
             .exec(self)
             .await
@@ -254,16 +573,33 @@ impl TaskDAO for DatabaseConnection {
         Ok(())
     }
 
-    async fn update_task_exit_code(&self, id: i64, exit_code: i64) -> crate::Result<()> {
+    async fn update_task_outcome(
+        &self,
+        id: i64,
+        instance: Option<u32>,
+        outcome: &Outcome,
+        succeeded: Option<bool>,
+    ) -> crate::Result<()> {
+        if let Some(instance) = instance {
+            let am = entity::replica_states::ActiveModel {
+                task_id: Set(id),
+                instance: Set(i64::from(instance)),
+                last_outcome: Set(serde_json::to_string(outcome).ok()),
+                ..Default::default()
+            };
+            upsert_replica_state(self, am, entity::replica_states::Column::LastOutcome).await?;
+        }
         let am = entity::tasks::ActiveModel {
             id: Unchanged(id),
-            last_exit_code: Set(Some(exit_code)),
+            last_exit_code: Set(outcome.code()),
+            last_succeeded: Set(succeeded),
+            last_outcome: Set(serde_json::to_string(outcome).ok()),
             ..Default::default()
         };
         am.update(self).await.map_err(|e| {
             crate::Error::with_source(
                 crate::ErrorKind::Db,
-                format!("failed to update exit code of task id: {id}"),
+                format!("failed to update outcome of task id: {id}"),
                 Box::new(e),
             )
         })?;
@@ -297,6 +633,79 @@ impl TaskDAO for DatabaseConnection {
         Ok(())
     }
 
+    async fn set_task_suspension(
+        &self,
+        id: i64,
+        instance: Option<u32>,
+        suspension: Option<&Suspension>,
+    ) -> crate::Result<()> {
+        if let Some(instance) = instance {
+            let am = entity::replica_states::ActiveModel {
+                task_id: Set(id),
+                instance: Set(i64::from(instance)),
+                suspended: Set(suspension.and_then(|s| serde_json::to_string(s).ok())),
+                ..Default::default()
+            };
+            return upsert_replica_state(self, am, entity::replica_states::Column::Suspended).await;
+        }
+        let am = entity::tasks::ActiveModel {
+            id: Unchanged(id),
+            suspended: Set(suspension.and_then(|s| serde_json::to_string(s).ok())),
+            ..Default::default()
+        };
+        am.update(self).await.map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                format!("failed to update suspension of task id: {id}"),
+                Box::new(e),
+            )
+        })?;
+        Ok(())
+    }
+
+    async fn clear_task_suspensions(&self, id: i64) -> crate::Result<()> {
+        self.transaction::<_, (), crate::Error>(|txn| {
+            Box::pin(async move {
+                let am = entity::tasks::ActiveModel {
+                    id: Unchanged(id),
+                    suspended: Set(None),
+                    ..Default::default()
+                };
+                am.update(txn).await.map_err(|e| {
+                    crate::Error::with_source(
+                        crate::ErrorKind::Db,
+                        format!("failed to clear suspension of task id: {id}"),
+                        Box::new(e),
+                    )
+                })?;
+                entity::replica_states::Entity::update_many()
+                    .set(entity::replica_states::ActiveModel {
+                        suspended: Set(None),
+                        ..Default::default()
+                    })
+                    .filter(entity::replica_states::Column::TaskId.eq(id))
+                    .exec(txn)
+                    .await
+                    .map_err(|e| {
+                        crate::Error::with_source(
+                            crate::ErrorKind::Db,
+                            format!("failed to clear suspensions of replicas of task id: {id}"),
+                            Box::new(e),
+                        )
+                    })?;
+                Ok(())
+            })
+        })
+        .await
+        .map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                "failed to clear suspensions in transaction",
+                Box::new(e),
+            )
+        })
+    }
+
     async fn reorder_tasks(&self, ordered_ids: Vec<i64>) -> crate::Result<()> {
         self.transaction::<_, (), crate::Error>(|txn| {
             Box::pin(async move {
@@ -328,3 +737,146 @@ impl TaskDAO for DatabaseConnection {
         Ok(())
     }
 }
+
+/// 读取副本的状态, 按任务 id 和副本编号索引, `task_id` 为空时读取所有任务的副本.
+async fn replica_states(
+    db: &DatabaseConnection,
+    task_id: Option<i64>,
+) -> crate::Result<HashMap<i64, HashMap<u32, ReplicaState>>> {
+    let mut query = entity::replica_states::Entity::find();
+    if let Some(id) = task_id {
+        query = query.filter(entity::replica_states::Column::TaskId.eq(id));
+    }
+    let rows = query.all(db).await.map_err(|e| {
+        crate::Error::with_source(
+            crate::ErrorKind::Db,
+            "failed to list replica states",
+            Box::new(e),
+        )
+    })?;
+    let mut states: HashMap<i64, HashMap<u32, ReplicaState>> = HashMap::new();
+    for row in rows {
+        let Ok(instance) = u32::try_from(row.instance) else {
+            continue;
+        };
+        states.entry(row.task_id).or_default().insert(
+            instance,
+            ReplicaState {
+                last_outcome: row.last_outcome.and_then(|s| serde_json::from_str(&s).ok()),
+                suspended: row.suspended.and_then(|s| serde_json::from_str(&s).ok()),
+            },
+        );
+    }
+    Ok(states)
+}
+
+/// 写入一个副本的状态中的 `column` 列, 副本还没有状态时插入一行.
+///
+/// 插入和更新在同一条语句中完成, 只修改这个副本的这一列, 其他副本同时写入时不会互相覆盖.
+async fn upsert_replica_state(
+    db: &DatabaseConnection,
+    am: entity::replica_states::ActiveModel,
+    column: entity::replica_states::Column,
+) -> crate::Result<()> {
+    entity::replica_states::Entity::insert(am)
+        .on_conflict(
+            OnConflict::columns([
+                entity::replica_states::Column::TaskId,
+                entity::replica_states::Column::Instance,
+            ])
+            .update_column(column)
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await
+        .map_err(|e| {
+            crate::Error::with_source(
+                crate::ErrorKind::Db,
+                "failed to update replica state",
+                Box::new(e),
+            )
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replicated() -> Task {
+        Task::builder()
+            .name("worker")
+            .program("/opt/${INSTANCE}/worker")
+            .args(vec!["--port=80${INSTANCE}".to_string(), "$$".to_string()])
+            .stdout("/var/log/worker-${INSTANCE}.log")
+            .env_vars(HashMap::from([(
+                "DATA".to_string(),
+                "/data/${INSTANCE}".to_string(),
+            )]))
+            .trigger(Trigger::KeepAlive)
+            .replicas(3)
+            .build()
+    }
+
+    #[test]
+    fn instances_expand_instance() {
+        let instances = replicated().instances();
+        assert_eq!(instances.len(), 3);
+        let replica = &instances[2];
+        assert_eq!(replica.instance, Some(2));
+        assert_eq!(replica.program, PathBuf::from("/opt/2/worker"));
+        assert_eq!(replica.args, ["--port=802", "$$"]);
+        assert_eq!(replica.stdout, Some(PathBuf::from("/var/log/worker-2.log")));
+        assert_eq!(replica.env_vars["DATA"], "/data/2");
+        assert_eq!(replica.env_vars["INSTANCE"], "2");
+    }
+
+    #[test]
+    fn single_instance_is_unchanged() {
+        let task = Task {
+            replicas: 1,
+            ..replicated()
+        };
+        let instances = task.instances();
+        assert_eq!(instances.len(), 1);
+        assert_eq!(instances[0].instance, None);
+        assert_eq!(
+            instances[0].program,
+            PathBuf::from("/opt/${INSTANCE}/worker")
+        );
+        assert!(!instances[0].env_vars.contains_key("INSTANCE"));
+        assert_eq!(instances[0].expand_instance("${INSTANCE}"), "${INSTANCE}");
+    }
+
+    #[test]
+    fn instances_restore_replica_state() {
+        let mut task = replicated();
+        task.replica_states.insert(
+            1,
+            ReplicaState {
+                last_outcome: Some(Outcome::Exited { code: 3 }),
+                suspended: None,
+            },
+        );
+        let instances = task.instances();
+        assert_eq!(instances[0].last_outcome, None);
+        assert_eq!(instances[1].last_outcome, Some(Outcome::Exited { code: 3 }));
+    }
+
+    #[test]
+    fn first_replica_adopts_orphaned_runs() {
+        let instances = replicated().instances();
+        assert!(instances[1].adopts(Some(1)));
+        assert!(!instances[1].adopts(Some(0)));
+        // 减少副本数量之前和不使用副本时留下的运行
+        assert!(instances[0].adopts(Some(5)));
+        assert!(instances[0].adopts(None));
+        assert!(!instances[1].adopts(Some(5)));
+        let single = Task {
+            replicas: 1,
+            ..replicated()
+        };
+        assert!(single.adopts(None));
+        assert!(single.adopts(Some(2)));
+    }
+}
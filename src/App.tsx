@@ -1,11 +1,14 @@
 import { useState, useEffect } from "react";
-import { AlertCircle, Globe, Power, RefreshCw, Settings } from "lucide-react";
+import { listen } from "@tauri-apps/api/event";
+import { AlertCircle, Globe, Layers, Power, RefreshCw, Settings } from "lucide-react";
 import { useTranslation } from "react-i18next";
 import { toast } from "sonner";
 import "./App.css";
 import { TaskList } from "./components/TaskList";
 import { TaskEditDialog } from "./components/TaskEditDialog";
+import { TaskOutputDialog } from "./components/TaskOutputDialog";
 import { AppConfigDialog } from "./components/AppConfigDialog";
+import { EnvironmentsDialog } from "./components/EnvironmentsDialog";
 import { Button } from "./components/ui/button";
 import {
   Dialog,
@@ -18,7 +21,7 @@ import {
 import { useTaskList, useTaskActions } from "./lib/hooks";
 import { taskApi, appApi } from "./lib/api";
 import type { Task } from "./types/task";
-import { TaskStatus } from "./types/task";
+import type { ProgressEvent, TaskStatus } from "./types/task";
 
 function App() {
   const { t, i18n } = useTranslation();
@@ -26,8 +29,12 @@ function App() {
   const { saveTask, removeTask, switchTask, manuallyRunTask } = useTaskActions();
   const [selectedTask, setSelectedTask] = useState<Task | undefined>(undefined);
   const [dialogOpen, setDialogOpen] = useState(false);
+  const [outputTask, setOutputTask] = useState<Task | undefined>(undefined);
   const [configDialogOpen, setConfigDialogOpen] = useState(false);
+  const [environmentsDialogOpen, setEnvironmentsDialogOpen] = useState(false);
   const [exitDialogOpen, setExitDialogOpen] = useState(false);
+  const [inputTask, setInputTask] = useState<Task | undefined>(undefined);
+  const [runInput, setRunInput] = useState("");
   const [taskRunStatus, setTaskRunStatus] = useState<Record<number, boolean>>({});
   const [runnableProgramStatus, setRunnableProgramStatus] = useState<Record<number, boolean>>({});
   const [taskStatusById, setTaskStatusById] = useState<Record<number, TaskStatus>>({});
@@ -42,10 +49,13 @@ function App() {
         try {
           const status = await taskApi.getTaskStatus(task.id);
           statusById[task.id] = status;
-          statuses[task.id] = status === TaskStatus.Running;
+          statuses[task.id] =
+            status.tag === "Running" ||
+            (status.tag === "Replicated" &&
+              status.content.replicas.some((replica) => replica.tag === "Running"));
         } catch {
           statuses[task.id] = false;
-          statusById[task.id] = TaskStatus.Idle;
+          statusById[task.id] = { tag: "Idle", content: {} };
         }
 
         // Check if program is runnable
@@ -76,6 +86,21 @@ function App() {
     return () => clearInterval(interval);
   }, [tasks]);
 
+  // 进度报告实时更新, 不等待下一次刷新
+  useEffect(() => {
+    const unlisten = listen<ProgressEvent>("task-progress", (event) => {
+      const { task_id, progress } = event.payload;
+      setTaskStatusById((prev) => {
+        const status = prev[task_id];
+        if (status?.tag !== "Running") return prev;
+        return { ...prev, [task_id]: { ...status, content: { ...status.content, progress } } };
+      });
+    });
+    return () => {
+      unlisten.then((stop) => stop());
+    };
+  }, []);
+
   const handleEditTask = (task: Task) => {
     setSelectedTask(task);
     setDialogOpen(true);
@@ -110,9 +135,16 @@ function App() {
     }
   };
 
-  const handleRunTask = async (id: number) => {
+  const handleRunTask = async (id: number, payload?: string) => {
+    // 标准输入来自触发器的任务, 先填写输入
+    const task = tasks.find((t) => t.id === id);
+    if (payload === undefined && task?.stdin_source?.tag === "Trigger") {
+      setRunInput("");
+      setInputTask(task);
+      return;
+    }
     try {
-      await manuallyRunTask(id);
+      await manuallyRunTask(id, payload);
     } catch (err) {
       console.error("Failed to run task:", err);
     }
@@ -229,6 +261,15 @@ function App() {
               >
                 <Settings className="h-3 w-3" />
               </Button>
+              <Button
+                size="sm"
+                variant="outline"
+                onClick={() => setEnvironmentsDialogOpen(true)}
+                className="text-xs"
+                title={t("button.environments")}
+              >
+                <Layers className="h-3 w-3" />
+              </Button>
               <Button
                 size="sm"
                 variant="outline"
@@ -287,6 +328,7 @@ function App() {
           <TaskList
             tasks={tasks}
             onEdit={handleEditTask}
+            onViewOutput={setOutputTask}
             onDelete={handleDeleteTask}
             onRun={handleRunTask}
             onStop={handleStopTask}
@@ -307,12 +349,59 @@ function App() {
         onSave={handleSaveTask}
       />
 
+      {/* Task Output Dialog */}
+      <TaskOutputDialog
+        open={outputTask !== undefined}
+        task={outputTask}
+        onOpenChange={(open) => !open && setOutputTask(undefined)}
+      />
+
       {/* App Config Dialog */}
       <AppConfigDialog
         open={configDialogOpen}
         onOpenChange={setConfigDialogOpen}
       />
 
+      {/* Environments Dialog */}
+      <EnvironmentsDialog
+        open={environmentsDialogOpen}
+        onOpenChange={setEnvironmentsDialogOpen}
+      />
+
+      <Dialog
+        open={inputTask !== undefined}
+        onOpenChange={(open) => !open && setInputTask(undefined)}
+      >
+        <DialogContent className="max-w-lg">
+          <DialogHeader>
+            <DialogTitle>{t("dialog.runInputTitle", { name: inputTask?.name ?? "" })}</DialogTitle>
+            <DialogDescription>{t("dialog.runInputDesc")}</DialogDescription>
+          </DialogHeader>
+          <textarea
+            rows={8}
+            className="flex w-full rounded-md border border-slate-200 bg-white px-3 py-2 font-mono text-sm ring-offset-white focus-visible:outline-none focus-visible:ring-2 focus-visible:ring-slate-950 focus-visible:ring-offset-2"
+            value={runInput}
+            onChange={(e) => setRunInput(e.target.value)}
+          />
+          <DialogFooter>
+            <Button variant="ghost" onClick={() => setInputTask(undefined)}>
+              {t("button.cancel")}
+            </Button>
+            <Button
+              onClick={async () => {
+                const id = inputTask?.id;
+                setInputTask(undefined);
+                if (id !== undefined) {
+                  await handleRunTask(id, runInput);
+                }
+              }}
+            >
+              {t("button.runWithInput")}
+            </Button>
+          </DialogFooter>
+        </DialogContent>
+      </Dialog>
+
       <Dialog open={exitDialogOpen} onOpenChange={setExitDialogOpen}>
         <DialogContent className="max-w-sm">
           <DialogHeader>
@@ -12,8 +12,31 @@ import {
   DialogDescription,
 } from "@/components/ui/dialog";
 import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { Switch } from "@/components/ui/switch";
+import { SuspensionPolicyFields } from "@/components/SuspensionPolicyFields";
+
+// 解释器的文本格式, 每行一个扩展名, 例如 "py = python3 -u"
+const interpretersToText = (interpreters?: Record<string, string[]>) =>
+  Object.entries(interpreters ?? {})
+    .map(([ext, command]) => `${ext} = ${command.join(" ")}`)
+    .join("\n");
+
+const textToInterpreters = (text: string): Record<string, string[]> =>
+  Object.fromEntries(
+    text
+      .split("\n")
+      .map((line) => line.split("="))
+      .filter(([ext, command]) => ext?.trim() && command?.trim())
+      .map(([ext, ...command]): [string, string[]] => [
+        ext.trim().replace(/^\./, ""),
+        command.join("=").trim().split(/\s+/),
+      ])
+  );
+
+const textareaClass =
+  "flex w-full rounded-md border border-slate-200 bg-white px-3 py-2 font-mono text-sm ring-offset-white placeholder:text-slate-500 focus-visible:outline-none focus-visible:ring-2 focus-visible:ring-slate-950 focus-visible:ring-offset-2";
 
 interface AppConfigDialogProps {
   open: boolean;
@@ -31,6 +54,9 @@ export function AppConfigDialog({
     quiet_launch: false,
   });
   const [loading, setLoading] = useState(true);
+  const [searchPathText, setSearchPathText] = useState("");
+  const [interpretersText, setInterpretersText] = useState("");
+  const [refreshingEnv, setRefreshingEnv] = useState(false);
 
   useEffect(() => {
     if (open) {
@@ -43,6 +69,8 @@ export function AppConfigDialog({
       setLoading(true);
       const loadedConfig = await configApi.getConfig();
       setConfig(loadedConfig);
+      setSearchPathText((loadedConfig.search_path ?? []).join("\n"));
+      setInterpretersText(interpretersToText(loadedConfig.interpreters));
     } catch (err) {
       console.error("Failed to load config:", err);
       toast.error(t("toast.loadConfigFailed"));
@@ -64,6 +92,22 @@ export function AppConfigDialog({
     }
   };
 
+  // 使用已保存的设置, 开关修改之后需要先保存
+  const handleRefreshShellEnv = async () => {
+    try {
+      setRefreshingEnv(true);
+      const count = await configApi.refreshShellEnv();
+      toast.success(t("toast.shellEnvRefreshed", { count }));
+    } catch (err) {
+      console.error("Failed to refresh shell environment:", err);
+      toast.error(t("toast.shellEnvRefreshFailed"), {
+        description: typeof err === "string" ? err : t("toast.unknownError"),
+      });
+    } finally {
+      setRefreshingEnv(false);
+    }
+  };
+
   return (
     <Dialog open={open} onOpenChange={onOpenChange}>
       <DialogContent className="max-w-md">
@@ -100,6 +144,105 @@ export function AppConfigDialog({
               </div>
               <p className="text-xs text-slate-500">{t("form.quietLaunchDesc")}</p>
             </div>
+
+            {/* cgroup Root */}
+            <div className="space-y-2">
+              <Label htmlFor="cgroup-root">{t("form.cgroupRoot")}</Label>
+              <Input
+                id="cgroup-root"
+                placeholder="/sys/fs/cgroup/user.slice/user-1000.slice/user@1000.service/app.slice"
+                value={config.cgroup_root || ""}
+                onChange={(e) =>
+                  setConfig((prev) => ({
+                    ...prev,
+                    cgroup_root: e.target.value || null,
+                  }))
+                }
+              />
+              <p className="text-xs text-slate-500">{t("form.cgroupRootDesc")}</p>
+            </div>
+
+            {/* Login Shell Environment */}
+            <div className="space-y-2">
+              <div className="flex items-center justify-between">
+                <Label htmlFor="login-shell-env">{t("form.loginShellEnv")}</Label>
+                <div className="flex items-center gap-2">
+                  <Button
+                    size="sm"
+                    variant="outline"
+                    onClick={handleRefreshShellEnv}
+                    disabled={!config.login_shell_env || refreshingEnv}
+                  >
+                    {t("button.refreshShellEnv")}
+                  </Button>
+                  <Switch
+                    id="login-shell-env"
+                    checked={config.login_shell_env ?? false}
+                    onCheckedChange={(checked) =>
+                      setConfig((prev) => ({
+                        ...prev,
+                        login_shell_env: checked,
+                      }))
+                    }
+                  />
+                </div>
+              </div>
+              <p className="text-xs text-slate-500">{t("form.loginShellEnvDesc")}</p>
+            </div>
+
+            {/* Search Path */}
+            <div className="space-y-2">
+              <Label htmlFor="search-path">{t("form.searchPath")}</Label>
+              <textarea
+                id="search-path"
+                rows={2}
+                className={textareaClass}
+                placeholder="/home/me/.local/bin"
+                value={searchPathText}
+                onChange={(e) => {
+                  setSearchPathText(e.target.value);
+                  setConfig((prev) => ({
+                    ...prev,
+                    search_path: e.target.value
+                      .split("\n")
+                      .map((p) => p.trim())
+                      .filter((p) => p !== ""),
+                  }));
+                }}
+              />
+              <p className="text-xs text-slate-500">{t("form.searchPathDesc")}</p>
+            </div>
+
+            {/* Interpreters */}
+            <div className="space-y-2">
+              <Label htmlFor="interpreters">{t("form.interpreters")}</Label>
+              <textarea
+                id="interpreters"
+                rows={3}
+                className={textareaClass}
+                placeholder={"py = python3\njs = node"}
+                value={interpretersText}
+                onChange={(e) => {
+                  setInterpretersText(e.target.value);
+                  setConfig((prev) => ({
+                    ...prev,
+                    interpreters: textToInterpreters(e.target.value),
+                  }));
+                }}
+              />
+              <p className="text-xs text-slate-500">{t("form.interpretersDesc")}</p>
+            </div>
+
+            {/* Default Suspension */}
+            <div className="space-y-2">
+              <Label>{t("form.defaultSuspension")}</Label>
+              <SuspensionPolicyFields
+                idPrefix="default-suspension"
+                policy={config.suspension || {}}
+                onChange={(suspension) => setConfig((prev) => ({ ...prev, suspension }))}
+              />
+              <p className="text-xs text-slate-500">{t("form.suspensionDesc")}</p>
+            </div>
           </div>
         )}
 
@@ -0,0 +1,244 @@
+import { useState, useEffect } from "react";
+import { Plus, Trash2 } from "lucide-react";
+import { useTranslation } from "react-i18next";
+import { toast } from "sonner";
+import type { Environment } from "@/types/environment";
+import { environmentApi } from "@/lib/api";
+import {
+  Dialog,
+  DialogContent,
+  DialogHeader,
+  DialogTitle,
+  DialogFooter,
+  DialogDescription,
+} from "@/components/ui/dialog";
+import { Button } from "@/components/ui/button";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+import { cn } from "@/lib/utils";
+
+interface EnvironmentsDialogProps {
+  open: boolean;
+  onOpenChange: (open: boolean) => void;
+}
+
+// 编辑中的环境, 环境变量和 PATH 前缀使用文本格式, 每行一个
+interface EnvironmentForm {
+  id?: number;
+  name: string;
+  envVarsText: string;
+  pathPrefixesText: string;
+  activationScript: string;
+}
+
+const emptyForm = (): EnvironmentForm => ({
+  name: "",
+  envVarsText: "",
+  pathPrefixesText: "",
+  activationScript: "",
+});
+
+const toForm = (env: Environment): EnvironmentForm => ({
+  id: env.id,
+  name: env.name,
+  envVarsText: Object.entries(env.env_vars ?? {})
+    .map(([key, value]) => `${key}=${value}`)
+    .join("\n"),
+  pathPrefixesText: (env.path_prefixes ?? []).join("\n"),
+  activationScript: env.activation_script ?? "",
+});
+
+const fromForm = (form: EnvironmentForm): Environment => ({
+  id: form.id,
+  name: form.name.trim(),
+  env_vars: Object.fromEntries(
+    form.envVarsText
+      .split("\n")
+      .filter((line) => line.includes("="))
+      .map((line) => {
+        const index = line.indexOf("=");
+        return [line.slice(0, index).trim(), line.slice(index + 1)];
+      })
+      .filter(([key]) => key !== "")
+  ),
+  path_prefixes: form.pathPrefixesText
+    .split("\n")
+    .map((p) => p.trim())
+    .filter((p) => p !== ""),
+  activation_script: form.activationScript.trim() ? form.activationScript : null,
+});
+
+const textareaClass =
+  "flex w-full rounded-md border border-slate-200 bg-white px-3 py-2 font-mono text-sm ring-offset-white placeholder:text-slate-500 focus-visible:outline-none focus-visible:ring-2 focus-visible:ring-slate-950 focus-visible:ring-offset-2";
+
+export function EnvironmentsDialog({ open, onOpenChange }: EnvironmentsDialogProps) {
+  const { t } = useTranslation();
+  const [environments, setEnvironments] = useState<Environment[]>([]);
+  const [form, setForm] = useState<EnvironmentForm>(emptyForm());
+  const [saving, setSaving] = useState(false);
+
+  useEffect(() => {
+    if (open) {
+      loadEnvironments();
+      setForm(emptyForm());
+    }
+  }, [open]);
+
+  const loadEnvironments = async () => {
+    try {
+      setEnvironments(await environmentApi.listEnvironments());
+    } catch (err) {
+      console.error("Failed to load environments:", err);
+      toast.error(t("toast.loadEnvironmentsFailed"));
+    }
+  };
+
+  const handleSave = async () => {
+    if (!form.name.trim()) {
+      toast.error(t("toast.environmentNameRequired"));
+      return;
+    }
+    try {
+      setSaving(true);
+      const id = await environmentApi.saveEnvironment(fromForm(form));
+      setForm((prev) => ({ ...prev, id }));
+      await loadEnvironments();
+      toast.success(t("toast.environmentSaved"));
+    } catch (err) {
+      console.error("Failed to save environment:", err);
+      toast.error(t("toast.environmentSaveFailed"), {
+        description: typeof err === "string" ? err : t("toast.unknownError"),
+      });
+    } finally {
+      setSaving(false);
+    }
+  };
+
+  const handleRemove = async () => {
+    if (form.id === undefined) return;
+    try {
+      await environmentApi.removeEnvironment(form.id);
+      setForm(emptyForm());
+      await loadEnvironments();
+      toast.success(t("toast.environmentRemoved"));
+    } catch (err) {
+      console.error("Failed to remove environment:", err);
+      toast.error(t("toast.environmentRemoveFailed"), {
+        description: typeof err === "string" ? err : t("toast.unknownError"),
+      });
+    }
+  };
+
+  return (
+    <Dialog open={open} onOpenChange={onOpenChange}>
+      <DialogContent className="max-w-2xl">
+        <DialogHeader>
+          <DialogTitle>{t("dialog.environmentsTitle")}</DialogTitle>
+          <DialogDescription>{t("dialog.environmentsDesc")}</DialogDescription>
+        </DialogHeader>
+
+        <div className="flex gap-4">
+          {/* Environment List */}
+          <div className="w-40 shrink-0 space-y-1">
+            <Button
+              size="sm"
+              variant="outline"
+              className="w-full text-xs"
+              onClick={() => setForm(emptyForm())}
+            >
+              <Plus className="h-3 w-3 mr-1" />
+              {t("button.newEnvironment")}
+            </Button>
+            {environments.map((env) => (
+              <button
+                key={env.id}
+                type="button"
+                onClick={() => setForm(toForm(env))}
+                className={cn(
+                  "w-full truncate rounded px-2 py-1 text-left text-sm hover:bg-slate-100",
+                  form.id === env.id && "bg-slate-100 font-medium"
+                )}
+              >
+                {env.name}
+              </button>
+            ))}
+            {environments.length === 0 && (
+              <p className="px-2 text-xs text-slate-500">{t("status.noEnvironments")}</p>
+            )}
+          </div>
+
+          {/* Environment Form */}
+          <div className="flex-1 space-y-3">
+            <div className="space-y-1">
+              <Label htmlFor="environment-name">{t("form.environmentName")}</Label>
+              <Input
+                id="environment-name"
+                placeholder="scraper-venv"
+                value={form.name}
+                onChange={(e) => setForm((prev) => ({ ...prev, name: e.target.value }))}
+              />
+            </div>
+            <div className="space-y-1">
+              <Label htmlFor="environment-vars">{t("form.environmentVariables")}</Label>
+              <textarea
+                id="environment-vars"
+                rows={4}
+                className={textareaClass}
+                placeholder={"HTTPS_PROXY=http://proxy:3128\nPYTHONUNBUFFERED=1"}
+                value={form.envVarsText}
+                onChange={(e) => setForm((prev) => ({ ...prev, envVarsText: e.target.value }))}
+              />
+            </div>
+            <div className="space-y-1">
+              <Label htmlFor="environment-path">{t("form.pathPrefixes")}</Label>
+              <textarea
+                id="environment-path"
+                rows={2}
+                className={textareaClass}
+                placeholder="/opt/venvs/scraper/bin"
+                value={form.pathPrefixesText}
+                onChange={(e) =>
+                  setForm((prev) => ({ ...prev, pathPrefixesText: e.target.value }))
+                }
+              />
+              <p className="text-xs text-slate-500">{t("form.pathPrefixesDesc")}</p>
+            </div>
+            <div className="space-y-1">
+              <Label htmlFor="environment-script">{t("form.activationScript")}</Label>
+              <textarea
+                id="environment-script"
+                rows={2}
+                className={textareaClass}
+                placeholder=". /opt/venvs/scraper/bin/activate"
+                value={form.activationScript}
+                onChange={(e) =>
+                  setForm((prev) => ({ ...prev, activationScript: e.target.value }))
+                }
+              />
+              <p className="text-xs text-slate-500">{t("form.activationScriptDesc")}</p>
+            </div>
+          </div>
+        </div>
+
+        <DialogFooter className="gap-2">
+          {form.id !== undefined && (
+            <Button
+              variant="outline"
+              onClick={handleRemove}
+              className="mr-auto text-red-600 hover:text-red-700 hover:bg-red-50"
+            >
+              <Trash2 className="h-4 w-4 mr-1" />
+              {t("button.delete")}
+            </Button>
+          )}
+          <Button variant="outline" onClick={() => onOpenChange(false)}>
+            {t("button.close")}
+          </Button>
+          <Button onClick={handleSave} disabled={saving}>
+            {saving ? t("button.saving") : t("button.save")}
+          </Button>
+        </DialogFooter>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -0,0 +1,57 @@
+import { useTranslation } from "react-i18next";
+import type { SuspensionPolicy } from "@/types/task";
+import { Input } from "@/components/ui/input";
+import { Label } from "@/components/ui/label";
+
+interface SuspensionPolicyFieldsProps {
+  /** 用于区分同一页面中的多组输入 */
+  idPrefix: string;
+  policy: SuspensionPolicy;
+  onChange: (policy: SuspensionPolicy) => void;
+}
+
+// Suspension 条件: 时间窗口内的失败次数和自动恢复的等待时间
+export function SuspensionPolicyFields({ idPrefix, policy, onChange }: SuspensionPolicyFieldsProps) {
+  const { t } = useTranslation();
+  const optional = (value: string) => (value === "" ? undefined : Number(value));
+
+  return (
+    <div className="grid grid-cols-3 gap-2">
+      <div className="space-y-1">
+        <Label htmlFor={`${idPrefix}-failures`} className="text-xs">{t("form.suspensionFailures")}</Label>
+        <Input
+          id={`${idPrefix}-failures`}
+          type="number"
+          min="1"
+          placeholder="6"
+          value={policy.failures ?? ""}
+          onChange={(e) => onChange({ ...policy, failures: optional(e.target.value) })}
+        />
+      </div>
+      <div className="space-y-1">
+        <Label htmlFor={`${idPrefix}-timespan`} className="text-xs">{t("form.suspensionTimespan")}</Label>
+        <Input
+          id={`${idPrefix}-timespan`}
+          type="number"
+          min="0"
+          placeholder="60000"
+          value={policy.timespan_ms ?? ""}
+          onChange={(e) => onChange({ ...policy, timespan_ms: optional(e.target.value) })}
+        />
+      </div>
+      <div className="space-y-1">
+        <Label htmlFor={`${idPrefix}-cooldown`} className="text-xs">{t("form.suspensionCooldown")}</Label>
+        <Input
+          id={`${idPrefix}-cooldown`}
+          type="number"
+          min="0"
+          placeholder={t("form.manualResume")}
+          value={policy.cooldown_ms ?? ""}
+          onChange={(e) =>
+            onChange({ ...policy, cooldown_ms: e.target.value === "" ? null : Number(e.target.value) })
+          }
+        />
+      </div>
+    </div>
+  );
+}
@@ -2,8 +2,28 @@ import { useState, useEffect } from "react";
 import { Plus, Trash2, FolderOpen } from "lucide-react";
 import { useTranslation } from "react-i18next";
 import { toast } from "sonner";
-import type { Task, Trigger } from "@/types/task";
-import { appApi } from "@/lib/api";
+import type {
+  CgroupLimits,
+  ConcurrencyPolicy,
+  HealthCheck,
+  Hook,
+  Hooks,
+  Identity,
+  IoMax,
+  OnExit,
+  OutputOptions,
+  Priority,
+  Probe,
+  ResourceLimits,
+  RestartPolicy,
+  SandboxOptions,
+  SuccessCriteria,
+  Task,
+  Trigger,
+} from "@/types/task";
+import { appApi, environmentApi } from "@/lib/api";
+import { isLinux } from "@/lib/utils";
+import type { Environment } from "@/types/environment";
 import {
   Dialog,
   DialogContent,
@@ -16,6 +36,7 @@ import { Button } from "@/components/ui/button";
 import { Input } from "@/components/ui/input";
 import { Label } from "@/components/ui/label";
 import { Switch } from "@/components/ui/switch";
+import { SuspensionPolicyFields } from "@/components/SuspensionPolicyFields";
 import {
   Select,
   SelectContent,
@@ -34,6 +55,14 @@ interface TaskEditDialogProps {
 
 type TriggerType = "Manual" | "Startup" | "KeepAlive" | "Routine" | "Instant" | "UntilSucceed";
 
+type RotationType = "None" | "Size" | "Interval";
+
+const defaultOutputOptions = (): OutputOptions => ({
+  append: false,
+  timestamp: false,
+  rotation: null,
+});
+
 const getProgramBaseName = (programPath: string) => {
   if (!programPath) return "";
   let normalized = programPath.replace(/\\/g, "/");
@@ -58,6 +87,865 @@ const envVarsListToDict = (list: Array<{ key: string; value: string }>): Record<
   return dict;
 };
 
+// 添加时间戳或者轮转时输出需要由管理器转发
+const needsPump = (options?: OutputOptions): boolean =>
+  !!options && (options.timestamp || !!options.rotation);
+
+interface OutputOptionsFieldsProps {
+  id: string;
+  options: OutputOptions;
+  onChange: (options: OutputOptions) => void;
+}
+
+// 单个输出流的追加/时间戳/轮转设置
+function OutputOptionsFields({ id, options, onChange }: OutputOptionsFieldsProps) {
+  const { t } = useTranslation();
+  const rotationType: RotationType = options.rotation?.policy.tag ?? "None";
+  const keep = options.rotation?.keep ?? 5;
+
+  const handleRotationTypeChange = (type: RotationType) => {
+    switch (type) {
+      case "Size":
+        onChange({ ...options, rotation: { policy: { tag: "Size", content: 10 * 1024 * 1024 }, keep } });
+        break;
+      case "Interval":
+        onChange({ ...options, rotation: { policy: { tag: "Interval", content: { secs: 86400, nanos: 0 } }, keep } });
+        break;
+      case "None":
+      default:
+        onChange({ ...options, rotation: null });
+    }
+  };
+
+  return (
+    <div className="space-y-2 pl-3 border-l">
+      <div className="flex items-center justify-between">
+        <Label htmlFor={`${id}-append`} className="text-xs">{t("form.appendOutput")}</Label>
+        <Switch
+          id={`${id}-append`}
+          checked={options.append}
+          onCheckedChange={(checked) => onChange({ ...options, append: checked })}
+        />
+      </div>
+      <div className="flex items-center justify-between">
+        <Label htmlFor={`${id}-timestamp`} className="text-xs">{t("form.timestampOutput")}</Label>
+        <Switch
+          id={`${id}-timestamp`}
+          checked={options.timestamp}
+          onCheckedChange={(checked) => onChange({ ...options, timestamp: checked })}
+        />
+      </div>
+      <div className="flex gap-2">
+        <Select value={rotationType} onValueChange={handleRotationTypeChange}>
+          <SelectTrigger id={`${id}-rotation`} className="flex-1">
+            <SelectValue />
+          </SelectTrigger>
+          <SelectContent>
+            <SelectItem value="None">{t("form.rotationNone")}</SelectItem>
+            <SelectItem value="Size">{t("form.rotationSize")}</SelectItem>
+            <SelectItem value="Interval">{t("form.rotationInterval")}</SelectItem>
+          </SelectContent>
+        </Select>
+        {options.rotation?.policy.tag === "Size" && (
+          <Input
+            type="number"
+            min="1"
+            className="flex-1"
+            title={t("form.rotationSizeMb")}
+            value={Math.round(options.rotation.policy.content / 1024 / 1024)}
+            onChange={(e) =>
+              onChange({
+                ...options,
+                rotation: { policy: { tag: "Size", content: Number(e.target.value) * 1024 * 1024 }, keep },
+              })
+            }
+          />
+        )}
+        {options.rotation?.policy.tag === "Interval" && (
+          <Input
+            type="number"
+            min="1"
+            className="flex-1"
+            title={t("form.rotationIntervalHours")}
+            value={Math.round(options.rotation.policy.content.secs / 3600)}
+            onChange={(e) =>
+              onChange({
+                ...options,
+                rotation: {
+                  policy: { tag: "Interval", content: { secs: Number(e.target.value) * 3600, nanos: 0 } },
+                  keep,
+                },
+              })
+            }
+          />
+        )}
+        {options.rotation && (
+          <Input
+            type="number"
+            min="0"
+            className="w-20"
+            title={t("form.rotationKeep")}
+            value={keep}
+            onChange={(e) =>
+              onChange({
+                ...options,
+                rotation: { ...options.rotation!, keep: Number(e.target.value) },
+              })
+            }
+          />
+        )}
+      </div>
+      {options.rotation && (
+        <p className="text-xs text-slate-500">
+          {options.rotation.policy.tag === "Size" ? t("form.rotationSizeMb") : t("form.rotationIntervalHours")}
+          {" / "}
+          {t("form.rotationKeep")}
+        </p>
+      )}
+    </div>
+  );
+}
+
+interface ResourceLimitsFieldsProps {
+  limits: ResourceLimits;
+  onChange: (limits: ResourceLimits) => void;
+}
+
+const MIB = 1024 * 1024;
+
+// 资源限制, 留空表示不限制; 地址空间以 MiB 为单位编辑
+function ResourceLimitsFields({ limits, onChange }: ResourceLimitsFieldsProps) {
+  const { t } = useTranslation();
+  const fields: Array<{ key: keyof ResourceLimits; label: string; scale: number }> = [
+    { key: "address_space", label: t("form.limitAddressSpace"), scale: MIB },
+    { key: "cpu_seconds", label: t("form.limitCpuSeconds"), scale: 1 },
+    { key: "open_files", label: t("form.limitOpenFiles"), scale: 1 },
+    { key: "processes", label: t("form.limitProcesses"), scale: 1 },
+    { key: "core_size", label: t("form.limitCoreSize"), scale: MIB },
+  ];
+
+  return (
+    <div className="grid grid-cols-2 gap-2">
+      {fields.map(({ key, label, scale }) => (
+        <div key={key} className="space-y-1">
+          <Label htmlFor={`limit-${key}`} className="text-xs">{label}</Label>
+          <Input
+            id={`limit-${key}`}
+            type="number"
+            min="0"
+            placeholder={t("form.limitUnlimited")}
+            value={limits[key] == null ? "" : Math.round(limits[key]! / scale)}
+            onChange={(e) =>
+              onChange({
+                ...limits,
+                [key]: e.target.value === "" ? null : Number(e.target.value) * scale,
+              })
+            }
+          />
+        </div>
+      ))}
+    </div>
+  );
+}
+
+interface CgroupLimitsFieldsProps {
+  limits: CgroupLimits;
+  onChange: (limits: CgroupLimits) => void;
+}
+
+const CPU_PERIOD_US = 100000;
+
+// Select 的值不能为空字符串, 用于表示不使用命名环境
+const NO_ENVIRONMENT = "__none__";
+
+const ioMaxKeys = ["rbps", "wbps", "riops", "wiops"] as const;
+
+// io.max 的文本格式, 每行一个设备, 例如 "8:0 rbps=1048576 wbps=max"
+const ioMaxToText = (ioMax?: IoMax[]) =>
+  (ioMax ?? [])
+    .map((io) => [io.device, ...ioMaxKeys.map((k) => `${k}=${io[k] ?? "max"}`)].join(" "))
+    .join("\n");
+
+const textToIoMax = (text: string): IoMax[] =>
+  text
+    .split("\n")
+    .map((line) => line.trim().split(/\s+/))
+    .filter(([device]) => device)
+    .map(([device, ...pairs]) => {
+      const io: IoMax = { device };
+      for (const pair of pairs) {
+        const [key, value] = pair.split("=");
+        if ((ioMaxKeys as readonly string[]).includes(key) && value && value !== "max") {
+          io[key as (typeof ioMaxKeys)[number]] = Number(value);
+        }
+      }
+      return io;
+    });
+
+// cgroup 资源限制, 留空表示不限制; 内存以 MiB 为单位, CPU 以占用单个核心的百分比编辑
+function CgroupLimitsFields({ limits, onChange }: CgroupLimitsFieldsProps) {
+  const { t } = useTranslation();
+  const [ioText, setIoText] = useState(ioMaxToText(limits.io_max));
+
+  return (
+    <div className="space-y-2">
+      <div className="grid grid-cols-3 gap-2">
+        <div className="space-y-1">
+          <Label htmlFor="cgroup-memory" className="text-xs">{t("form.cgroupMemoryMax")}</Label>
+          <Input
+            id="cgroup-memory"
+            type="number"
+            min="1"
+            placeholder={t("form.limitUnlimited")}
+            value={limits.memory_max == null ? "" : Math.round(limits.memory_max / MIB)}
+            onChange={(e) =>
+              onChange({
+                ...limits,
+                memory_max: e.target.value === "" ? null : Number(e.target.value) * MIB,
+              })
+            }
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="cgroup-cpu" className="text-xs">{t("form.cgroupCpuMax")}</Label>
+          <Input
+            id="cgroup-cpu"
+            type="number"
+            min="1"
+            placeholder={t("form.limitUnlimited")}
+            value={
+              limits.cpu_max == null
+                ? ""
+                : Math.round((limits.cpu_max.quota_us / limits.cpu_max.period_us) * 100)
+            }
+            onChange={(e) =>
+              onChange({
+                ...limits,
+                cpu_max:
+                  e.target.value === ""
+                    ? null
+                    : {
+                        quota_us: Math.round((Number(e.target.value) / 100) * CPU_PERIOD_US),
+                        period_us: CPU_PERIOD_US,
+                      },
+              })
+            }
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="cgroup-pids" className="text-xs">{t("form.cgroupPidsMax")}</Label>
+          <Input
+            id="cgroup-pids"
+            type="number"
+            min="1"
+            placeholder={t("form.limitUnlimited")}
+            value={limits.pids_max ?? ""}
+            onChange={(e) =>
+              onChange({
+                ...limits,
+                pids_max: e.target.value === "" ? null : Number(e.target.value),
+              })
+            }
+          />
+        </div>
+      </div>
+      <div className="space-y-1">
+        <Label htmlFor="cgroup-io" className="text-xs">{t("form.cgroupIoMax")}</Label>
+        <textarea
+          id="cgroup-io"
+          rows={2}
+          className="flex w-full rounded-md border border-slate-200 bg-white px-3 py-2 font-mono text-sm ring-offset-white placeholder:text-slate-500 focus-visible:outline-none focus-visible:ring-2 focus-visible:ring-slate-950 focus-visible:ring-offset-2"
+          placeholder="8:0 rbps=1048576 wbps=max"
+          value={ioText}
+          onChange={(e) => {
+            setIoText(e.target.value);
+            onChange({ ...limits, io_max: textToIoMax(e.target.value) });
+          }}
+        />
+      </div>
+    </div>
+  );
+}
+
+// 逗号分隔的整数列表, 忽略无效的输入
+const parseNumbers = (text: string) =>
+  text
+    .split(",")
+    .map((c) => Number.parseInt(c.trim(), 10))
+    .filter((c) => !Number.isNaN(c));
+
+interface RestartPolicyFieldsProps {
+  policy: RestartPolicy;
+  onChange: (policy: RestartPolicy) => void;
+}
+
+// 重启策略, 空的输入使用默认值
+function RestartPolicyFields({ policy, onChange }: RestartPolicyFieldsProps) {
+  const { t } = useTranslation();
+  const [codesText, setCodesText] = useState((policy.restart_on ?? []).join(", "));
+  const optional = (value: string) => (value === "" ? undefined : Number(value));
+
+  return (
+    <div className="space-y-2">
+      <div className="grid grid-cols-2 gap-2">
+        <div className="space-y-1">
+          <Label htmlFor="restart-initial" className="text-xs">{t("form.restartInitialDelay")}</Label>
+          <Input
+            id="restart-initial"
+            type="number"
+            min="0"
+            placeholder="1000"
+            value={policy.initial_delay_ms ?? ""}
+            onChange={(e) => onChange({ ...policy, initial_delay_ms: optional(e.target.value) })}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="restart-max-delay" className="text-xs">{t("form.restartMaxDelay")}</Label>
+          <Input
+            id="restart-max-delay"
+            type="number"
+            min="0"
+            placeholder="60000"
+            value={policy.max_delay_ms ?? ""}
+            onChange={(e) => onChange({ ...policy, max_delay_ms: optional(e.target.value) })}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="restart-multiplier" className="text-xs">{t("form.restartMultiplier")}</Label>
+          <Input
+            id="restart-multiplier"
+            type="number"
+            min="1"
+            step="0.1"
+            placeholder="2"
+            value={policy.multiplier ?? ""}
+            onChange={(e) => onChange({ ...policy, multiplier: optional(e.target.value) })}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="restart-max-attempts" className="text-xs">{t("form.restartMaxAttempts")}</Label>
+          <Input
+            id="restart-max-attempts"
+            type="number"
+            min="1"
+            placeholder={t("form.unlimited")}
+            value={policy.max_attempts ?? ""}
+            onChange={(e) =>
+              onChange({ ...policy, max_attempts: e.target.value === "" ? null : Number(e.target.value) })
+            }
+          />
+        </div>
+      </div>
+      <div className="space-y-1">
+        <Label htmlFor="restart-on" className="text-xs">{t("form.restartOn")}</Label>
+        <Input
+          id="restart-on"
+          placeholder="1, 75"
+          value={codesText}
+          onChange={(e) => {
+            setCodesText(e.target.value);
+            onChange({
+              ...policy,
+              restart_on: parseNumbers(e.target.value),
+            });
+          }}
+        />
+      </div>
+    </div>
+  );
+}
+
+interface SuccessCriteriaFieldsProps {
+  criteria: SuccessCriteria;
+  onChange: (criteria: SuccessCriteria) => void;
+}
+
+// 成功条件, 退出码和信号以逗号分隔
+function SuccessCriteriaFields({ criteria, onChange }: SuccessCriteriaFieldsProps) {
+  const { t } = useTranslation();
+  const [codesText, setCodesText] = useState((criteria.exit_codes ?? [0]).join(", "));
+  const [signalsText, setSignalsText] = useState((criteria.signals ?? []).join(", "));
+
+  return (
+    <div className="space-y-2">
+      <div className="grid grid-cols-2 gap-2">
+        <div className="space-y-1">
+          <Label htmlFor="success-codes" className="text-xs">{t("form.successExitCodes")}</Label>
+          <Input
+            id="success-codes"
+            placeholder="0, 24"
+            value={codesText}
+            onChange={(e) => {
+              setCodesText(e.target.value);
+              onChange({ ...criteria, exit_codes: parseNumbers(e.target.value) });
+            }}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="success-signals" className="text-xs">{t("form.successSignals")}</Label>
+          <Input
+            id="success-signals"
+            placeholder="15"
+            value={signalsText}
+            onChange={(e) => {
+              setSignalsText(e.target.value);
+              onChange({ ...criteria, signals: parseNumbers(e.target.value) });
+            }}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="failure-pattern" className="text-xs">{t("form.failurePattern")}</Label>
+          <Input
+            id="failure-pattern"
+            className="font-mono"
+            placeholder="^ERROR"
+            value={criteria.failure_pattern ?? ""}
+            onChange={(e) => onChange({ ...criteria, failure_pattern: e.target.value || null })}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="success-pattern" className="text-xs">{t("form.successPattern")}</Label>
+          <Input
+            id="success-pattern"
+            className="font-mono"
+            placeholder="nothing to do"
+            value={criteria.success_pattern ?? ""}
+            onChange={(e) => onChange({ ...criteria, success_pattern: e.target.value || null })}
+          />
+        </div>
+      </div>
+    </div>
+  );
+}
+
+interface HealthCheckFieldsProps {
+  check: HealthCheck;
+  onChange: (check: HealthCheck) => void;
+}
+
+const defaultProbe = (tag: Probe["tag"]): Probe => {
+  switch (tag) {
+    case "Tcp":
+      return { tag, content: { port: 8080 } };
+    case "Http":
+      return { tag, content: { port: 8080, path: "/health", status: 200 } };
+    case "Command":
+      return { tag, content: { program: "", args: [] } };
+  }
+};
+
+// 健康检查的方式和时间, 空的输入使用默认值
+function HealthCheckFields({ check, onChange }: HealthCheckFieldsProps) {
+  const { t } = useTranslation();
+  const [argsText, setArgsText] = useState(
+    check.probe.tag === "Command" ? check.probe.content.args.join("\n") : ""
+  );
+  const optional = (value: string) => (value === "" ? undefined : Number(value));
+  const probe = check.probe;
+
+  return (
+    <div className="space-y-2">
+      <div className="flex gap-2">
+        <Select
+          value={probe.tag}
+          onValueChange={(tag) => onChange({ ...check, probe: defaultProbe(tag as Probe["tag"]) })}
+        >
+          <SelectTrigger id="health-probe" className="w-32">
+            <SelectValue />
+          </SelectTrigger>
+          <SelectContent>
+            <SelectItem value="Tcp">TCP</SelectItem>
+            <SelectItem value="Http">HTTP</SelectItem>
+            <SelectItem value="Command">{t("form.healthCommand")}</SelectItem>
+          </SelectContent>
+        </Select>
+        {(probe.tag === "Tcp" || probe.tag === "Http") && (
+          <Input
+            id="health-port"
+            type="number"
+            min="1"
+            max="65535"
+            className="w-24"
+            title={t("form.healthPort")}
+            value={probe.content.port}
+            onChange={(e) =>
+              onChange({
+                ...check,
+                probe: { ...probe, content: { ...probe.content, port: Number(e.target.value) } } as Probe,
+              })
+            }
+          />
+        )}
+        {probe.tag === "Http" && (
+          <>
+            <Input
+              id="health-path"
+              className="font-mono"
+              placeholder="/health"
+              value={probe.content.path}
+              onChange={(e) =>
+                onChange({ ...check, probe: { tag: "Http", content: { ...probe.content, path: e.target.value } } })
+              }
+            />
+            <Input
+              id="health-status"
+              type="number"
+              className="w-20"
+              title={t("form.healthStatus")}
+              value={probe.content.status}
+              onChange={(e) =>
+                onChange({
+                  ...check,
+                  probe: { tag: "Http", content: { ...probe.content, status: Number(e.target.value) } },
+                })
+              }
+            />
+          </>
+        )}
+        {probe.tag === "Command" && (
+          <Input
+            id="health-program"
+            className="font-mono"
+            placeholder="/usr/bin/curl"
+            value={probe.content.program}
+            onChange={(e) =>
+              onChange({ ...check, probe: { tag: "Command", content: { ...probe.content, program: e.target.value } } })
+            }
+          />
+        )}
+      </div>
+      {probe.tag === "Command" && (
+        <textarea
+          id="health-args"
+          rows={2}
+          className="flex w-full rounded-md border border-slate-200 bg-white px-3 py-2 font-mono text-sm ring-offset-white placeholder:text-slate-500 focus-visible:outline-none focus-visible:ring-2 focus-visible:ring-slate-950 focus-visible:ring-offset-2"
+          placeholder={t("form.healthArgsPlaceholder")}
+          value={argsText}
+          onChange={(e) => {
+            setArgsText(e.target.value);
+            onChange({
+              ...check,
+              probe: {
+                tag: "Command",
+                content: { ...probe.content, args: e.target.value.split("\n").filter((a) => a !== "") },
+              },
+            });
+          }}
+        />
+      )}
+      <div className="grid grid-cols-4 gap-2">
+        <div className="space-y-1">
+          <Label htmlFor="health-initial" className="text-xs">{t("form.healthInitialDelay")}</Label>
+          <Input
+            id="health-initial"
+            type="number"
+            min="0"
+            placeholder="5000"
+            value={check.initial_delay_ms ?? ""}
+            onChange={(e) => onChange({ ...check, initial_delay_ms: optional(e.target.value) })}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="health-interval" className="text-xs">{t("form.healthInterval")}</Label>
+          <Input
+            id="health-interval"
+            type="number"
+            min="0"
+            placeholder="10000"
+            value={check.interval_ms ?? ""}
+            onChange={(e) => onChange({ ...check, interval_ms: optional(e.target.value) })}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="health-timeout" className="text-xs">{t("form.healthTimeout")}</Label>
+          <Input
+            id="health-timeout"
+            type="number"
+            min="0"
+            placeholder="3000"
+            value={check.timeout_ms ?? ""}
+            onChange={(e) => onChange({ ...check, timeout_ms: optional(e.target.value) })}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="health-threshold" className="text-xs">{t("form.healthThreshold")}</Label>
+          <Input
+            id="health-threshold"
+            type="number"
+            min="1"
+            placeholder="3"
+            value={check.failure_threshold ?? ""}
+            onChange={(e) => onChange({ ...check, failure_threshold: optional(e.target.value) })}
+          />
+        </div>
+      </div>
+    </div>
+  );
+}
+
+interface HookFieldsProps {
+  name: keyof Hooks;
+  hook?: Hook | null;
+  onChange: (hook: Hook | null) => void;
+}
+
+// 单个 hook 的程序和参数, 程序为空时删除 hook
+function HookFields({ name, hook, onChange }: HookFieldsProps) {
+  const { t } = useTranslation();
+  const [argsText, setArgsText] = useState((hook?.args ?? []).join("\n"));
+
+  return (
+    <div className="space-y-1">
+      <Label htmlFor={`hook-${name}`} className="text-xs">{t(`hooks.${name}`)}</Label>
+      <Input
+        id={`hook-${name}`}
+        className="font-mono"
+        placeholder={t("hooks.programPlaceholder")}
+        value={hook?.program ?? ""}
+        onChange={(e) =>
+          onChange(e.target.value === "" ? null : { ...hook, program: e.target.value })
+        }
+      />
+      {hook && (
+        <textarea
+          id={`hook-${name}-args`}
+          rows={2}
+          className="flex w-full rounded-md border border-slate-200 bg-white px-3 py-2 font-mono text-sm ring-offset-white placeholder:text-slate-500 focus-visible:outline-none focus-visible:ring-2 focus-visible:ring-slate-950 focus-visible:ring-offset-2"
+          placeholder={t("hooks.argsPlaceholder")}
+          value={argsText}
+          onChange={(e) => {
+            setArgsText(e.target.value);
+            onChange({ ...hook, args: e.target.value.split("\n").filter((a) => a !== "") });
+          }}
+        />
+      )}
+    </div>
+  );
+}
+
+interface SandboxFieldsProps {
+  sandbox: SandboxOptions;
+  onChange: (sandbox: SandboxOptions) => void;
+}
+
+// 沙箱中可写的路径, 每行一个
+function SandboxFields({ sandbox, onChange }: SandboxFieldsProps) {
+  const { t } = useTranslation();
+  const [pathsText, setPathsText] = useState(sandbox.writable_paths.join("\n"));
+
+  return (
+    <div className="space-y-1">
+      <Label htmlFor="sandbox-writable" className="text-xs">{t("form.sandboxWritablePaths")}</Label>
+      <textarea
+        id="sandbox-writable"
+        rows={2}
+        className="flex w-full rounded-md border border-slate-200 bg-white px-3 py-2 font-mono text-sm ring-offset-white placeholder:text-slate-500 focus-visible:outline-none focus-visible:ring-2 focus-visible:ring-slate-950 focus-visible:ring-offset-2"
+        placeholder="/var/lib/scraper"
+        value={pathsText}
+        onChange={(e) => {
+          setPathsText(e.target.value);
+          onChange({
+            writable_paths: e.target.value
+              .split("\n")
+              .map((p) => p.trim())
+              .filter((p) => p !== ""),
+          });
+        }}
+      />
+    </div>
+  );
+}
+
+interface IdentityFieldsProps {
+  identity: Identity;
+  onChange: (identity: Identity) => void;
+}
+
+// 运行任务的用户, 组, 附加组和 umask (八进制)
+function IdentityFields({ identity, onChange }: IdentityFieldsProps) {
+  const { t } = useTranslation();
+  const [groupsText, setGroupsText] = useState((identity.groups ?? []).join(", "));
+  const [umaskText, setUmaskText] = useState(
+    identity.umask == null ? "" : identity.umask.toString(8).padStart(3, "0"),
+  );
+  const text = (value: string) => (value.trim() === "" ? null : value.trim());
+
+  return (
+    <div className="space-y-2">
+      <div className="grid grid-cols-3 gap-2">
+        <div className="space-y-1">
+          <Label htmlFor="identity-user" className="text-xs">{t("form.runAsUser")}</Label>
+          <Input
+            id="identity-user"
+            placeholder={t("form.inherit")}
+            value={identity.user ?? ""}
+            onChange={(e) => onChange({ ...identity, user: text(e.target.value) })}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="identity-group" className="text-xs">{t("form.runAsGroup")}</Label>
+          <Input
+            id="identity-group"
+            placeholder={t("form.inherit")}
+            value={identity.group ?? ""}
+            onChange={(e) => onChange({ ...identity, group: text(e.target.value) })}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="identity-umask" className="text-xs">{t("form.umask")}</Label>
+          <Input
+            id="identity-umask"
+            placeholder="022"
+            value={umaskText}
+            onChange={(e) => {
+              setUmaskText(e.target.value);
+              const umask = Number.parseInt(e.target.value, 8);
+              onChange({ ...identity, umask: Number.isNaN(umask) ? null : umask & 0o777 });
+            }}
+          />
+        </div>
+      </div>
+      <div className="space-y-1">
+        <Label htmlFor="identity-groups" className="text-xs">{t("form.supplementaryGroups")}</Label>
+        <Input
+          id="identity-groups"
+          placeholder="docker, wheel"
+          value={groupsText}
+          onChange={(e) => {
+            setGroupsText(e.target.value);
+            onChange({
+              ...identity,
+              groups: e.target.value
+                .split(",")
+                .map((g) => g.trim())
+                .filter((g) => g !== ""),
+            });
+          }}
+        />
+      </div>
+    </div>
+  );
+}
+
+interface PriorityFieldsProps {
+  priority: Priority;
+  onChange: (priority: Priority) => void;
+}
+
+// CPU 列表的文本格式, 与 taskset 相同, 例如 "0,2,4-7"
+const cpusToText = (cpus?: number[]) => {
+  const sorted = [...(cpus ?? [])].sort((a, b) => a - b);
+  const ranges: string[] = [];
+  for (let i = 0; i < sorted.length; ) {
+    let j = i;
+    while (j + 1 < sorted.length && sorted[j + 1] === sorted[j] + 1) j++;
+    ranges.push(i === j ? `${sorted[i]}` : `${sorted[i]}-${sorted[j]}`);
+    i = j + 1;
+  }
+  return ranges.join(",");
+};
+
+const textToCpus = (text: string): number[] => {
+  const cpus = new Set<number>();
+  for (const part of text.split(",")) {
+    const [start, end] = part.trim().split("-").map((n) => Number.parseInt(n, 10));
+    if (Number.isNaN(start)) continue;
+    for (let cpu = start; cpu <= (Number.isNaN(end ?? NaN) ? start : end); cpu++) {
+      cpus.add(cpu);
+    }
+  }
+  return [...cpus];
+};
+
+// nice 值, IO 调度类别和 CPU 亲和性
+function PriorityFields({ priority, onChange }: PriorityFieldsProps) {
+  const { t } = useTranslation();
+  const [affinityText, setAffinityText] = useState(cpusToText(priority.cpu_affinity));
+  const ioClass = priority.io_class?.tag ?? "Inherit";
+
+  return (
+    <div className="space-y-2">
+      <div className="grid grid-cols-3 gap-2">
+        <div className="space-y-1">
+          <Label htmlFor="priority-nice" className="text-xs">{t("form.nice")}</Label>
+          <Input
+            id="priority-nice"
+            type="number"
+            min="-20"
+            max="19"
+            placeholder={t("form.inherit")}
+            value={priority.nice ?? ""}
+            onChange={(e) =>
+              onChange({
+                ...priority,
+                nice: e.target.value === "" ? null : Number(e.target.value),
+              })
+            }
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="priority-io-class" className="text-xs">{t("form.ioClass")}</Label>
+          <Select
+            value={ioClass}
+            onValueChange={(tag) =>
+              onChange({
+                ...priority,
+                io_class:
+                  tag === "Inherit"
+                    ? null
+                    : tag === "Idle"
+                      ? { tag: "Idle" }
+                      : { tag: tag as "RealTime" | "BestEffort", content: 4 },
+              })
+            }
+          >
+            <SelectTrigger id="priority-io-class">
+              <SelectValue />
+            </SelectTrigger>
+            <SelectContent>
+              <SelectItem value="Inherit">{t("form.inherit")}</SelectItem>
+              <SelectItem value="RealTime">{t("form.ioClassRealTime")}</SelectItem>
+              <SelectItem value="BestEffort">{t("form.ioClassBestEffort")}</SelectItem>
+              <SelectItem value="Idle">{t("form.ioClassIdle")}</SelectItem>
+            </SelectContent>
+          </Select>
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="priority-io-level" className="text-xs">{t("form.ioLevel")}</Label>
+          <Input
+            id="priority-io-level"
+            type="number"
+            min="0"
+            max="7"
+            disabled={!priority.io_class || priority.io_class.tag === "Idle"}
+            value={priority.io_class && "content" in priority.io_class ? priority.io_class.content : ""}
+            onChange={(e) =>
+              priority.io_class &&
+              priority.io_class.tag !== "Idle" &&
+              onChange({
+                ...priority,
+                io_class: { tag: priority.io_class.tag, content: Number(e.target.value) },
+              })
+            }
+          />
+        </div>
+      </div>
+      <div className="space-y-1">
+        <Label htmlFor="priority-affinity" className="text-xs">{t("form.cpuAffinity")}</Label>
+        <Input
+          id="priority-affinity"
+          placeholder="4-7"
+          value={affinityText}
+          onChange={(e) => {
+            setAffinityText(e.target.value);
+            onChange({ ...priority, cpu_affinity: textToCpus(e.target.value) });
+          }}
+        />
+      </div>
+    </div>
+  );
+}
+
 export function TaskEditDialog({
   open,
   task,
@@ -75,6 +963,9 @@ export function TaskEditDialog({
     enabled: true,
     no_console: false,
     env_vars: {},
+    stdout_options: defaultOutputOptions(),
+    stderr_options: defaultOutputOptions(),
+    merge_stderr: false,
   });
 
   const [triggerType, setTriggerType] = useState<TriggerType>("Manual");
@@ -84,6 +975,21 @@ export function TaskEditDialog({
   const [browsingWorkingDir, setBrowsingWorkingDir] = useState(false);
   const [isNameAuto, setIsNameAuto] = useState(true);
   const [envVarsList, setEnvVarsList] = useState<Array<{ key: string; value: string }>>([]);
+  const [environments, setEnvironments] = useState<Environment[]>([]);
+
+  // 管理器退出之后继续运行的任务的输出直接写入文件
+  const pumpsOutput =
+    (!!formData.stdout && needsPump(formData.stdout_options)) ||
+    (!!formData.stderr && !formData.merge_stderr && needsPump(formData.stderr_options));
+
+  useEffect(() => {
+    if (open) {
+      environmentApi
+        .listEnvironments()
+        .then(setEnvironments)
+        .catch((err) => console.error("Failed to load environments:", err));
+    }
+  }, [open]);
 
   useEffect(() => {
     if (task) {
@@ -91,6 +997,9 @@ export function TaskEditDialog({
         ...task,
         working_dir: task.working_dir || "",
         env_vars: task.env_vars || {},
+        stdout_options: task.stdout_options || defaultOutputOptions(),
+        stderr_options: task.stderr_options || defaultOutputOptions(),
+        merge_stderr: task.merge_stderr || false,
       });
       setEnvVarsList(envVarsToList(task.env_vars));
       setIsNameAuto(false);
@@ -112,6 +1021,9 @@ export function TaskEditDialog({
         no_console: false,
         enabled: true,
         env_vars: {},
+        stdout_options: defaultOutputOptions(),
+        stderr_options: defaultOutputOptions(),
+        merge_stderr: false,
       });
       setEnvVarsList([]);
       setTriggerType("Manual");
@@ -386,6 +1298,38 @@ export function TaskEditDialog({
             )}
           </div>
 
+          {/* Named Environment */}
+          <div className="space-y-2">
+            <Label htmlFor="environment">{t("form.environment")}</Label>
+            <Select
+              value={formData.environment ?? NO_ENVIRONMENT}
+              onValueChange={(value) =>
+                setFormData((prev) => ({
+                  ...prev,
+                  environment: value === NO_ENVIRONMENT ? null : value,
+                }))
+              }
+            >
+              <SelectTrigger id="environment">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value={NO_ENVIRONMENT}>{t("form.environmentNone")}</SelectItem>
+                {environments.map((env) => (
+                  <SelectItem key={env.id} value={env.name}>
+                    {env.name}
+                  </SelectItem>
+                ))}
+                {/* 引用的环境已经不存在时仍然显示, 启动时会失败 */}
+                {formData.environment &&
+                  !environments.some((env) => env.name === formData.environment) && (
+                    <SelectItem value={formData.environment}>{formData.environment}</SelectItem>
+                  )}
+              </SelectContent>
+            </Select>
+            <p className="text-xs text-slate-500">{t("form.environmentDesc")}</p>
+          </div>
+
           {/* Environment Variables */}
           <div className="space-y-3">
             <div className="flex items-center justify-between">
@@ -527,6 +1471,239 @@ export function TaskEditDialog({
             </div>
           )}
 
+          {(triggerType === "KeepAlive" || triggerType === "UntilSucceed") && (
+            <div className="space-y-2">
+              <Label>{t("form.restartPolicy")}</Label>
+              <RestartPolicyFields
+                key={`${task?.id ?? "new"}-${open}`}
+                policy={formData.restart || {}}
+                onChange={(restart) => setFormData((prev) => ({ ...prev, restart }))}
+              />
+              <p className="text-xs text-slate-500">{t("form.restartPolicyDesc")}</p>
+            </div>
+          )}
+
+          <div className="space-y-2">
+            <Label>{t("form.successCriteria")}</Label>
+            <SuccessCriteriaFields
+              key={`${task?.id ?? "new"}-${open}`}
+              criteria={formData.success || {}}
+              onChange={(success) => setFormData((prev) => ({ ...prev, success }))}
+            />
+            <p className="text-xs text-slate-500">{t("form.successCriteriaDesc")}</p>
+          </div>
+
+          {triggerType === "KeepAlive" && (
+            <div className="space-y-2">
+              <div className="flex items-center justify-between">
+                <Label htmlFor="health-check">{t("form.healthCheck")}</Label>
+                <Switch
+                  id="health-check"
+                  checked={!!formData.health_check}
+                  onCheckedChange={(checked) =>
+                    setFormData((prev) => ({
+                      ...prev,
+                      health_check: checked ? { probe: defaultProbe("Tcp") } : null,
+                    }))
+                  }
+                />
+              </div>
+              {formData.health_check && (
+                <HealthCheckFields
+                  key={`${task?.id ?? "new"}-${open}`}
+                  check={formData.health_check}
+                  onChange={(health_check) => setFormData((prev) => ({ ...prev, health_check }))}
+                />
+              )}
+              <p className="text-xs text-slate-500">{t("form.healthCheckDesc")}</p>
+            </div>
+          )}
+
+          <div className="space-y-2">
+            <div className="flex items-center justify-between">
+              <Label htmlFor="sd-notify">{t("form.sdNotify")}</Label>
+              <Switch
+                id="sd-notify"
+                checked={!!formData.notify}
+                onCheckedChange={(checked) =>
+                  setFormData((prev) => ({ ...prev, notify: checked ? {} : null }))
+                }
+              />
+            </div>
+            {formData.notify && (
+              <div className="space-y-1">
+                <Label htmlFor="watchdog" className="text-xs">{t("form.watchdog")}</Label>
+                <Input
+                  id="watchdog"
+                  type="number"
+                  min="1"
+                  placeholder={t("form.watchdogDisabled")}
+                  value={formData.notify.watchdog_ms ?? ""}
+                  onChange={(e) =>
+                    setFormData((prev) => ({
+                      ...prev,
+                      notify: { watchdog_ms: e.target.value === "" ? null : Number(e.target.value) },
+                    }))
+                  }
+                />
+              </div>
+            )}
+            <p className="text-xs text-slate-500">{t("form.sdNotifyDesc")}</p>
+          </div>
+
+          {triggerType === "KeepAlive" && (
+            <div className="space-y-2">
+              <div className="flex items-center justify-between">
+                <Label htmlFor="custom-suspension">{t("form.customSuspension")}</Label>
+                <Switch
+                  id="custom-suspension"
+                  checked={!!formData.suspension}
+                  onCheckedChange={(checked) =>
+                    setFormData((prev) => ({ ...prev, suspension: checked ? {} : null }))
+                  }
+                />
+              </div>
+              {formData.suspension && (
+                <SuspensionPolicyFields
+                  idPrefix="task-suspension"
+                  policy={formData.suspension}
+                  onChange={(suspension) => setFormData((prev) => ({ ...prev, suspension }))}
+                />
+              )}
+              <p className="text-xs text-slate-500">{t("form.suspensionDesc")}</p>
+            </div>
+          )}
+
+          {/* Hooks */}
+          <div className="space-y-2">
+            <Label>{t("hooks.title")}</Label>
+            {(["pre_start", "post_stop", "on_failure"] as const).map((name) => (
+              <HookFields
+                key={`${name}-${task?.id ?? "new"}-${open}`}
+                name={name}
+                hook={formData.hooks?.[name]}
+                onChange={(hook) =>
+                  setFormData((prev) => ({ ...prev, hooks: { ...prev.hooks, [name]: hook } }))
+                }
+              />
+            ))}
+            <p className="text-xs text-slate-500">{t("hooks.desc")}</p>
+          </div>
+
+          {/* Concurrency Policy */}
+          <div className="space-y-2">
+            <Label htmlFor="concurrency">{t("form.concurrency")}</Label>
+            <div className="flex gap-2">
+              <Select
+                value={formData.concurrency?.tag ?? "Skip"}
+                onValueChange={(tag) =>
+                  setFormData((prev) => ({
+                    ...prev,
+                    concurrency:
+                      tag === "Parallel"
+                        ? { tag: "Parallel", content: 2 }
+                        : ({ tag } as ConcurrencyPolicy),
+                  }))
+                }
+              >
+                <SelectTrigger id="concurrency">
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="Skip">{t("concurrency.skip")}</SelectItem>
+                  <SelectItem value="Queue">{t("concurrency.queue")}</SelectItem>
+                  <SelectItem value="Replace">{t("concurrency.replace")}</SelectItem>
+                  <SelectItem value="Parallel">{t("concurrency.parallel")}</SelectItem>
+                </SelectContent>
+              </Select>
+              {formData.concurrency?.tag === "Parallel" && (
+                <Input
+                  id="concurrency-max"
+                  type="number"
+                  min="1"
+                  className="w-24"
+                  title={t("concurrency.maxInstances")}
+                  value={formData.concurrency.content}
+                  onChange={(e) =>
+                    setFormData((prev) => ({
+                      ...prev,
+                      concurrency: { tag: "Parallel", content: Math.max(1, Number(e.target.value)) },
+                    }))
+                  }
+                />
+              )}
+            </div>
+            <p className="text-xs text-slate-500">
+              {t(`concurrency.${(formData.concurrency?.tag ?? "Skip").toLowerCase()}Desc`)}
+            </p>
+          </div>
+
+          {/* Replicas */}
+          <div className="space-y-2">
+            <Label htmlFor="replicas">{t("form.replicas")}</Label>
+            <Input
+              id="replicas"
+              type="number"
+              min="1"
+              className="w-24"
+              value={formData.replicas ?? 1}
+              onChange={(e) =>
+                setFormData((prev) => ({
+                  ...prev,
+                  replicas: Math.max(1, Math.floor(Number(e.target.value)) || 1),
+                }))
+              }
+            />
+            <p className="text-xs text-slate-500">{t("form.replicasDesc")}</p>
+          </div>
+
+          {/* Progress Fd */}
+          <div className="space-y-2">
+            <Label htmlFor="progress-fd">{t("form.progressFd")}</Label>
+            <Input
+              id="progress-fd"
+              type="number"
+              min="3"
+              className="w-24"
+              placeholder="3"
+              value={formData.progress_fd ?? ""}
+              disabled={formData.on_exit === "Detach"}
+              onChange={(e) =>
+                setFormData((prev) => ({
+                  ...prev,
+                  progress_fd: e.target.value === "" ? null : Math.max(3, Number(e.target.value)),
+                }))
+              }
+            />
+            <p className="text-xs text-slate-500">{t("form.progressFdDesc")}</p>
+          </div>
+
+          {/* On Exit Policy */}
+          <div className="space-y-2">
+            <Label htmlFor="on-exit">{t("form.onExit")}</Label>
+            <Select
+              value={formData.on_exit ?? "Kill"}
+              onValueChange={(value) =>
+                setFormData((prev) => ({ ...prev, on_exit: value as OnExit }))
+              }
+            >
+              <SelectTrigger id="on-exit">
+                <SelectValue />
+              </SelectTrigger>
+              <SelectContent>
+                <SelectItem value="Kill">{t("form.onExitKill")}</SelectItem>
+                {(isLinux || formData.on_exit === "Detach") && (
+                  <SelectItem value="Detach" disabled={!isLinux || !!formData.pty || formData.progress_fd != null || pumpsOutput}>
+                    {t("form.onExitDetach")}
+                  </SelectItem>
+                )}
+              </SelectContent>
+            </Select>
+            <p className="text-xs text-slate-500">
+              {t(formData.on_exit === "Detach" ? "form.onExitDetachDesc" : "form.onExitKillDesc")}
+            </p>
+          </div>
+
           {/* Console Display Option */}
           <div className="space-y-2">
             <div className="flex items-center justify-between">
@@ -545,23 +1722,141 @@ export function TaskEditDialog({
             <p className="text-xs text-slate-500">{t("form.noConsoleDesc")}</p>
           </div>
 
+          {/* Pseudo-terminal Option */}
+          <div className="space-y-2">
+            <div className="flex items-center justify-between">
+              <Label htmlFor="pty">{t("form.pty")}</Label>
+              <Switch
+                id="pty"
+                checked={!!formData.pty}
+                disabled={formData.on_exit === "Detach"}
+                onCheckedChange={(checked) =>
+                  setFormData((prev) => ({
+                    ...prev,
+                    pty: checked ? { rows: 24, cols: 80 } : null,
+                  }))
+                }
+              />
+            </div>
+            <p className="text-xs text-slate-500">{t("form.ptyDesc")}</p>
+            {formData.pty && (
+              <div className="flex gap-2">
+                <Input
+                  id="pty-rows"
+                  type="number"
+                  min="1"
+                  title={t("form.ptyRows")}
+                  value={formData.pty.rows}
+                  onChange={(e) =>
+                    setFormData((prev) => ({
+                      ...prev,
+                      pty: { ...prev.pty!, rows: Number(e.target.value) },
+                    }))
+                  }
+                />
+                <Input
+                  id="pty-cols"
+                  type="number"
+                  min="1"
+                  title={t("form.ptyCols")}
+                  value={formData.pty.cols}
+                  onChange={(e) =>
+                    setFormData((prev) => ({
+                      ...prev,
+                      pty: { ...prev.pty!, cols: Number(e.target.value) },
+                    }))
+                  }
+                />
+              </div>
+            )}
+          </div>
+
+          {/* Sandbox Option */}
+          <div className="space-y-2">
+            <div className="flex items-center justify-between">
+              <Label htmlFor="sandbox">{t("form.sandbox")}</Label>
+              <Switch
+                id="sandbox"
+                checked={!!formData.sandbox}
+                onCheckedChange={(checked) =>
+                  setFormData((prev) => ({
+                    ...prev,
+                    sandbox: checked ? { writable_paths: [] } : null,
+                  }))
+                }
+              />
+            </div>
+            <p className="text-xs text-slate-500">{t("form.sandboxDesc")}</p>
+            {formData.sandbox && (
+              <SandboxFields
+                key={`${task?.id ?? "new"}-${open}`}
+                sandbox={formData.sandbox}
+                onChange={(sandbox) => setFormData((prev) => ({ ...prev, sandbox }))}
+              />
+            )}
+          </div>
+
           {/* File Redirections */}
           <div className="border-t pt-4 space-y-3">
             <p className="font-semibold text-sm">{t("form.fileRedirections")}</p>
 
             <div className="space-y-2">
               <Label htmlFor="stdin">{t("form.standardInput")}</Label>
-              <Input
-                id="stdin"
-                placeholder="/path/to/input/file"
-                value={formData.stdin || ""}
-                onChange={(e) =>
+              <Select
+                value={formData.stdin_source?.tag ?? "File"}
+                onValueChange={(tag) =>
                   setFormData((prev) => ({
                     ...prev,
-                    stdin: e.target.value || undefined,
+                    stdin_source:
+                      tag === "Inline"
+                        ? { tag: "Inline", content: "" }
+                        : { tag: tag as "File" | "Trigger" },
                   }))
                 }
-              />
+              >
+                <SelectTrigger id="stdin-source" title={t("form.stdinSource")}>
+                  <SelectValue />
+                </SelectTrigger>
+                <SelectContent>
+                  <SelectItem value="File">{t("form.stdinFile")}</SelectItem>
+                  <SelectItem value="Inline">{t("form.stdinInline")}</SelectItem>
+                  <SelectItem value="Trigger">{t("form.stdinTrigger")}</SelectItem>
+                </SelectContent>
+              </Select>
+              {(formData.stdin_source?.tag ?? "File") === "File" && (
+                <Input
+                  id="stdin"
+                  placeholder="/path/to/input/file"
+                  value={formData.stdin || ""}
+                  onChange={(e) =>
+                    setFormData((prev) => ({
+                      ...prev,
+                      stdin: e.target.value || undefined,
+                    }))
+                  }
+                />
+              )}
+              {formData.stdin_source?.tag === "Inline" && (
+                <>
+                  <textarea
+                    id="stdin"
+                    rows={4}
+                    className="flex w-full rounded-md border border-slate-200 bg-white px-3 py-2 font-mono text-sm ring-offset-white placeholder:text-slate-500 focus-visible:outline-none focus-visible:ring-2 focus-visible:ring-slate-950 focus-visible:ring-offset-2"
+                    placeholder={t("form.stdinInlinePlaceholder")}
+                    value={formData.stdin_source.content}
+                    onChange={(e) =>
+                      setFormData((prev) => ({
+                        ...prev,
+                        stdin_source: { tag: "Inline", content: e.target.value },
+                      }))
+                    }
+                  />
+                  <p className="text-xs text-slate-500">{t("form.stdinInlineDesc")}</p>
+                </>
+              )}
+              {formData.stdin_source?.tag === "Trigger" && (
+                <p className="text-xs text-slate-500">{t("form.stdinTriggerDesc")}</p>
+              )}
             </div>
 
             <div className="space-y-2">
@@ -577,22 +1872,99 @@ export function TaskEditDialog({
                   }))
                 }
               />
+              {formData.stdout && (
+                <OutputOptionsFields
+                  id="stdout"
+                  options={formData.stdout_options || defaultOutputOptions()}
+                  onChange={(options) =>
+                    setFormData((prev) => ({ ...prev, stdout_options: options }))
+                  }
+                />
+              )}
             </div>
 
             <div className="space-y-2">
-              <Label htmlFor="stderr">{t("form.standardError")}</Label>
-              <Input
-                id="stderr"
-                placeholder="/path/to/error/file"
-                value={formData.stderr || ""}
-                onChange={(e) =>
-                  setFormData((prev) => ({
-                    ...prev,
-                    stderr: e.target.value || undefined,
-                  }))
-                }
-              />
+              <div className="flex items-center justify-between">
+                <Label htmlFor="merge-stderr">{t("form.mergeStderr")}</Label>
+                <Switch
+                  id="merge-stderr"
+                  checked={formData.merge_stderr || false}
+                  onCheckedChange={(checked) =>
+                    setFormData((prev) => ({ ...prev, merge_stderr: checked }))
+                  }
+                />
+              </div>
+              <p className="text-xs text-slate-500">{t("form.mergeStderrDesc")}</p>
             </div>
+
+            {!formData.merge_stderr && (
+              <div className="space-y-2">
+                <Label htmlFor="stderr">{t("form.standardError")}</Label>
+                <Input
+                  id="stderr"
+                  placeholder="/path/to/error/file"
+                  value={formData.stderr || ""}
+                  onChange={(e) =>
+                    setFormData((prev) => ({
+                      ...prev,
+                      stderr: e.target.value || undefined,
+                    }))
+                  }
+                />
+                {formData.stderr && (
+                  <OutputOptionsFields
+                    id="stderr"
+                    options={formData.stderr_options || defaultOutputOptions()}
+                    onChange={(options) =>
+                      setFormData((prev) => ({ ...prev, stderr_options: options }))
+                    }
+                  />
+                )}
+              </div>
+            )}
+          </div>
+
+          {/* Resource Limits */}
+          <div className="border-t pt-4 space-y-3">
+            <p className="font-semibold text-sm">{t("form.resourceLimits")}</p>
+            <p className="text-xs text-slate-500">{t("form.resourceLimitsDesc")}</p>
+            <ResourceLimitsFields
+              limits={formData.limits || {}}
+              onChange={(limits) => setFormData((prev) => ({ ...prev, limits }))}
+            />
+          </div>
+
+          {/* Run As */}
+          <div className="border-t pt-4 space-y-3">
+            <p className="font-semibold text-sm">{t("form.runAs")}</p>
+            <p className="text-xs text-slate-500">{t("form.runAsDesc")}</p>
+            <IdentityFields
+              key={`${task?.id ?? "new"}-${open}`}
+              identity={formData.identity || {}}
+              onChange={(identity) => setFormData((prev) => ({ ...prev, identity }))}
+            />
+          </div>
+
+          {/* Scheduling Priority */}
+          <div className="border-t pt-4 space-y-3">
+            <p className="font-semibold text-sm">{t("form.scheduling")}</p>
+            <p className="text-xs text-slate-500">{t("form.schedulingDesc")}</p>
+            <PriorityFields
+              key={`${task?.id ?? "new"}-${open}`}
+              priority={formData.priority || {}}
+              onChange={(priority) => setFormData((prev) => ({ ...prev, priority }))}
+            />
+          </div>
+
+          {/* cgroup Limits */}
+          <div className="border-t pt-4 space-y-3">
+            <p className="font-semibold text-sm">{t("form.cgroupLimits")}</p>
+            <p className="text-xs text-slate-500">{t("form.cgroupLimitsDesc")}</p>
+            <CgroupLimitsFields
+              key={`${task?.id ?? "new"}-${open}`}
+              limits={formData.cgroup || {}}
+              onChange={(cgroup) => setFormData((prev) => ({ ...prev, cgroup }))}
+            />
           </div>
         </div>
 
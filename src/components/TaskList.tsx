@@ -1,6 +1,6 @@
 import { useState } from "react";
 import { format } from "date-fns";
-import { ChevronRight, GripVertical, Play, Square, Trash2 } from "lucide-react";
+import { ChevronRight, GripVertical, Play, ScrollText, Square, Trash2 } from "lucide-react";
 import { useTranslation } from "react-i18next";
 import {
   DndContext,
@@ -17,7 +17,7 @@ import {
 } from "@dnd-kit/sortable";
 import { CSS } from "@dnd-kit/utilities";
 import type { Task } from "@/types/task";
-import { TaskStatus } from "@/types/task";
+import type { Health, NotifyState, Outcome, Progress, TaskStatus } from "@/types/task";
 import { Button } from "@/components/ui/button";
 import {
   Dialog,
@@ -33,6 +33,7 @@ import { cn } from "@/lib/utils";
 interface TaskListProps {
   tasks: Task[];
   onEdit: (task: Task) => void;
+  onViewOutput: (task: Task) => void;
   onDelete: (id: number) => void;
   onRun: (id: number) => void;
   onStop: (id: number) => void;
@@ -43,14 +44,73 @@ interface TaskListProps {
   taskStatuses?: Record<number, TaskStatus>;
 }
 
+// 使用副本的任务按编号展开每个副本的状态, 否则只有任务本身的状态
+const replicaStatuses = (status?: TaskStatus): TaskStatus[] =>
+  status?.tag === "Replicated" ? status.content.replicas : status ? [status] : [];
+
+const runningInstances = (status?: TaskStatus) =>
+  replicaStatuses(status).reduce(
+    (sum, s) => sum + (s.tag === "Running" ? s.content.instances : 0),
+    0
+  );
+
+const replicasStatus = (status?: TaskStatus) =>
+  status?.tag === "Replicated" ? status.content.replicas : undefined;
+
+const replicaClass: Record<TaskStatus["tag"], string> = {
+  Running: "bg-green-100 text-green-800",
+  Restarting: "bg-amber-100 text-amber-800",
+  Suspended: "bg-red-100 text-red-800",
+  Idle: "bg-slate-100 text-slate-600",
+  Replicated: "bg-slate-100 text-slate-600",
+};
+
+const healthStatus = (status?: TaskStatus) =>
+  status?.tag === "Running" ? status.content.health ?? undefined : undefined;
+
+const suspensionStatus = (status?: TaskStatus) =>
+  status?.tag === "Suspended" ? status.content : undefined;
+
+const notifyStatus = (status?: TaskStatus) =>
+  status?.tag === "Running" ? status.content.notify ?? undefined : undefined;
+
+const progressStatus = (status?: TaskStatus) =>
+  status?.tag === "Running" ? status.content.progress ?? undefined : undefined;
+
+// 进度的文本形式, 例如 "42/100 (42%)", 总数未知时只有已完成的数量
+const formatProgress = (progress: Progress): string | undefined => {
+  if (progress.current == null) return undefined;
+  if (!progress.total) return `${progress.current}`;
+  const percent = Math.min(100, Math.round((progress.current / progress.total) * 100));
+  return `${progress.current}/${progress.total} (${percent}%)`;
+};
+
+const restartingStatus = (status?: TaskStatus) =>
+  status?.tag === "Restarting" ? status.content : undefined;
+
 // ---------- 单个可排序任务行 ----------
 
 interface SortableTaskItemProps {
   task: Task;
   running: boolean;
+  /** 正在运行的实例数量 */
+  instances: number;
   programRunnable: boolean;
   isSuspended: boolean;
+  /** 暂停的原因和自动恢复的时间 */
+  suspension?: { since: string; reason: string; resume_at?: string | null };
+  /** 设置了健康检查时运行中任务的健康状态 */
+  health?: Health;
+  /** 使用 sd_notify 时运行中任务报告的状态 */
+  notify?: NotifyState;
+  /** 等待重启时的重启次数和时间 */
+  restarting?: { attempt: number; next_retry_at: string };
+  /** 使用副本时每个副本的状态 */
+  replicas?: TaskStatus[];
+  /** 运行中任务通过 ::bgm 报告的进度和状态 */
+  progress?: Progress;
   onEdit: (task: Task) => void;
+  onViewOutput: (task: Task) => void;
   onDelete: (id: number | undefined) => void;
   onRun: (id: number | undefined) => void;
   onStop: (id: number) => void;
@@ -60,9 +120,17 @@ interface SortableTaskItemProps {
 function SortableTaskItem({
   task,
   running,
+  instances,
   programRunnable,
   isSuspended,
+  suspension,
+  health,
+  notify,
+  restarting,
+  replicas,
+  progress,
   onEdit,
+  onViewOutput,
   onDelete,
   onRun,
   onStop,
@@ -105,6 +173,48 @@ function SortableTaskItem({
     return "Unknown";
   };
 
+  const getOutcomeLabel = (outcome: Outcome): string => {
+    switch (outcome.tag) {
+      case "Exited": return `${t("task.exit")} ${outcome.content.code}`;
+      case "Signaled":
+        return outcome.content.core_dumped
+          ? t("outcome.signaledCore", { signal: outcome.content.signal })
+          : t("outcome.signaled", { signal: outcome.content.signal });
+      case "Stopped": return t("outcome.stopped");
+      case "TimedOut": return t("outcome.timedOut");
+      case "Unhealthy": return t("outcome.unhealthy");
+      case "FailedToLaunch":
+        switch (outcome.content.kind) {
+          case "NotFound": return t("outcome.notFound");
+          case "PermissionDenied": return t("outcome.permissionDenied");
+          case "BadInterpreter": return t("outcome.badInterpreter");
+          case "Setup": return t("outcome.setupFailed");
+          default: return t("outcome.failedToLaunch");
+        }
+      default: return t("outcome.unknown");
+    }
+  };
+
+  const getReplicaLabel = (status: TaskStatus): string => {
+    switch (status.tag) {
+      case "Running": {
+        const progress = status.content.progress;
+        const text = progress && [formatProgress(progress), progress.status].filter(Boolean).join(" ");
+        return text ? `${t("task.running")}: ${text}` : t("task.running");
+      }
+      case "Restarting":
+        return t("task.restarting", {
+          attempt: status.content.attempt,
+          time: formatLastRun(status.content.next_retry_at),
+        });
+      case "Suspended": return t("task.suspended");
+      default:
+        return status.tag === "Idle" && status.content.last_outcome
+          ? getOutcomeLabel(status.content.last_outcome)
+          : t("task.idle");
+    }
+  };
+
   const formatLastRun = (timestamp?: string): string => {
     if (!timestamp) return "-";
     try {
@@ -115,7 +225,14 @@ function SortableTaskItem({
   };
 
   const tooltip = isSuspended
-    ? t("task.suspended")
+    ? [
+        t("task.suspended"),
+        suspension &&
+          t("task.suspendedSince", { time: formatLastRun(suspension.since), reason: suspension.reason }),
+        suspension?.resume_at && t("task.resumeAt", { time: formatLastRun(suspension.resume_at) }),
+      ]
+        .filter(Boolean)
+        .join("\n")
     : programRunnable
       ? undefined
       : t("task.programNotRunnable");
@@ -143,23 +260,83 @@ function SortableTaskItem({
           </h3>
           {running && (
             <span className="px-2 py-1 text-xs bg-green-100 text-green-800 rounded-full">
-              {t("task.running")}
+              {instances > 1 ? t("task.runningInstances", { count: instances }) : t("task.running")}
+            </span>
+          )}
+          {running && notify && (!notify.ready || notify.stopping) && (
+            <span className="px-2 py-1 text-xs bg-blue-100 text-blue-800 rounded-full">
+              {notify.stopping ? t("task.stopping") : t("task.starting")}
+            </span>
+          )}
+          {running && health?.tag === "Unhealthy" && (
+            <span
+              className="px-2 py-1 text-xs bg-red-100 text-red-800 rounded-full"
+              title={health.content.reason}
+            >
+              {t("task.unhealthy", { count: health.content.failures })}
+            </span>
+          )}
+          {!running && restarting && (
+            <span className="px-2 py-1 text-xs bg-amber-100 text-amber-800 rounded-full">
+              {t("task.restarting", {
+                attempt: restarting.attempt,
+                time: formatLastRun(restarting.next_retry_at),
+              })}
             </span>
           )}
         </div>
+        {replicas && (
+          <div className="flex flex-wrap gap-1 mb-1">
+            {replicas.map((replica, index) => (
+              <span
+                key={index}
+                className={cn("px-1.5 py-0.5 text-xs rounded", replicaClass[replica.tag])}
+                title={getReplicaLabel(replica)}
+              >
+                #{index}
+              </span>
+            ))}
+          </div>
+        )}
         <p className="text-xs text-slate-600 truncate mb-1">{task.program}</p>
+        {running && notify?.status && (
+          <p className="text-xs text-slate-500 italic truncate mb-1">{notify.status}</p>
+        )}
+        {running && progress && (
+          <div className="flex items-center gap-2 mb-1 text-xs text-slate-500">
+            {progress.current != null && !!progress.total && (
+              <div className="h-1.5 w-32 shrink-0 overflow-hidden rounded-full bg-slate-100">
+                <div
+                  className="h-full bg-green-500 transition-all"
+                  style={{
+                    width: `${Math.min(100, (progress.current / progress.total) * 100)}%`,
+                  }}
+                />
+              </div>
+            )}
+            {formatProgress(progress) && <span>{formatProgress(progress)}</span>}
+            {progress.status && <span className="italic truncate">{progress.status}</span>}
+          </div>
+        )}
         <div className="flex flex-wrap gap-2 text-xs text-slate-500">
           <span>{getTriggerLabel()}</span>
-          {(task.last_exit_code != null) && (
+          {(task.last_outcome || task.last_exit_code != null) && (
             <span
               className={cn(
                 "px-1.5 py-0.5 rounded",
-                task.last_exit_code === 0
+                (task.last_succeeded ?? task.last_exit_code === 0)
                   ? "bg-green-50 text-green-700"
                   : "bg-red-50 text-red-700"
               )}
+              title={
+                task.last_outcome?.tag === "FailedToLaunch"
+                  ? task.last_outcome.content.reason
+                  : undefined
+              }
             >
-              {t("task.exit")} {task.last_exit_code}
+              {task.last_outcome
+                ? getOutcomeLabel(task.last_outcome)
+                : `${t("task.exit")} ${task.last_exit_code}`}
             </span>
           )}
           {task.last_run_at && (
@@ -215,6 +392,16 @@ function SortableTaskItem({
           </Button>
         )}
 
+        {/* Output button */}
+        <Button
+          size="sm"
+          variant="ghost"
+          onClick={() => onViewOutput(task)}
+          title={t("button.output")}
+        >
+          <ScrollText className="h-4 w-4" />
+        </Button>
+
         {/* Edit button */}
         <Button
           size="sm"
@@ -245,6 +432,7 @@ function SortableTaskItem({
 export function TaskList({
   tasks,
   onEdit,
+  onViewOutput,
   onDelete,
   onRun,
   onStop,
@@ -311,8 +499,16 @@ export function TaskList({
                 task={task}
                 running={!!isRunning[task.id!]}
                 programRunnable={runnablePrograms[task.id!] !== false}
-                isSuspended={taskStatuses[task.id!] === TaskStatus.Suspended}
+                instances={runningInstances(taskStatuses[task.id!])}
+                isSuspended={taskStatuses[task.id!]?.tag === "Suspended"}
+                suspension={suspensionStatus(taskStatuses[task.id!])}
+                health={healthStatus(taskStatuses[task.id!])}
+                notify={notifyStatus(taskStatuses[task.id!])}
+                restarting={restartingStatus(taskStatuses[task.id!])}
+                replicas={replicasStatus(taskStatuses[task.id!])}
+                progress={progressStatus(taskStatuses[task.id!])}
                 onEdit={onEdit}
+                onViewOutput={onViewOutput}
                 onDelete={handleDeleteRequest}
                 onRun={handleRun}
                 onStop={onStop}
@@ -0,0 +1,100 @@
+import { useEffect, useRef, useState } from "react";
+import { listen } from "@tauri-apps/api/event";
+import { useTranslation } from "react-i18next";
+import type { OutputLine, Task } from "@/types/task";
+import { taskApi } from "@/lib/api";
+import {
+  Dialog,
+  DialogContent,
+  DialogHeader,
+  DialogTitle,
+  DialogDescription,
+} from "@/components/ui/dialog";
+import { cn } from "@/lib/utils";
+
+interface TaskOutputDialogProps {
+  open: boolean;
+  task?: Task;
+  onOpenChange: (open: boolean) => void;
+}
+
+// 打开时先加载最近的输出, 再订阅实时输出
+const INITIAL_LINES = 500;
+// 最多保留的行数, 避免长时间运行的任务占用过多内存
+const MAX_LINES = 5000;
+
+export function TaskOutputDialog({ open, task, onOpenChange }: TaskOutputDialogProps) {
+  const { t } = useTranslation();
+  const [lines, setLines] = useState<Array<Pick<OutputLine, "stream" | "line">>>([]);
+  const bottomRef = useRef<HTMLDivElement>(null);
+
+  useEffect(() => {
+    const id = task?.id;
+    if (!open || id === undefined) return;
+
+    let disposed = false;
+    let unlisten: (() => void) | undefined;
+
+    const start = async () => {
+      try {
+        const tail = await taskApi.tailTaskOutput(id, INITIAL_LINES);
+        if (!disposed) {
+          setLines(tail.split("\n").filter((l) => l.length > 0).map((line) => ({ stream: "Stdout", line })));
+        }
+      } catch {
+        // 任务还没有输出
+        setLines([]);
+      }
+      const stop = await listen<OutputLine>("task-output", (event) => {
+        if (event.payload.task_id !== id) return;
+        setLines((prev) => [...prev, event.payload].slice(-MAX_LINES));
+      });
+      if (disposed) {
+        stop();
+        return;
+      }
+      unlisten = stop;
+      await taskApi.subscribeTaskOutput(id);
+    };
+    start().catch((err) => console.error("Failed to subscribe task output:", err));
+
+    return () => {
+      disposed = true;
+      unlisten?.();
+      taskApi.unsubscribeTaskOutput(id).catch(() => {});
+    };
+  }, [open, task?.id]);
+
+  useEffect(() => {
+    bottomRef.current?.scrollIntoView({ block: "end" });
+  }, [lines]);
+
+  return (
+    <Dialog open={open} onOpenChange={onOpenChange}>
+      <DialogContent className="max-w-4xl max-h-[90vh] flex flex-col">
+        <DialogHeader>
+          <DialogTitle>{t("dialog.outputTitle", { name: task?.name ?? "" })}</DialogTitle>
+          <DialogDescription>{t("dialog.outputDesc")}</DialogDescription>
+        </DialogHeader>
+        <div className="flex-1 min-h-[50vh] overflow-y-auto rounded-md bg-slate-950 p-3 font-mono text-xs">
+          {lines.length > 0 ? (
+            lines.map((l, index) => (
+              <div
+                key={index}
+                className={cn(
+                  "whitespace-pre-wrap break-all",
+                  l.stream === "Stderr" ? "text-red-400" : "text-slate-100"
+                )}
+              >
+                {l.line}
+              </div>
+            ))
+          ) : (
+            <p className="text-slate-500">{t("status.noOutput")}</p>
+          )}
+          <div ref={bottomRef} />
+        </div>
+      </DialogContent>
+    </Dialog>
+  );
+}
@@ -1,6 +1,7 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { Task, TaskStatus } from "../types/task";
+import type { CgroupStats, OutputChunk, Run, Task, TaskStatus } from "../types/task";
 import type { AppConfig } from "../types/config";
+import type { Environment } from "../types/environment";
 
 export const taskApi = {
   async listTasks(): Promise<Task[]> {
@@ -23,8 +24,8 @@ export const taskApi = {
     return invoke("switch_task", { id, enable });
   },
 
-  async manualRunTask(id: number): Promise<void> {
-    return invoke("manually_run_task", { id });
+  async manualRunTask(id: number, payload?: string): Promise<void> {
+    return invoke("manually_run_task", { id, payload: payload ?? null });
   },
 
   async getTaskStatus(id: number): Promise<TaskStatus> {
@@ -46,6 +47,34 @@ export const taskApi = {
   async isProgramRunnable(path: string): Promise<boolean> {
     return invoke("is_program_runnable", { path });
   },
+
+  async listTaskRuns(id: number, limit: number): Promise<Run[]> {
+    return invoke("list_task_runs", { id, limit });
+  },
+
+  async getRunOutput(runId: number, offset: number, limit: number): Promise<OutputChunk> {
+    return invoke("get_run_output", { runId, offset, limit });
+  },
+
+  async getRunRecording(runId: number): Promise<string> {
+    return invoke("get_run_recording", { runId });
+  },
+
+  async getTaskStats(id: number, instance?: number): Promise<CgroupStats | null> {
+    return invoke("get_task_stats", { id, instance: instance ?? null });
+  },
+
+  async tailTaskOutput(id: number, lines: number, instance?: number): Promise<string> {
+    return invoke("tail_task_output", { id, instance: instance ?? null, lines });
+  },
+
+  async subscribeTaskOutput(id: number): Promise<void> {
+    return invoke("subscribe_task_output", { id });
+  },
+
+  async unsubscribeTaskOutput(id: number): Promise<void> {
+    return invoke("unsubscribe_task_output", { id });
+  },
 };
 
 export const appApi = {
@@ -69,5 +98,24 @@ export const configApi = {
 
   async saveConfig(config: AppConfig): Promise<void> {
     return invoke("update_config", { config });
+  },
+
+  // 重新捕获登录 shell 的环境变量, 返回变量的数量
+  async refreshShellEnv(): Promise<number> {
+    return invoke("refresh_shell_env");
+  }
+};
+
+export const environmentApi = {
+  async listEnvironments(): Promise<Environment[]> {
+    return invoke("list_environments");
+  },
+
+  async saveEnvironment(environment: Environment): Promise<number> {
+    return invoke("save_environment", { environment });
+  },
+
+  async removeEnvironment(id: number): Promise<void> {
+    return invoke("remove_environment", { id });
   }
 };
@@ -66,10 +66,10 @@ export function useTaskActions() {
     }
   }, []);
 
-  const manuallyRunTask = useCallback(async (id: number) => {
+  const manuallyRunTask = useCallback(async (id: number, payload?: string) => {
     try {
       setError(null);
-      await taskApi.manualRunTask(id);
+      await taskApi.manualRunTask(id, payload);
     } catch (err) {
       setError(err instanceof Error ? err.message : "Failed to run task");
       throw err;
@@ -4,3 +4,6 @@ import { twMerge } from "tailwind-merge"
 export function cn(...inputs: ClassValue[]) {
   return twMerge(clsx(inputs))
 }
+
+// 管理器退出之后继续运行的任务 (Detach) 只能在 Linux 上重新接管
+export const isLinux = navigator.userAgent.includes("Linux")
@@ -1,3 +1,13 @@
+import type { SuspensionPolicy } from "./task";
+
 export interface AppConfig {
   quiet_launch: boolean;
+  cgroup_root?: string | null;
+  suspension?: SuspensionPolicy;
+  // 捕获登录 shell 的环境变量作为任务环境变量的基础
+  login_shell_env?: boolean;
+  // 在 PATH 之前搜索的目录
+  search_path?: string[];
+  // 扩展名 (不含 ".") 对应的解释器命令
+  interpreters?: Record<string, string[]>;
 }
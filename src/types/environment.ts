@@ -0,0 +1,10 @@
+// 命名环境, 任务通过名称引用
+export interface Environment {
+  id?: number;
+  name: string;
+  env_vars: Record<string, string>;
+  // 按顺序添加到 PATH 前面的目录
+  path_prefixes: string[];
+  // 每次启动任务之前由 /bin/sh 执行的激活脚本
+  activation_script?: string | null;
+}
@@ -6,14 +6,221 @@ export interface Task {
   working_dir?: string;
   args: string[];
   stdin?: string;
+  stdin_source?: StdinSource;
   stdout?: string;
   stderr?: string;
+  stdout_options?: OutputOptions;
+  stderr_options?: OutputOptions;
+  merge_stderr?: boolean;
   trigger: Trigger;
   enabled: boolean;
   no_console?: boolean;
   last_exit_code?: number;
+  // 上一次运行是否满足成功条件
+  last_succeeded?: boolean | null;
+  // 上一次运行的结束方式
+  last_outcome?: Outcome | null;
   last_run_at?: string;
   env_vars?: Record<string, string>;
+  // 引用的命名环境的名称
+  environment?: string | null;
+  pty?: PtyOptions | null;
+  limits?: ResourceLimits;
+  cgroup?: CgroupLimits;
+  priority?: Priority;
+  identity?: Identity;
+  sandbox?: SandboxOptions | null;
+  concurrency?: ConcurrencyPolicy;
+  restart?: RestartPolicy;
+  suspension?: SuspensionPolicy | null;
+  // 当前的 suspension 状态, 保存任务时忽略
+  suspended?: Suspension | null;
+  success?: SuccessCriteria;
+  health_check?: HealthCheck | null;
+  // 通过 NOTIFY_SOCKET 接收 sd_notify 消息
+  notify?: NotifyOptions | null;
+  hooks?: Hooks;
+  on_exit?: OnExit;
+  // 同时运行的副本数量, 大于 1 时参数, 环境变量和路径中的 ${INSTANCE} 展开为副本编号
+  replicas?: number;
+  // 专用的进度报告文件描述符, 为空表示只从输出中读取报告
+  progress_fd?: number | null;
+}
+
+// 管理器退出时如何处理正在运行的进程, Detach 的进程在下一次启动时被接管
+export type OnExit = "Kill" | "Detach";
+
+export interface Hook {
+  program: string;
+  args?: string[];
+  timeout_ms?: number | null;
+}
+
+// 启动之前和退出之后运行的 hook, 通过 BGM_ 开头的环境变量获得运行信息
+export interface Hooks {
+  pre_start?: Hook | null;
+  post_stop?: Hook | null;
+  on_failure?: Hook | null;
+}
+
+export interface NotifyOptions {
+  watchdog_ms?: number | null;
+}
+
+// 子进程通过 sd_notify 报告的状态
+export interface NotifyState {
+  ready: boolean;
+  status?: string | null;
+  stopping: boolean;
+}
+
+// 任务通过 "::bgm progress 42/100" 和 "::bgm status ..." 报告的进度和状态
+export interface Progress {
+  current?: number | null;
+  // 总数, 为空表示未知
+  total?: number | null;
+  status?: string | null;
+}
+
+// task-progress 事件的内容
+export interface ProgressEvent {
+  task_id: number;
+  run_id: number;
+  progress: Progress;
+}
+
+export type Probe =
+  | { tag: "Tcp"; content: { port: number } }
+  | { tag: "Http"; content: { port: number; path: string; status: number } }
+  | { tag: "Command"; content: { program: string; args: string[] } };
+
+// KeepAlive 任务的健康检查, 时间单位为毫秒
+export interface HealthCheck {
+  probe: Probe;
+  initial_delay_ms?: number;
+  interval_ms?: number;
+  timeout_ms?: number;
+  failure_threshold?: number;
+}
+
+export type Health =
+  | { tag: "Starting" }
+  | { tag: "Healthy" }
+  | { tag: "Unhealthy"; content: { failures: number; reason: string } };
+
+// 被管理器结束的运行总是失败, 其次输出匹配优先于退出码和信号, failure_pattern 优先于 success_pattern
+export interface SuccessCriteria {
+  exit_codes?: number[];
+  signals?: number[];
+  failure_pattern?: string | null;
+  success_pattern?: string | null;
+}
+
+export interface SuspensionPolicy {
+  timespan_ms?: number;
+  failures?: number;
+  cooldown_ms?: number | null;
+}
+
+export interface Suspension {
+  since: string;
+  reason: string;
+}
+
+export interface RestartPolicy {
+  initial_delay_ms?: number;
+  multiplier?: number;
+  max_delay_ms?: number;
+  max_attempts?: number | null;
+  restart_on?: number[];
+}
+
+export type ConcurrencyPolicy =
+  | { tag: "Skip" }
+  | { tag: "Queue" }
+  | { tag: "Replace" }
+  | { tag: "Parallel"; content: number };
+
+export interface SandboxOptions {
+  writable_paths: string[];
+}
+
+export interface Identity {
+  user?: string | null;
+  group?: string | null;
+  groups?: string[];
+  umask?: number | null;
+}
+
+export type IoClass =
+  | { tag: "RealTime"; content: number }
+  | { tag: "BestEffort"; content: number }
+  | { tag: "Idle" };
+
+export interface Priority {
+  nice?: number | null;
+  io_class?: IoClass | null;
+  cpu_affinity?: number[];
+}
+
+export interface CgroupLimits {
+  memory_max?: number | null;
+  cpu_max?: { quota_us: number; period_us: number } | null;
+  pids_max?: number | null;
+  io_max?: IoMax[];
+}
+
+export interface IoMax {
+  device: string;
+  rbps?: number | null;
+  wbps?: number | null;
+  riops?: number | null;
+  wiops?: number | null;
+}
+
+export interface CgroupStats {
+  cpu_usage_usec: number;
+  memory_current: number;
+  memory_peak?: number | null;
+  pids_current: number;
+}
+
+export interface ResourceLimits {
+  address_space?: number | null;
+  cpu_seconds?: number | null;
+  open_files?: number | null;
+  processes?: number | null;
+  core_size?: number | null;
+}
+
+export interface PtyOptions {
+  rows: number;
+  cols: number;
+}
+
+export type StdinSource =
+  | { tag: "File" }
+  | { tag: "Inline"; content: string }
+  | { tag: "Trigger" };
+
+export interface Duration {
+  secs: number;
+  nanos: number;
+}
+
+export type RotationPolicy =
+  | { tag: "Size"; content: number }
+  | { tag: "Interval"; content: Duration };
+
+export interface Rotation {
+  policy: RotationPolicy;
+  keep: number;
+}
+
+export interface OutputOptions {
+  append: boolean;
+  timestamp: boolean;
+  rotation?: Rotation | null;
 }
 
 export type Trigger =
@@ -24,6 +231,47 @@ export type Trigger =
   | { tag: "Manual" }
   | { tag: "UntilSucceed" };
 
+export interface Run {
+  id: number;
+  task_id: number;
+  started_at: string;
+  ended_at?: string | null;
+  exit_code?: number | null;
+  // 运行的结束方式, 为空表示仍在运行
+  outcome?: Outcome | null;
+  output?: string | null;
+  // 伪终端会话的 asciicast 录制文件
+  recording?: string | null;
+  limit_violation?: LimitViolation | null;
+  cpu_usage_usec?: number | null;
+  memory_peak?: number | null;
+  // 运行期间被跳过的触发次数
+  skipped_triggers: number;
+  // 按照任务的成功条件判断的结果, 为空表示仍在运行
+  succeeded?: boolean | null;
+  // 进程 ID, 启动失败时为空
+  pid?: number | null;
+  // 运行所属副本的编号, 任务不使用副本时为空
+  instance?: number | null;
+}
+
+export type LimitViolation = "CpuTime";
+
+export interface OutputChunk {
+  content: string;
+  next_offset: number;
+  eof: boolean;
+}
+
+export type OutputStream = "Stdout" | "Stderr";
+
+export interface OutputLine {
+  task_id: number;
+  run_id: number;
+  stream: OutputStream;
+  line: string;
+}
+
 export interface TaskRunStatus {
   id: number;
   is_running: boolean;
@@ -31,8 +279,34 @@ export interface TaskRunStatus {
   last_run_at?: string;
 }
 
-export enum TaskStatus {
-  Suspended = "Suspended",
-  Running = "Running",
-  Idle = "Idle"
-}
+export type TaskStatus =
+  | { tag: "Suspended"; content: { since: string; reason: string; resume_at?: string | null } }
+  | {
+      tag: "Running";
+      content: {
+        instances: number;
+        health?: Health | null;
+        notify?: NotifyState | null;
+        progress?: Progress | null;
+      };
+    }
+  | {
+      tag: "Restarting";
+      content: { attempt: number; next_retry_at: string; last_outcome?: Outcome | null };
+    }
+  | { tag: "Idle"; content: { last_outcome?: Outcome | null } }
+  // 使用副本的任务, 按编号列出每个副本的状态
+  | { tag: "Replicated"; content: { replicas: TaskStatus[] } };
+
+// 一次运行的结束方式
+export type Outcome =
+  | { tag: "Exited"; content: { code: number } }
+  | { tag: "Signaled"; content: { signal: number; core_dumped: boolean } }
+  | { tag: "Stopped" }
+  | { tag: "TimedOut" }
+  | { tag: "Unhealthy" }
+  | { tag: "FailedToLaunch"; content: { kind?: LaunchFailure; reason: string } }
+  | { tag: "Unknown" };
+
+// 启动失败的类型
+export type LaunchFailure = "NotFound" | "PermissionDenied" | "BadInterpreter" | "Setup" | "Other";